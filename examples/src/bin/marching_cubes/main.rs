@@ -4,21 +4,24 @@ use rhyolite::geometry::marched::Metaball;
 use rhyolite::lighting::{AmbientLight, PointLight};
 use rhyolite::renderer::mesh::DrawInfo;
 use rhyolite::transform::Transform;
-use rhyolite::{Rhyolite, TimeState};
+use rhyolite::{RhyoliteBuilder, RhyoliteError, TimeState};
 
 use winit::event::{Event, VirtualKeyCode, WindowEvent};
 use nalgebra_glm::{vec3, Vec3, translate, identity};
 use examples::{CamRotationMode, KeyBinding};
-use rhyolite::geometry::mesh::{MeshObjectParams, BasicVertex};
+use rhyolite::geometry::mesh::{Material, MeshObjectParams, BasicVertex};
 use rhyolite::renderer::Renderer;
+use vulkano::image::SampleCount;
 
 mod marching_cubes;
 mod metaball;
 
 use crate::marching_cubes::MarchingCubesGenerator;
 
-fn main() {
-    let rhyolite = Rhyolite::mesh();
+fn main() -> Result<(), RhyoliteError> {
+    let rhyolite = RhyoliteBuilder::new()
+        .with_msaa(SampleCount::Sample4)
+        .mesh()?;
 
     let camera_transform = Transform::identity();
     let mut camera = Camera::new(camera_transform, 1.2, 0.02, 100.0);
@@ -49,11 +52,11 @@ fn main() {
     let mut camera_pos: Vec3 = vec3(0.0, 0.0, 0.0);
     let mut camera_euler: Vec3 = vec3(0.0, 0.0, 0.0);
     
-    let params = MeshObjectParams {
+    let mut params = MeshObjectParams {
         transform: Transform::identity(),
-        specular_intensity: 1.0,
-        shininess: 64.0,
+        material: Material::new(vec3(1.0, 1.0, 1.0), 1.0, 64.0),
     };
+    rhyolite.renderer.upload_material(&mut params.material)?;
 
     const GRID_WIDTH: u32 = 3;
     const GRID_HEIGHT: u32 = 3;
@@ -82,7 +85,6 @@ fn main() {
                 ..
             } => {
                 camera.configure(renderer.get_window_size());
-                generator.recreate_graphics_pipeline(renderer);
             }
             Event::RedrawEventsCleared => {
                 if keyboard.key_pressed(VirtualKeyCode::Escape) {
@@ -120,23 +122,30 @@ fn main() {
                 );
 
                 // Rendering
-                renderer.start_render_pass(&mut camera);
-
-                let info: DrawInfo<BasicVertex> = DrawInfo::IndirectBlank{ indirect_commands: indirect_buffer.clone() };
-                renderer.draw_lit(
-                    info,
-                    generator.graphics_pipeline().clone(), 
-                    generator.graphics_descriptors(vertex_buffer, renderer, &params)
-                ).unwrap();
-
-                renderer.draw_ambient_light(&mut ambient_light);
-                for point_light in point_lights.iter_mut() {
-                    renderer.draw_point_light(point_light);
+                let frame = (|| -> Result<(), RhyoliteError> {
+                    renderer.start_render_pass(&mut camera)?;
+
+                    let info: DrawInfo<BasicVertex> = DrawInfo::IndirectBlank{ indirect_commands: indirect_buffer.clone() };
+                    renderer.draw_lit(
+                        info,
+                        generator.graphics_pipeline().clone(), 
+                        generator.graphics_descriptors(vertex_buffer, renderer, &params)
+                    )?;
+
+                    renderer.draw_ambient_light(&mut ambient_light)?;
+                    for point_light in point_lights.iter_mut() {
+                        renderer.draw_point_light(point_light)?;
+                    }
+
+                    renderer.end_render_pass()
+                })();
+                if let Err(e) = frame {
+                    eprintln!("Failed to render frame: {}", e);
                 }
-
-                renderer.end_render_pass();
             }
             _ => (),
         }
     });
+
+    Ok(())
 }
@@ -11,7 +11,8 @@ use vulkano::{
         GraphicsPipeline,
         graphics::{
             input_assembly::InputAssemblyState,
-            viewport::{ViewportState, Viewport},
+            multisample::MultisampleState,
+            viewport::ViewportState,
             depth_stencil::DepthStencilState,
             rasterization::{RasterizationState, CullMode}
         }
@@ -87,7 +88,7 @@ impl MarchingCubesGenerator {
             device.clone(),
             cs.entry_point("main").unwrap(),
             &(),
-            None,
+            Some(render_base.pipeline_cache().clone()),
             |_| {}
         ).unwrap();
 
@@ -159,10 +160,6 @@ impl MarchingCubesGenerator {
         &self.graphics_pipeline
     }
 
-    pub fn recreate_graphics_pipeline(&mut self, renderer: &MeshRenderer) {
-        self.graphics_pipeline = create_graphics_pipeline(renderer);
-    }
-
     /// Gets the descriptor data for the compute pipeline
     pub fn compute_descriptors(
         &self,
@@ -192,14 +189,14 @@ impl MarchingCubesGenerator {
         (sbo_set, self.index_descriptors.clone(), metaball_set)
     }
 
-    /// Gets descriptor data for the render pipeline. Doesn't get all of the render descriptors, 
-    /// just the vertex buffer with the correct binding
+    /// Gets descriptor data for the render pipeline. The vertex buffer is bound to set 2, so the
+    /// default material set is moved after it
     pub fn graphics_descriptors(
         &self,
         vertex_buffer: Subbuffer<[[f32; 4]]>,
         renderer: &MeshRenderer,
         params: &MeshObjectParams
-    ) -> (Arc<PersistentDescriptorSet>, Arc<PersistentDescriptorSet>, Arc<PersistentDescriptorSet>) {
+    ) -> (Arc<PersistentDescriptorSet>, Arc<PersistentDescriptorSet>, Arc<PersistentDescriptorSet>, Arc<PersistentDescriptorSet>) {
         let set_layouts = self.graphics_pipeline.layout().set_layouts();
         let layout = set_layouts.get(2).unwrap();
 
@@ -211,9 +208,9 @@ impl MarchingCubesGenerator {
             ]
         ).unwrap();
 
-        let default_descriptors = renderer.default_lit_descriptors(&params);
+        let default_descriptors = renderer.default_lit_descriptors(&params).unwrap();
 
-        (default_descriptors.0, default_descriptors.1, vertex_set)
+        (default_descriptors.0, default_descriptors.1, vertex_set, default_descriptors.2)
     }
 
     pub fn generate_vertices(
@@ -225,6 +222,7 @@ impl MarchingCubesGenerator {
     ) {
         let compute_descriptors = self.compute_descriptors(renderer, vertex_buffer, indirect_buffer, objects);
         renderer.get_base_mut().commands_mut()
+            .unwrap()
             .bind_descriptor_sets(
                 PipelineBindPoint::Compute,
                 self.compute_pipeline.layout().clone(),
@@ -242,7 +240,6 @@ fn create_graphics_pipeline(renderer: &MeshRenderer) -> Arc<GraphicsPipeline> {
     let device = base.get_device();
 
     let albedo_pass = Subpass::from(renderer.get_render_pass().clone(), 0).unwrap();
-    let dimensions = base.get_viewport().dimensions;
 
     let fs = fs::load(device.clone()).unwrap();
     let vs = vs::load(device.clone()).unwrap();
@@ -251,17 +248,17 @@ fn create_graphics_pipeline(renderer: &MeshRenderer) -> Arc<GraphicsPipeline> {
         // .vertex_input_state(vulkano::pipeline::graphics::vertex_input::VertexInputState::)
         .vertex_shader(vs.entry_point("main").unwrap(), ())
         .input_assembly_state(InputAssemblyState::new())
-        .viewport_state(ViewportState::viewport_fixed_scissor_irrelevant([
-            Viewport {
-                origin: [0.0, 0.0],
-                dimensions: [dimensions[0] as f32, dimensions[1] as f32],
-                depth_range: 0.0..1.0,
-            },
-        ]))
+        .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+        // The G-buffer is multisampled when MSAA is enabled
+        .multisample_state(MultisampleState {
+            rasterization_samples: base.samples(),
+            ..Default::default()
+        })
         .fragment_shader(fs.entry_point("main").unwrap(), ())
         .depth_stencil_state(DepthStencilState::simple_depth_test())
         .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
         .render_pass(albedo_pass)
+        .build_with_cache(base.pipeline_cache().clone())
         .build(base.get_device().clone())
         .unwrap()
 }
@@ -295,5 +292,6 @@ fn get_u32_buf(
     )
         .unwrap()
         .into_device_local(num_values as u64, buffer_allocator, &render_base)
+        .unwrap()
 
 }
\ No newline at end of file
@@ -22,7 +22,8 @@ pub fn metaball_set(renderer: &MeshRenderer, objects: &Vec<Metaball>, layout: Ar
     let len = objects.len() as u32;
     let data = unsafe {
         to_partially_init_arr::<MAX_METABALLS, Padded<marched_frag::UMetaball, 12>>(objects)
-    };
+    }
+    .expect("Too many metaballs");
 
     let metaball_buf = renderer.get_subbuffer_allocator().allocate_unsized(MAX_METABALLS as u64).unwrap();
     *metaball_buf.write().unwrap() = marched_frag::UMetaballData { data, len };
@@ -4,10 +4,11 @@ layout(location = 0) in vec3 in_color;
 layout(location = 1) in vec3 in_normal;
 layout(location = 2) in vec4 in_pos;
 
-layout(set = 1, binding = 1) uniform USpecularData {
-    float intensity;
+layout(set = 3, binding = 0) uniform UMaterialData {
+    vec4 albedo;
+    float specular_intensity;
     float shininess;
-} specular;
+} material;
 
 layout(location = 0) out vec4 out_color;
 layout(location = 1) out vec3 out_normal;
@@ -15,8 +16,8 @@ layout(location = 2) out vec4 out_frag_pos;
 layout(location = 3) out vec2 out_specular;
 
 void main() {
-    out_color = vec4(in_color, 1.0);
+    out_color = vec4(in_color * material.albedo.rgb, 1.0);
     out_normal = in_normal;
     out_frag_pos = in_pos;
-    out_specular = vec2(specular.intensity, specular.shininess);
+    out_specular = vec2(material.specular_intensity, material.shininess);
 }
\ No newline at end of file
@@ -1,18 +1,33 @@
 
 use rhyolite::camera::Camera;
-use rhyolite::geometry::mesh::{MeshObject, MeshObjectBuilder};
-use rhyolite::lighting::{AmbientLight, PointLight};
+use rhyolite::geometry::mesh::{Material, MeshObject, MeshObjectBuilder, Texture};
+use rhyolite::lighting::{AmbientLight, DirectionalLight, PointLight};
 use rhyolite::renderer::mesh::DrawInfo;
 use rhyolite::transform::Transform;
-use rhyolite::Rhyolite;
+use rhyolite::{RhyoliteBuilder, RhyoliteError};
 
 use winit::event::{Event, WindowEvent};
 use nalgebra_glm::{identity, rotate_x, rotate_y, rotate_z, vec3};
 use examples::CamRotationMode;
+use rhyolite::renderer::post_process::{Bloom, PostEffect};
+use rhyolite::renderer::tonemap::{TonemapOperator, Tonemapping};
 use rhyolite::renderer::Renderer;
+use vulkano::swapchain::PresentMode;
 
-fn main() {
-    let rhyolite = Rhyolite::mesh();
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut rhyolite = RhyoliteBuilder::new()
+        .with_title("mesh_basic")
+        .with_window_size(800, 600)
+        .with_present_mode(PresentMode::Mailbox)
+        // A dark blue-gray, sRGB (0.05, 0.05, 0.08) converted to linear color
+        .with_clear_color([0.0039, 0.0039, 0.0072, 1.0])
+        .with_tonemapping(Tonemapping {
+            operator: TonemapOperator::Aces,
+            exposure: 1.0,
+        })
+        .mesh()?;
     let renderer = &rhyolite.renderer;
 
     let camera_transform = Transform::identity();
@@ -25,7 +40,7 @@ fn main() {
         &vec3(1.0, 1.0, 1.0),
         &vec3(1.0, 1.0, 1.0),
         (0.3, 4.0),
-    ).build(renderer);
+    )?.build(renderer)?;
 
     let mut teapot = MeshObjectBuilder::from_file(
         "examples/models/teapot.obj",
@@ -33,7 +48,7 @@ fn main() {
         &vec3(0.5, 0.5, 0.5),
         &vec3(1.0, 1.0, 1.0),
         (1.0, 128.0),
-    ).build(renderer);
+    )?.build(renderer)?;
 
     let plane = MeshObjectBuilder::from_file(
         "examples/models/plane.obj",
@@ -41,7 +56,7 @@ fn main() {
         &vec3(10.0, 10.0, 10.0),
         &vec3(0.5, 0.5, 0.5),
         (0.2, 2.0),
-    ).build(renderer);
+    )?.build(renderer)?;
 
     let mut torus1 = MeshObjectBuilder::from_file(
         "examples/models/torus.obj",
@@ -49,7 +64,7 @@ fn main() {
         &vec3(3.0, 3.0, 3.0),
         &vec3(0.0, 1.0, 0.0),
         (1.0, 128.0),
-    ).build(renderer);
+    )?.build(renderer)?;
 
     let mut torus2 = MeshObjectBuilder::from_file(
         "examples/models/torus.obj",
@@ -57,7 +72,7 @@ fn main() {
         &vec3(3.5, 3.5, 3.5),
         &vec3(1.0, 0.0, 0.0),
         (1.0, 128.0),
-    ).build(renderer);
+    )?.build(renderer)?;
 
     let mut bunny = MeshObjectBuilder::from_file(
         "examples/models/bunny.obj",
@@ -65,7 +80,33 @@ fn main() {
         &vec3(14.0, 14.0, 14.0),
         &vec3(1.0, 1.0, 1.0),
         (0.2, 2.0),
-    ).build(renderer);
+    )?.build(renderer)?;
+
+    let mut cube = MeshObjectBuilder::from_file_textured(
+        "examples/models/cube.obj",
+        &vec3(4.0, 0.5, -7.0),
+        &vec3(1.0, 1.0, 1.0),
+        (0.5, 16.0),
+    )?.build(renderer)?;
+    let checkerboard = Texture::from_rgba8(64, 64, checkerboard(64, 8), renderer)?;
+
+    // Translucent spheres, which are drawn after everything else
+    let glass = [vec3(-2.0, 0.0, -4.0), vec3(1.5, -1.0, -6.0)]
+        .iter()
+        .map(|position| -> Result<_, Box<dyn Error>> {
+            let mut material = Material::new(vec3(0.6, 0.8, 1.0), 1.0, 64.0);
+            material.set_opacity(0.35);
+            Ok(MeshObjectBuilder::from_file(
+                "examples/models/sphere.obj",
+                position,
+                &vec3(0.8, 0.8, 0.8),
+                &vec3(1.0, 1.0, 1.0),
+                (1.0, 64.0),
+            )?
+                .with_material(material)
+                .build(renderer)?)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
     torus1.transform_mut().set_rotation_mat({
         let mut rotation = identity();
@@ -91,25 +132,35 @@ fn main() {
         0.05,
     );
 
-    let mut directional_lights: Vec<(PointLight, MeshObject<_>)> = vec![
+    let mut sun = DirectionalLight::new(
+        vec3(0.3, 1.0, -0.5),
+        vec3(1.0, 0.95, 0.85),
+        0.3,
+    );
+
+    let directional_lights: Vec<(PointLight, MeshObject<_>)> = vec![
         (vec3(-4.0, 0.0, -2.0), vec3(1.0, 0.0, 0.0), 3.0f32),
         (vec3(0.0, -3.0, -14.0), vec3(0.0, 1.0, 0.0), 8.0f32),
         (vec3(4.0, -2.0, -1.0), vec3(0.0, 0.0, 1.0), 5.0f32),
         (vec3(0.0, -25.0, -5.0), vec3(1.0, 0.9, 0.8), 80.0f32),
     ]
     .iter()
-    .map(|f| {
-        let obj = MeshObjectBuilder::from_file(
+    .map(|f| -> Result<_, Box<dyn Error>> {
+        let obj = MeshObjectBuilder::from_file_unlit(
             "examples/models/sphere.obj",
             &f.0,
             &(vec3(0.1, 0.1, 0.1) * f.2.sqrt()),
             &f.1,
-            // TODO: shouldn't be necessary to specify specular data here
-            (0.2, 2.0),
-        ).build(renderer);
-        (PointLight::new(f.0, f.1, f.2), obj)
+        )?.build(renderer)?;
+        Ok((PointLight::new(f.0, f.1, f.2), obj))
     })
-    .collect();
+    .collect::<Result<_, _>>()?;
+    rhyolite.renderer.set_point_lights(directional_lights.iter().map(|light| &light.0));
+
+    // Post-processing
+    let base = rhyolite.renderer.get_base_mut();
+    base.push_post_effect(PostEffect::Bloom(Bloom::default()))?;
+    base.push_post_effect(PostEffect::Fxaa)?;
 
     // Other
     let mut camera_pos = vec3(0.0, 0.0, 0.0);
@@ -143,6 +194,13 @@ fn main() {
                     rotation
                 });
 
+                cube.transform_mut().set_rotation_mat({
+                    let mut rotation = identity();
+                    rotation = rotate_y(&rotation, time.current / 3.0);
+                    rotation = rotate_x(&rotation, 0.4);
+                    rotation
+                });
+
                 // Camera movement
                 examples::do_camera_movement(
                     CamRotationMode::Mesh,
@@ -154,24 +212,49 @@ fn main() {
                 );
 
                 // Rendering
-                renderer.start_render_pass(&mut camera);
-                renderer.draw_lit_auto(DrawInfo::Vertex { object: &suzanne });
-                renderer.draw_lit_auto(DrawInfo::Vertex { object: &plane });
-                renderer.draw_lit_auto(DrawInfo::Vertex { object: &teapot });
-                renderer.draw_lit_auto(DrawInfo::Vertex { object: &torus1 });
-                renderer.draw_lit_auto(DrawInfo::Vertex { object: &torus2 });
-                renderer.draw_lit_auto(DrawInfo::Vertex { object: &bunny });
-                renderer.draw_ambient_light(&mut ambient_light);
-                for light in directional_lights.iter_mut() {
-                    renderer.draw_point_light(&mut light.0);
+                let frame = (|| -> Result<(), RhyoliteError> {
+                    renderer.start_render_pass(&mut camera)?;
+                    renderer.draw_lit_auto(DrawInfo::Vertex { object: &suzanne })?;
+                    renderer.draw_lit_auto(DrawInfo::Vertex { object: &plane })?;
+                    renderer.draw_lit_auto(DrawInfo::Vertex { object: &teapot })?;
+                    renderer.draw_lit_auto(DrawInfo::Vertex { object: &torus1 })?;
+                    renderer.draw_lit_auto(DrawInfo::Vertex { object: &torus2 })?;
+                    renderer.draw_lit_auto(DrawInfo::Vertex { object: &bunny })?;
+                    renderer.draw_textured_auto(DrawInfo::Vertex { object: &cube }, &checkerboard)?;
+                    renderer.draw_ambient_light(&mut ambient_light)?;
+                    renderer.draw_point_lights()?;
+                    renderer.draw_directional_light(&mut sun)?;
+                    for light in directional_lights.iter() {
+                        // TODO: should ideally use an instancing method instead of this, buffer recreated multiple times per frame
+                        renderer.draw_unlit_auto(DrawInfo::Vertex { object: &light.1 })?;
+                    }
+                    renderer.draw_transparent_auto(
+                        glass.iter().map(|object| DrawInfo::Vertex { object }),
+                    )?;
+                    renderer.end_render_pass()
+                })();
+                if let Err(e) = frame {
+                    eprintln!("Failed to render frame: {}", e);
                 }
-                // for light in directional_lights.iter_mut() {
-                //     // TODO: should ideally use an instancing method instead of this, buffer recreated multiple times per frame
-                //     renderer.draw_unlit(DrawType::Vertex(&light.1), None);
-                // }
-                renderer.end_render_pass();
             }
             _ => (),
         }
     });
+
+    Ok(())
+}
+
+/// Generates RGBA8 data for a square checkerboard texture
+fn checkerboard(size: u32, squares: u32) -> Vec<u8> {
+    let square_size = size / squares;
+    (0..size * size)
+        .flat_map(|i| {
+            let (x, y) = (i % size / square_size, i / size / square_size);
+            if (x + y) % 2 == 0 {
+                [230, 230, 230, 255]
+            } else {
+                [40, 40, 40, 255]
+            }
+        })
+        .collect()
 }
@@ -1,8 +1,8 @@
 
 use rhyolite::geometry::marched::Metaball;
-use rhyolite::lighting::AmbientLight;
+use rhyolite::lighting::{AmbientLight, DirectionalLight};
 use rhyolite::transform::Transform;
-use rhyolite::Rhyolite;
+use rhyolite::{Rhyolite, RhyoliteError};
 use rhyolite::{camera::Camera, lighting::PointLight};
 
 use winit::event::{Event, VirtualKeyCode, WindowEvent};
@@ -12,8 +12,8 @@ use examples::{CamRotationMode, KeyBinding};
 
 use rhyolite::renderer::Renderer;
 
-fn main() {
-    let mut rhyolite = Rhyolite::ray_marched();
+fn main() -> Result<(), RhyoliteError> {
+    let mut rhyolite = Rhyolite::ray_marched()?;
 
     let camera_transform = Transform::identity();
     let mut camera = Camera::new(camera_transform, 1.2, 0.02, 100.0);
@@ -31,12 +31,25 @@ fn main() {
             60.0
         ),
     ];
+    // A sun-like key light shining down at an angle
+    let mut directional_lights = vec![
+        DirectionalLight::new(
+            vec3(-0.4, 1.0, -0.3),
+            vec3(1.0, 0.95, 0.85),
+            0.6
+        ),
+    ];
     let mut ambient_light = AmbientLight::new(
         vec3(1.0, 1.0, 1.0),
         0.1
     );
 
-    rhyolite.renderer.config_lighting(&mut point_lights, &mut ambient_light);
+    rhyolite.renderer.config_lighting(
+        &mut point_lights,
+        &mut directional_lights,
+        &mut vec![],
+        &mut ambient_light,
+    )?;
 
     const GRID_WIDTH: u32 = 3;
     const GRID_HEIGHT: u32 = 3;
@@ -96,11 +109,18 @@ fn main() {
                 metaballs[0].set_position(ctrl_metaball_pos);
 
                 // Rendering
-                renderer.start(&mut camera);
-                renderer.add_objects(&metaballs);
-                renderer.finish().unwrap();
+                let frame = (|| -> Result<(), RhyoliteError> {
+                    renderer.start(&mut camera)?;
+                    renderer.add_objects(&metaballs)?;
+                    renderer.finish()
+                })();
+                if let Err(e) = frame {
+                    eprintln!("Failed to render frame: {}", e);
+                }
             }
             _ => (),
         }
     });
+
+    Ok(())
 }
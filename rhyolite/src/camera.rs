@@ -3,8 +3,7 @@ use nalgebra_glm::{perspective, TMat4};
 use vulkano::buffer::allocator::SubbufferAllocator;
 use vulkano::buffer::Subbuffer;
 
-use crate::{shaders::albedo_vert, transform::Transform, UnconfiguredError};
-use crate::renderer::staging::UniformSrc;
+use crate::{shaders::albedo_vert, transform::Transform, RhyoliteError};
 
 pub struct Camera {
     fovy: f32,
@@ -61,14 +60,14 @@ impl Camera {
         });
     }
 
-    fn get_post_config(&self) -> Result<&CameraPostConfig, UnconfiguredError> {
-        self.post_config.as_ref().ok_or(UnconfiguredError(
+    fn get_post_config(&self) -> Result<&CameraPostConfig, RhyoliteError> {
+        self.post_config.as_ref().ok_or(RhyoliteError::Unconfigured(
             "Camera not yet configured. Do so with `Camera::configure()` before accessing projection matrix"
         ))
     }
 
-    fn get_post_config_mut(&mut self) -> Result<&mut CameraPostConfig, UnconfiguredError> {
-        self.post_config.as_mut().ok_or(UnconfiguredError(
+    fn get_post_config_mut(&mut self) -> Result<&mut CameraPostConfig, RhyoliteError> {
+        self.post_config.as_mut().ok_or(RhyoliteError::Unconfigured(
             "Camera not yet configured. Do so with `Camera::configure()` before accessing projection matrix"
         ))
     }
@@ -92,40 +91,48 @@ impl Camera {
     }
 
     /// Calculates the camera's view matrix, and caches the result
-    fn get_view(&self) -> TMat4<f32> {
+    /// # Errors
+    /// Returns `RhyoliteError::SingularTransform` if the camera's transform can't be inverted.
+    fn get_view(&self) -> Result<TMat4<f32>, RhyoliteError> {
         if let Some(cache) = self.cache.get() {
-            return cache;
+            return Ok(cache);
         }
         let view = self
             .transform
             .get_matrices()
             .0
             .try_inverse()
-            .unwrap();
+            .ok_or(RhyoliteError::SingularTransform(
+                "Camera transform can't be inverted into a view matrix. Make sure its scale isn't 0"
+            ))?;
         self.cache.set(Some(view));
-        view
+        Ok(view)
+    }
+
+    /// Gets the camera's view and projection matrices.
+    /// # Errors
+    /// - Returns `RhyoliteError::Unconfigured` if the camera hasn't been configured yet.
+    /// - Returns `RhyoliteError::SingularTransform` if the camera's transform can't be inverted.
+    pub(crate) fn view_projection(&self) -> Result<(TMat4<f32>, TMat4<f32>), RhyoliteError> {
+        Ok((self.get_view()?, self.get_post_config()?.projection))
     }
 
     /// Returns a subbuffer containing the camera's view and projection data as required for
     /// rendering. Allocates from a `SubbufferAllocator`.
+    /// # Errors
+    /// - Returns `RhyoliteError::Unconfigured` if the camera hasn't been configured yet.
+    /// - Returns `RhyoliteError::SingularTransform` if the camera's transform can't be inverted.
     pub(crate) fn get_vp_subbuffer(
         &mut self,
         subbuffer_allocator: &SubbufferAllocator,
-    ) -> Result<Subbuffer<albedo_vert::UCamData>, UnconfiguredError> {
-        let buf = subbuffer_allocator.allocate_sized().unwrap();
-        *buf.write().unwrap() = self.get_raw();
+    ) -> Result<Subbuffer<albedo_vert::UCamData>, RhyoliteError> {
+        let (view, projection) = self.view_projection()?;
+        let buf = subbuffer_allocator.allocate_sized()?;
+        *buf.write()? = albedo_vert::UCamData {
+            view: view.into(),
+            projection: projection.into(),
+        };
 
         Ok(buf)
     }
-}
-
-impl UniformSrc<albedo_vert::UCamData> for Camera {
-    fn get_raw(&self) -> albedo_vert::UCamData {
-        albedo_vert::UCamData {
-            view: self.get_view().into(),
-            projection: self.get_post_config().expect( // TODO: EWWW EXPECT (should be unconfigurederror somehow)
-                "Camera must be configured before getting buffer"
-            ).projection.into(),
-        }
-    }
 }
\ No newline at end of file
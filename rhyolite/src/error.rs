@@ -0,0 +1,175 @@
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "mesh")]
+use crate::geometry::mesh::LoadError;
+use crate::renderer::capture::UnsupportedFormatError;
+use crate::renderer::DeviceSelector;
+
+/// An error from anywhere in Rhyolite, from creating a renderer to submitting a frame.
+///
+/// Errors returned by Vulkano are grouped by what Rhyolite was doing when they occurred, and can
+/// be retrieved with `Error::source()`.
+#[derive(Debug)]
+pub enum RhyoliteError {
+    /// The Vulkan library, instance, window, or surface couldn't be created.
+    Setup(Box<dyn Error + Send + Sync>),
+    /// None of the physical devices matching the `DeviceSelector` support the extensions and
+    /// queues needed for rendering.
+    NoSuitableDevice(DeviceSelector),
+    /// The physical devices couldn't be enumerated, or the logical device couldn't be created.
+    Device(Box<dyn Error + Send + Sync>),
+    /// The surface couldn't be queried, the swapchain couldn't be created or recreated, or an
+    /// image couldn't be acquired from it.
+    Swapchain(Box<dyn Error + Send + Sync>),
+    /// A shader, render pass, framebuffer, pipeline, or sampler couldn't be created.
+    Pipeline(Box<dyn Error + Send + Sync>),
+    /// A buffer, image, or descriptor set couldn't be allocated or written to.
+    Allocation(Box<dyn Error + Send + Sync>),
+    /// Commands couldn't be recorded into a command buffer, or the command buffer couldn't be
+    /// submitted.
+    Command(Box<dyn Error + Send + Sync>),
+    /// A model, material, texture, or scene couldn't be loaded.
+    #[cfg(feature = "mesh")]
+    Loader(LoadError),
+    /// A rendering function was called out of order, such as drawing an object after lighting
+    /// has started.
+    StageOrder {
+        attempted: &'static str,
+        current: &'static str,
+    },
+    /// Something was used before it was configured.
+    Unconfigured(&'static str),
+    /// A transform that needs to be inverted, such as a camera's, has no inverse.
+    SingularTransform(&'static str),
+    /// More lights of one kind were given to the ray marching renderer than its shaders can hold.
+    TooManyLights { kind: &'static str, max: usize },
+    /// More metaballs were given to the ray marching renderer than its shaders can hold.
+    TooManyObjects { max: usize },
+    /// A frame can't be captured, because its format can't be converted to RGBA8.
+    UnsupportedFormat(UnsupportedFormatError),
+}
+
+impl fmt::Display for RhyoliteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RhyoliteError::Setup(e) => write!(f, "unable to set up Vulkan: {}", e),
+            RhyoliteError::NoSuitableDevice(selector) => write!(
+                f,
+                "no physical device matching {:?} supports the required extensions and queues",
+                selector
+            ),
+            RhyoliteError::Device(e) => write!(f, "unable to create logical device: {}", e),
+            RhyoliteError::Swapchain(e) => write!(f, "swapchain error: {}", e),
+            RhyoliteError::Pipeline(e) => write!(f, "unable to create pipeline: {}", e),
+            RhyoliteError::Allocation(e) => write!(f, "allocation error: {}", e),
+            RhyoliteError::Command(e) => write!(f, "command buffer error: {}", e),
+            #[cfg(feature = "mesh")]
+            RhyoliteError::Loader(e) => write!(f, "{}", e),
+            RhyoliteError::StageOrder { attempted, current } => {
+                write!(f, "can't enter {} stage after {} stage", attempted, current)
+            }
+            RhyoliteError::Unconfigured(message) => write!(f, "{}", message),
+            RhyoliteError::SingularTransform(message) => write!(f, "{}", message),
+            RhyoliteError::TooManyLights { kind, max } => {
+                write!(f, "too many {} lights, at most {} are supported", kind, max)
+            }
+            RhyoliteError::TooManyObjects { max } => {
+                write!(f, "too many objects, at most {} are supported", max)
+            }
+            RhyoliteError::UnsupportedFormat(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for RhyoliteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RhyoliteError::Setup(e)
+            | RhyoliteError::Device(e)
+            | RhyoliteError::Swapchain(e)
+            | RhyoliteError::Pipeline(e)
+            | RhyoliteError::Allocation(e)
+            | RhyoliteError::Command(e) => Some(e.as_ref()),
+            #[cfg(feature = "mesh")]
+            RhyoliteError::Loader(e) => Some(e),
+            RhyoliteError::UnsupportedFormat(e) => Some(e),
+            RhyoliteError::NoSuitableDevice(_)
+            | RhyoliteError::StageOrder { .. }
+            | RhyoliteError::Unconfigured(_)
+            | RhyoliteError::SingularTransform(_)
+            | RhyoliteError::TooManyLights { .. }
+            | RhyoliteError::TooManyObjects { .. } => None,
+        }
+    }
+}
+
+#[cfg(feature = "mesh")]
+impl From<LoadError> for RhyoliteError {
+    fn from(value: LoadError) -> Self {
+        RhyoliteError::Loader(value)
+    }
+}
+
+impl From<UnsupportedFormatError> for RhyoliteError {
+    fn from(value: UnsupportedFormatError) -> Self {
+        RhyoliteError::UnsupportedFormat(value)
+    }
+}
+
+/// Implements `From` for each Vulkano error type, wrapping it in the specified variant.
+macro_rules! from_errors {
+    ($variant:ident: $($error:ty),+ $(,)?) => {
+        $(
+            impl From<$error> for RhyoliteError {
+                fn from(value: $error) -> Self {
+                    RhyoliteError::$variant(Box::new(value))
+                }
+            }
+        )+
+    };
+}
+
+from_errors!(Setup:
+    vulkano::LoadingError,
+    vulkano::instance::InstanceCreationError,
+    vulkano::instance::debug::DebugUtilsMessengerCreationError,
+    vulkano_win::CreationError,
+    winit::error::OsError,
+);
+from_errors!(Device:
+    vulkano::VulkanError,
+    vulkano::device::DeviceCreationError,
+);
+from_errors!(Swapchain:
+    vulkano::device::physical::PhysicalDeviceError,
+    vulkano::swapchain::SwapchainCreationError,
+    vulkano::swapchain::AcquireError,
+);
+from_errors!(Pipeline:
+    vulkano::OomError,
+    vulkano::shader::ShaderCreationError,
+    vulkano::render_pass::RenderPassCreationError,
+    vulkano::render_pass::FramebufferCreationError,
+    vulkano::pipeline::graphics::GraphicsPipelineCreationError,
+    vulkano::pipeline::compute::ComputePipelineCreationError,
+    vulkano::sampler::SamplerCreationError,
+);
+from_errors!(Allocation:
+    vulkano::buffer::BufferError,
+    vulkano::image::ImageError,
+    vulkano::image::immutable::ImmutableImageCreationError,
+    vulkano::image::view::ImageViewCreationError,
+    vulkano::memory::allocator::AllocationCreationError,
+    vulkano::descriptor_set::DescriptorSetCreationError,
+);
+from_errors!(Command:
+    vulkano::command_buffer::CommandBufferBeginError,
+    vulkano::command_buffer::BuildError,
+    vulkano::command_buffer::DebugUtilsError,
+    vulkano::command_buffer::CopyError,
+    vulkano::command_buffer::RenderPassError,
+    vulkano::command_buffer::PipelineExecutionError,
+    vulkano::command_buffer::CommandBufferExecError,
+    vulkano::sync::FlushError,
+);
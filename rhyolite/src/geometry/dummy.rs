@@ -1,5 +1,6 @@
 use crate::renderer::staging::StagingBuffer;
 use crate::renderer::RenderBase;
+use crate::RhyoliteError;
 use vulkano::buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer};
 use vulkano::memory::allocator::{AllocationCreateInfo, MemoryAllocator, MemoryUsage};
 use vulkano::pipeline::graphics::vertex_input::Vertex;
@@ -38,7 +39,7 @@ impl DummyVertex {
     pub fn buf(
         buffer_allocator: &(impl MemoryAllocator + ?Sized),
         base: &RenderBase,
-    ) -> Subbuffer<[DummyVertex]> {
+    ) -> Result<Subbuffer<[DummyVertex]>, RhyoliteError> {
         Buffer::from_iter(
             buffer_allocator,
             BufferCreateInfo {
@@ -50,8 +51,7 @@ impl DummyVertex {
                 ..Default::default()
             },
             DummyVertex::list(),
-        )
-        .unwrap()
+        )?
         .into_device_local(6, buffer_allocator, &base)
     }
 }
@@ -1,10 +1,17 @@
 use std::{
+    collections::HashMap,
     fs::File,
-    io::{BufRead, BufReader},
+    hash::Hash,
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
 };
+use nalgebra_glm::{angle, cross, dot, normalize, Vec3};
+use super::mtl::MtlMaterial;
 use vulkano::buffer::BufferContents;
 use vulkano::pipeline::graphics::vertex_input::Vertex;
 
+/// A vertex with a position and normal, used with untextured objects. Its color comes from the
+/// `Material` of the object it belongs to.
 #[repr(C)]
 #[derive(Vertex, Clone, Copy, Debug, Default, BufferContents)]
 pub struct BasicVertex {
@@ -12,8 +19,6 @@ pub struct BasicVertex {
     pub position: [f32; 3],
     #[format(R32G32B32_SFLOAT)]
     pub normal: [f32; 3],
-    #[format(R32G32B32_SFLOAT)]
-    pub color: [f32; 3],
 }
 
 #[repr(C)]
@@ -25,150 +30,676 @@ pub struct UnlitVertex {
     pub color: [f32; 3],
 }
 
+/// A vertex with texture coordinates, used with textured objects. Its color is sampled from a
+/// texture rather than stored in the vertex.
+#[repr(C)]
+#[derive(Vertex, Clone, Copy, Debug, Default, BufferContents)]
+pub struct TexturedVertex {
+    #[format(R32G32B32_SFLOAT)]
+    pub position: [f32; 3],
+    #[format(R32G32B32_SFLOAT)]
+    pub normal: [f32; 3],
+    #[format(R32G32_SFLOAT)]
+    pub tex_coord: [f32; 2],
+}
+
+/// An error that occurs while loading a model.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The model couldn't be read from its source.
+    Io(io::Error),
+    /// A line of the model couldn't be parsed. Lines are numbered starting from 1.
+    Parse { line: usize, message: String },
+    /// A material library referenced by the model couldn't be loaded.
+    MaterialLibrary { path: PathBuf, error: Box<LoadError> },
+    /// A glTF file couldn't be loaded.
+    #[cfg(feature = "gltf")]
+    Gltf(gltf::Error),
+    /// A texture image couldn't be read or decoded.
+    Image(image::ImageError),
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "unable to read model: {}", e),
+            LoadError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            LoadError::MaterialLibrary { path, error } => {
+                write!(f, "material library {}: {}", path.display(), error)
+            }
+            #[cfg(feature = "gltf")]
+            LoadError::Gltf(e) => write!(f, "unable to load glTF file: {}", e),
+            LoadError::Image(e) => write!(f, "unable to load image: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Parse { .. } => None,
+            LoadError::MaterialLibrary { error, .. } => Some(error.as_ref()),
+            #[cfg(feature = "gltf")]
+            LoadError::Gltf(e) => Some(e),
+            LoadError::Image(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(value: io::Error) -> Self {
+        LoadError::Io(value)
+    }
+}
+
+#[cfg(feature = "gltf")]
+impl From<gltf::Error> for LoadError {
+    fn from(value: gltf::Error) -> Self {
+        LoadError::Gltf(value)
+    }
+}
+
+impl From<image::ImageError> for LoadError {
+    fn from(value: image::ImageError) -> Self {
+        LoadError::Image(value)
+    }
+}
+
 struct RawVertex(f32, f32, f32);
 
 impl RawVertex {
-    /// Loads vertex data from a str containing 3 values separated by whitespace, for example `0.0 0.0 0.0`
-    fn from_str(input: &str) -> RawVertex {
+    /// Loads vertex data from a str containing `min_values` or more values separated by
+    /// whitespace, for example `0.0 0.0 0.0`. Values past the third, such as the `w` component of
+    /// a position, are ignored, and missing values default to 0.
+    fn from_str(input: &str, min_values: usize) -> Result<RawVertex, String> {
         let mut contents: Vec<f32> = input
             .split_whitespace()
+            .take(3)
             .map(|item| {
                 item.parse()
-                    .expect(&format!("Unable to parse element \"{}\"", input))
+                    .map_err(|_| format!("unable to parse element \"{}\"", item))
             })
-            .collect();
-        if contents.len() == 2 {
-            contents.push(0.0);
+            .collect::<Result<_, _>>()?;
+        if contents.len() < min_values {
+            return Err(format!(
+                "expected at least {} values, found {}",
+                min_values,
+                contents.len()
+            ));
         }
-        RawVertex(contents[0], contents[1], contents[2])
+        contents.resize(3, 0.0);
+        Ok(RawVertex(contents[0], contents[1], contents[2]))
     }
 
     fn to_arr(&self) -> [f32; 3] {
         [self.0, self.1, self.2]
     }
+
+    fn to_vec3(&self) -> Vec3 {
+        Vec3::new(self.0, self.1, self.2)
+    }
+
+    fn from_vec3(vec: &Vec3) -> RawVertex {
+        RawVertex(vec.x, vec.y, vec.z)
+    }
 }
 
-#[derive(Debug)]
+/// The method used to generate normals for a model, usually because it doesn't include them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalGeneration {
+    /// Every vertex uses the normal of its face, giving the model a faceted look.
+    Flat,
+    /// Every vertex uses a weighted average of the normals of all faces sharing its position.
+    /// If `crease_angle` is specified, faces whose normals differ by more than that many radians
+    /// aren't averaged together, which keeps hard edges sharp.
+    Smooth {
+        weighting: NormalWeighting,
+        crease_angle: Option<f32>,
+    },
+}
+
+impl Default for NormalGeneration {
+    fn default() -> Self {
+        NormalGeneration::Smooth {
+            weighting: NormalWeighting::Angle,
+            crease_angle: None,
+        }
+    }
+}
+
+/// How much each face contributes to a smooth normal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalWeighting {
+    /// Faces are weighted by their area, so large faces dominate the normal.
+    Area,
+    /// Faces are weighted by their interior angle at the vertex, which is independent of how the
+    /// surface is triangulated.
+    Angle,
+}
+
+/// A triangle of a loaded model, containing indices into the model's vertices, normals, and
+/// texture coordinates. Faces with more than three vertices are split into multiple `RawFace`s
+/// when they're loaded.
+#[derive(Debug, Clone)]
 pub struct RawFace {
     pub vertex_indices: [usize; 3],
     pub normal_indices: Option<[usize; 3]>,
     pub tex_coord_indices: Option<[usize; 3]>,
+    /// The index of the face's material in `ModelBuilder::material_names()`, if it has one
+    pub material: Option<usize>,
+}
+
+/// The indices of a single vertex of a face declaration, in the format `v/vt/vn`
+struct RawFaceVertex {
+    vertex: usize,
+    tex_coord: Option<usize>,
+    normal: Option<usize>,
 }
 
 impl RawFace {
-    pub fn from_str(input: &str, invert: bool) -> Self {
-        let args = RawFace::parse_args(input);
-        Self {
-            vertex_indices: RawFace::get_indices(&args, 0, invert).unwrap(),
-            tex_coord_indices: RawFace::get_indices(&args, 1, invert),
-            normal_indices: RawFace::get_indices(&args, 2, invert),
-        }
-    }
-    /// Parses an vertex of the face declaration in the format `v/vt/vn v/vt/vn v/vt/vn`, returning a value in the format
-    /// `[[v, vt, vn], [v, vt, vn], [v, vt, vn]]`
-    fn parse_args(input: &str) -> Vec<Vec<Option<usize>>> {
-        input
+    /// Parses a face declaration in the format `v/vt/vn v/vt/vn v/vt/vn ...`, where the texture
+    /// coordinate and normal indices are optional. Faces with more than three vertices are fan
+    /// triangulated, so they should be convex.
+    ///
+    /// The `*_count` parameters are the number of elements of each type loaded so far, which are
+    /// used to resolve negative (relative) indices.
+    fn from_str(
+        input: &str,
+        invert: bool,
+        material: Option<usize>,
+        vertex_count: usize,
+        tex_coord_count: usize,
+        normal_count: usize,
+    ) -> Result<Vec<Self>, String> {
+        let corners = input
             .split_whitespace()
             .map(|item| {
-                let mut contents: Vec<Option<usize>> =
-                    item.split('/').map(|item| item.parse().ok()).collect();
-                while contents.len() < 3 {
-                    contents.push(None);
+                let mut contents = item.split('/');
+                let mut next_index = |count: usize| -> Result<Option<usize>, String> {
+                    match contents.next() {
+                        None | Some("") => Ok(None),
+                        Some(idx) => resolve_index(idx, count).map(Some),
+                    }
+                };
+                Ok(RawFaceVertex {
+                    vertex: next_index(vertex_count)?
+                        .ok_or_else(|| format!("face vertex \"{}\" has no position index", item))?,
+                    tex_coord: next_index(tex_coord_count)?,
+                    normal: next_index(normal_count)?,
+                })
+            })
+            .collect::<Result<Vec<RawFaceVertex>, String>>()?;
+
+        if corners.len() < 3 {
+            return Err(format!("face has {} vertices, but at least 3 are required", corners.len()));
+        }
+
+        // Fan triangulation around the first vertex
+        Ok((1..corners.len() - 1)
+            .map(|i| {
+                let mut triangle = [&corners[0], &corners[i], &corners[i + 1]];
+                if invert {
+                    triangle.reverse();
+                }
+                RawFace {
+                    vertex_indices: triangle.map(|c| c.vertex),
+                    tex_coord_indices: RawFace::get_indices(&triangle, |c| c.tex_coord),
+                    normal_indices: RawFace::get_indices(&triangle, |c| c.normal),
+                    material,
                 }
-                contents
             })
-            .collect::<Vec<Vec<Option<usize>>>>()
+            .collect())
     }
 
-    /// Gets the indices of a specified type for all of the vertices in the face (e.g., all of the normal indices), returning None
-    /// if any of the indices are None for that data type
+    /// Gets the indices of a specified type for all of the vertices in the triangle (e.g., all of
+    /// the normal indices), returning None if any of the indices are None for that data type
     fn get_indices(
-        input: &Vec<Vec<Option<usize>>>,
-        data_type_idx: usize,
-        invert: bool,
+        triangle: &[&RawFaceVertex; 3],
+        data_type: impl Fn(&RawFaceVertex) -> Option<usize>,
     ) -> Option<[usize; 3]> {
-        let index_iter = [
-            input[0][data_type_idx],
-            input[1][data_type_idx],
-            input[2][data_type_idx],
-        ]
-        .into_iter()
-        .map(|wrapped_idx| wrapped_idx.map(|idx| idx - 1));
-
-        if invert {
-            return index_iter
-                .rev()
-                .collect::<Option<Vec<usize>>>()
-                .map(|val| val.try_into().unwrap());
-        } else {
-            return index_iter
-                .collect::<Option<Vec<usize>>>()
-                .map(|val| val.try_into().unwrap());
-        }
+        Some([
+            data_type(triangle[0])?,
+            data_type(triangle[1])?,
+            data_type(triangle[2])?,
+        ])
+    }
+}
+
+/// Converts a 1-based OBJ index into a 0-based index. Negative indices are relative to the end
+/// of the `count` elements loaded so far, so `-1` refers to the most recent element.
+fn resolve_index(input: &str, count: usize) -> Result<usize, String> {
+    let idx: i64 = input
+        .parse()
+        .map_err(|_| format!("unable to parse index \"{}\"", input))?;
+    let resolved = match idx {
+        0 => return Err(String::from("index 0 is invalid, since indices start at 1")),
+        idx if idx > 0 => idx - 1,
+        idx => count as i64 + idx,
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!(
+            "index {} is out of range, since only {} elements have been declared",
+            idx, count
+        ));
     }
+    Ok(resolved as usize)
 }
 
 pub struct ModelBuilder {
     name: Option<String>,
+    inverted: bool,
     vertices: Vec<RawVertex>,
     normals: Vec<RawVertex>,
     tex_coords: Vec<RawVertex>,
     faces: Vec<RawFace>,
+    material_libraries: Vec<String>,
+    material_names: Vec<String>,
+    materials: Vec<MtlMaterial>,
 }
 
 impl ModelBuilder {
-    pub fn from_file(filename: &'static str, invert_winding_order: bool) -> Self {
-        let data = File::open(filename).unwrap();
-        let buffered_data = BufReader::new(data);
+    /// Loads a model from an .obj file at the specified path, along with any material libraries
+    /// it references.
+    pub fn from_file(path: impl AsRef<Path>, invert_winding_order: bool) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let data = File::open(path)?;
+        let mut model = Self::from_reader(data, invert_winding_order)?;
+        model.load_materials(path.parent().unwrap_or(Path::new("")))?;
+        Ok(model)
+    }
+
+    /// Loads a model from any source of .obj data. Faces with more than three vertices are
+    /// triangulated, and blank lines, comments, and unsupported statements are ignored.
+    ///
+    /// Material libraries referenced by the model aren't loaded, since their locations are
+    /// relative to the model file. To load them, call `load_materials()`.
+    pub fn from_reader(reader: impl Read, invert_winding_order: bool) -> Result<Self, LoadError> {
+        let buffered_data = BufReader::new(reader);
 
         let mut name = None;
+        let mut material_libraries = Vec::new();
+        let mut material_names: Vec<String> = Vec::new();
+        let mut current_material = None;
 
         let mut vertices = Vec::new();
         let mut normals = Vec::new();
         let mut tex_coords = Vec::new();
         let mut faces = Vec::new();
 
-        for line in buffered_data.lines() {
-            let line = line.unwrap();
-            match line.split_at(2) {
-                ("o ", val) => {
-                    name = Some(String::from(val));
+        for (i, line) in buffered_data.lines().enumerate() {
+            let line = line?;
+            let parse_error = |message: String| LoadError::Parse { line: i + 1, message };
+
+            // Strip comments and surrounding whitespace
+            let line = line.split('#').next().unwrap_or("").trim();
+            let (keyword, val) = line
+                .split_once(char::is_whitespace)
+                .unwrap_or((line, ""));
+
+            match keyword {
+                "o" => {
+                    name = Some(String::from(val.trim()));
+                }
+                "mtllib" => material_libraries.extend(val.split_whitespace().map(String::from)),
+                "usemtl" => {
+                    let material = val.trim();
+                    current_material = Some(
+                        match material_names.iter().position(|name| name == material) {
+                            Some(idx) => idx,
+                            None => {
+                                material_names.push(String::from(material));
+                                material_names.len() - 1
+                            }
+                        },
+                    );
                 }
-                ("v ", val) => vertices.push(RawVertex::from_str(val)),
-                ("vn", val) => normals.push(RawVertex::from_str(val)),
-                ("vt", val) => tex_coords.push(RawVertex::from_str(val)),
-                ("f ", val) => faces.push(RawFace::from_str(val, invert_winding_order)),
-                (_, _) => {}
+                "v" => vertices.push(RawVertex::from_str(val, 2).map_err(parse_error)?),
+                "vn" => normals.push(RawVertex::from_str(val, 2).map_err(parse_error)?),
+                // Texture coordinates can have 1 to 3 components (u, v, w)
+                "vt" => tex_coords.push(RawVertex::from_str(val, 1).map_err(parse_error)?),
+                "f" => faces.extend(
+                    RawFace::from_str(
+                        val,
+                        invert_winding_order,
+                        current_material,
+                        vertices.len(),
+                        tex_coords.len(),
+                        normals.len(),
+                    )
+                    .map_err(parse_error)?,
+                ),
+                _ => {}
             }
         }
 
-        Self {
+        Ok(Self {
             name,
+            inverted: invert_winding_order,
             vertices,
             normals,
             tex_coords,
             faces,
+            material_libraries,
+            material_names,
+            materials: Vec::new(),
+        })
+    }
+
+    /// Creates a model from indexed triangles whose attributes are stored per vertex, as they are
+    /// in most formats other than .obj. Each index refers to the position, normal, and texture
+    /// coordinate at that index.
+    ///
+    /// Texture coordinates are expected to start from the bottom left of the image, like those in
+    /// .obj files.
+    #[cfg(feature = "gltf")]
+    pub(crate) fn from_triangles(
+        positions: Vec<[f32; 3]>,
+        normals: Option<Vec<[f32; 3]>>,
+        tex_coords: Option<Vec<[f32; 2]>>,
+        indices: &[u32],
+        invert_winding_order: bool,
+    ) -> Self {
+        let has_normals = normals.is_some();
+        let has_tex_coords = tex_coords.is_some();
+
+        let faces = indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let mut triangle = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize);
+                if invert_winding_order {
+                    triangle.reverse();
+                }
+                RawFace {
+                    vertex_indices: triangle,
+                    normal_indices: has_normals.then_some(triangle),
+                    tex_coord_indices: has_tex_coords.then_some(triangle),
+                    material: None,
+                }
+            })
+            .collect();
+
+        let to_raw = |[x, y, z]: [f32; 3]| RawVertex(x, y, z);
+        Self {
+            name: None,
+            inverted: invert_winding_order,
+            vertices: positions.into_iter().map(to_raw).collect(),
+            normals: normals.unwrap_or_default().into_iter().map(to_raw).collect(),
+            tex_coords: tex_coords
+                .unwrap_or_default()
+                .into_iter()
+                .map(|[u, v]| RawVertex(u, v, 0.0))
+                .collect(),
+            faces,
+            material_libraries: Vec::new(),
+            material_names: Vec::new(),
+            materials: Vec::new(),
         }
     }
 
-    /// Builds an array of vertices from the model. Does not require texture coordinates in the loaded model, but does
-    /// require normals and vertices.
-    pub fn build_basic(&self, custom_color: [f32; 3]) -> Vec<BasicVertex> {
-        let mut result = Vec::new();
+    /// Loads the materials from every material library referenced by the model, replacing any
+    /// materials that were already loaded. Library paths are resolved relative to `directory`.
+    ///
+    /// Libraries that don't exist are skipped with a warning, so their materials are left
+    /// missing and the faces using them fall back to the default material.
+    pub fn load_materials(&mut self, directory: impl AsRef<Path>) -> Result<(), LoadError> {
+        let mut materials = Vec::new();
+        for library in &self.material_libraries {
+            let path = directory.as_ref().join(library);
+            match MtlMaterial::from_file(&path) {
+                Ok(library_materials) => materials.extend(library_materials),
+                Err(LoadError::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+                    log::warn!("Material library {} not found: {}", path.display(), e);
+                }
+                Err(error) => {
+                    return Err(LoadError::MaterialLibrary {
+                        path,
+                        error: Box::new(error),
+                    })
+                }
+            }
+        }
+        self.materials = materials;
+        Ok(())
+    }
+
+    /// Gets the paths of the material libraries referenced by the model, as written in the model.
+    pub fn material_libraries(&self) -> &[String] {
+        &self.material_libraries
+    }
+    /// Gets the names of the materials used by the model's faces, in the order they first appear.
+    pub fn material_names(&self) -> &[String] {
+        &self.material_names
+    }
+    /// Gets the materials that have been loaded from the model's material libraries.
+    pub fn materials(&self) -> &[MtlMaterial] {
+        &self.materials
+    }
+
+    /// Gets a loaded material by name.
+    pub fn material(&self, name: &str) -> Option<&MtlMaterial> {
+        self.materials.iter().find(|material| material.name == name)
+    }
+
+    /// Splits the model into submeshes, one for each material used by its faces, in the order
+    /// the materials first appear. Faces without a material are placed in a final submesh with
+    /// no material.
+    pub fn submeshes(&self) -> Vec<Submesh<'_>> {
+        let mut groups: Vec<Vec<&RawFace>> = vec![Vec::new(); self.material_names.len() + 1];
         for face in &self.faces {
+            groups[face.material.unwrap_or(self.material_names.len())].push(face);
+        }
+
+        groups
+            .into_iter()
+            .enumerate()
+            .filter(|(_, faces)| !faces.is_empty())
+            .map(|(idx, faces)| Submesh {
+                model: self,
+                material_name: self.material_names.get(idx).map(String::as_str),
+                faces,
+            })
+            .collect()
+    }
+
+    /// Gets the name of the model, as declared by the last `o` statement.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Gets the triangles of the model.
+    pub fn faces(&self) -> &[RawFace] {
+        &self.faces
+    }
+
+    /// Returns `true` if every face of the model has normals.
+    pub fn has_normals(&self) -> bool {
+        self.faces.iter().all(|face| face.normal_indices.is_some())
+    }
+
+    /// Generates normals for every face of the model, replacing any normals that were loaded.
+    pub fn generate_normals(&mut self, mode: NormalGeneration) {
+        let face_normals: Vec<Vec3> = self.faces.iter().map(|face| self.face_normal(face)).collect();
+
+        let mut normals = Vec::new();
+        match mode {
+            NormalGeneration::Flat => {
+                for (face, normal) in self.faces.iter_mut().zip(&face_normals) {
+                    normals.push(RawVertex::from_vec3(&safe_normalize(normal)));
+                    face.normal_indices = Some([normals.len() - 1; 3]);
+                }
+            }
+            NormalGeneration::Smooth { weighting, crease_angle } => {
+                // Group the corners of all faces by position. Positions are compared by value
+                // rather than by index, since seams are often split into separate vertices.
+                let mut corners_at_position: HashMap<[u32; 3], Vec<(usize, usize)>> = HashMap::new();
+                for (face_idx, face) in self.faces.iter().enumerate() {
+                    for corner in 0..3 {
+                        let position = self.vertices[face.vertex_indices[corner]].to_arr();
+                        corners_at_position
+                            .entry(position.map(key_bits))
+                            .or_default()
+                            .push((face_idx, corner));
+                    }
+                }
+
+                let weight = |face_idx: usize, corner: usize| -> f32 {
+                    match weighting {
+                        NormalWeighting::Area => face_normals[face_idx].magnitude() / 2.0,
+                        NormalWeighting::Angle => self.corner_angle(&self.faces[face_idx], corner),
+                    }
+                };
+                let min_cos = crease_angle.map(f32::cos);
+
+                // Normals are deduplicated, since most corners sharing a position share a normal
+                let mut normal_lookup: HashMap<[u32; 3], usize> = HashMap::new();
+                let mut normal_indices = Vec::with_capacity(self.faces.len());
+
+                for (face_idx, face) in self.faces.iter().enumerate() {
+                    let face_unit = safe_normalize(&face_normals[face_idx]);
+                    let mut indices = [0; 3];
+
+                    for corner in 0..3 {
+                        let position = self.vertices[face.vertex_indices[corner]].to_arr();
+                        let mut sum = Vec3::zeros();
+                        for &(other_face, other_corner) in &corners_at_position[&position.map(key_bits)] {
+                            let other_unit = safe_normalize(&face_normals[other_face]);
+                            if min_cos.map_or(true, |min_cos| dot(&face_unit, &other_unit) >= min_cos) {
+                                sum += other_unit * weight(other_face, other_corner);
+                            }
+                        }
+
+                        let normal = if sum.magnitude() > f32::EPSILON { normalize(&sum) } else { face_unit };
+                        indices[corner] = *normal_lookup
+                            .entry([normal.x, normal.y, normal.z].map(key_bits))
+                            .or_insert_with(|| {
+                                normals.push(RawVertex::from_vec3(&normal));
+                                normals.len() - 1
+                            });
+                    }
+                    normal_indices.push(indices);
+                }
+
+                for (face, indices) in self.faces.iter_mut().zip(normal_indices) {
+                    face.normal_indices = Some(indices);
+                }
+            }
+        }
+
+        self.normals = normals;
+    }
+
+    /// Calculates the unnormalized normal of a face, whose magnitude is twice the face's area.
+    /// Faces are assumed to be wound counterclockwise in the source file.
+    fn face_normal(&self, face: &RawFace) -> Vec3 {
+        let [a, b, c] = face.vertex_indices.map(|i| self.vertices[i].to_vec3());
+        let normal = cross(&(b - a), &(c - a));
+        if self.inverted { -normal } else { normal }
+    }
+
+    /// Calculates the interior angle of a face at one of its corners, in radians.
+    fn corner_angle(&self, face: &RawFace, corner: usize) -> f32 {
+        let position = |i: usize| self.vertices[face.vertex_indices[i % 3]].to_vec3();
+        let origin = position(corner);
+        let (to_next, to_prev) = (position(corner + 1) - origin, position(corner + 2) - origin);
+        if to_next.magnitude() <= f32::EPSILON || to_prev.magnitude() <= f32::EPSILON {
+            return 0.0;
+        }
+        angle(&to_next, &to_prev)
+    }
+
+    /// Builds an array of vertices from the model. Does not require texture coordinates in the
+    /// loaded model. Faces without normals use their flat face normal, but it's usually better to
+    /// call `generate_normals()` on models without normals first.
+    pub fn build_basic(&self) -> Vec<BasicVertex> {
+        self.build_basic_faces(&self.faces)
+    }
+
+    fn build_basic_faces<'a>(
+        &self,
+        faces: impl IntoIterator<Item = &'a RawFace>,
+    ) -> Vec<BasicVertex> {
+        let mut result = Vec::new();
+        for face in faces {
             let verts = face.vertex_indices;
-            let norms = face.normal_indices.unwrap();
+            let normals = self.corner_normals(face);
             for i in 0..3 {
                 result.push(BasicVertex {
                     position: self.vertices[verts[i]].to_arr(),
-                    normal: self.normals[norms[i]].to_arr(),
-                    color: custom_color,
+                    normal: normals[i],
                 });
             }
         }
         result
     }
 
+    /// Gets the normals at each corner of a face, using the flat face normal if the face doesn't
+    /// have normals.
+    fn corner_normals(&self, face: &RawFace) -> [[f32; 3]; 3] {
+        match face.normal_indices {
+            Some(norms) => norms.map(|i| self.normals[i].to_arr()),
+            None => {
+                let flat_normal = safe_normalize(&self.face_normal(face));
+                [[flat_normal.x, flat_normal.y, flat_normal.z]; 3]
+            }
+        }
+    }
+
+    /// Returns `true` if every face of the model has texture coordinates.
+    pub fn has_tex_coords(&self) -> bool {
+        self.faces.iter().all(|face| face.tex_coord_indices.is_some())
+    }
+
+    /// Builds an array of textured vertices from the model. Normals are handled in the same way
+    /// as `build_basic()`, and faces without texture coordinates are mapped to the top left
+    /// corner of the texture.
+    ///
+    /// Texture coordinates in .obj files start from the bottom left of the image, so the V
+    /// coordinate is flipped to match Vulkan, which starts from the top left.
+    pub fn build_textured(&self) -> Vec<TexturedVertex> {
+        self.build_textured_faces(&self.faces)
+    }
+
+    fn build_textured_faces<'a>(
+        &self,
+        faces: impl IntoIterator<Item = &'a RawFace>,
+    ) -> Vec<TexturedVertex> {
+        let mut result = Vec::new();
+        for face in faces {
+            let verts = face.vertex_indices;
+            let normals = self.corner_normals(face);
+            for i in 0..3 {
+                let tex_coord = match face.tex_coord_indices {
+                    Some(tex_coords) => {
+                        let RawVertex(u, v, _) = self.tex_coords[tex_coords[i]];
+                        [u, 1.0 - v]
+                    }
+                    None => [0.0, 0.0],
+                };
+                result.push(TexturedVertex {
+                    position: self.vertices[verts[i]].to_arr(),
+                    normal: normals[i],
+                    tex_coord,
+                });
+            }
+        }
+        result
+    }
+
+    /// Builds an array of textured vertices from the model in the same way as
+    /// `build_textured()`, but deduplicates identical vertices and returns them alongside an
+    /// array of indices.
+    pub fn build_textured_indexed(&self) -> (Vec<TexturedVertex>, Vec<u32>) {
+        deduplicate_textured(self.build_textured())
+    }
+
+    /// Builds an array of vertices from the model in the same way as `build_basic()`, but
+    /// deduplicates identical vertices and returns them alongside an array of indices. Vertices
+    /// are only merged if their positions and normals are exactly equal.
+    pub fn build_basic_indexed(&self) -> (Vec<BasicVertex>, Vec<u32>) {
+        deduplicate_basic(self.build_basic())
+    }
+
     /// Builds an array of unlit vertices from the model. Does not require texture coordinates or normals in the loaded model, but
     /// does require vertices.
     pub fn build_unlit(&self, custom_color: [f32; 3]) -> Vec<UnlitVertex> {
@@ -185,3 +716,90 @@ impl ModelBuilder {
         result
     }
 }
+
+/// The faces of a model that share a material, created with `ModelBuilder::submeshes()`.
+pub struct Submesh<'a> {
+    model: &'a ModelBuilder,
+    material_name: Option<&'a str>,
+    faces: Vec<&'a RawFace>,
+}
+
+impl<'a> Submesh<'a> {
+    /// Gets the name of the submesh's material, or `None` if its faces have no material.
+    pub fn material_name(&self) -> Option<&'a str> {
+        self.material_name
+    }
+
+    /// Gets the submesh's material, or `None` if its faces have no material or the material
+    /// wasn't found in the model's material libraries.
+    pub fn material(&self) -> Option<&'a MtlMaterial> {
+        self.material_name.and_then(|name| self.model.material(name))
+    }
+
+    pub fn faces(&self) -> &[&'a RawFace] {
+        &self.faces
+    }
+
+    /// Builds the submesh's vertices and indices in the same way as
+    /// `ModelBuilder::build_basic_indexed()`. Its color comes from its material, which should be
+    /// converted into a `Material` for the built object.
+    pub fn build_basic_indexed(&self) -> (Vec<BasicVertex>, Vec<u32>) {
+        deduplicate_basic(self.model.build_basic_faces(self.faces.iter().copied()))
+    }
+
+    /// Builds the submesh's textured vertices and indices in the same way as
+    /// `ModelBuilder::build_textured_indexed()`.
+    pub fn build_textured_indexed(&self) -> (Vec<TexturedVertex>, Vec<u32>) {
+        deduplicate_textured(self.model.build_textured_faces(self.faces.iter().copied()))
+    }
+}
+
+/// Normalizes a vector, returning an arbitrary unit vector if it has no length (for example,
+/// the normal of a degenerate face)
+fn safe_normalize(vec: &Vec3) -> Vec3 {
+    if vec.magnitude() > f32::EPSILON {
+        normalize(vec)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0)
+    }
+}
+
+/// Gets the bits of a float for use in a hash key, treating `0.0` and `-0.0` as equal.
+fn key_bits(value: f32) -> u32 {
+    (value + 0.0).to_bits()
+}
+
+/// Merges vertices whose positions and normals are exactly equal.
+fn deduplicate_basic(vertices: Vec<BasicVertex>) -> (Vec<BasicVertex>, Vec<u32>) {
+    deduplicate(vertices, |v| {
+        [v.position, v.normal].map(|arr| arr.map(key_bits))
+    })
+}
+
+/// Merges vertices whose positions, normals, and texture coordinates are exactly equal.
+fn deduplicate_textured(vertices: Vec<TexturedVertex>) -> (Vec<TexturedVertex>, Vec<u32>) {
+    deduplicate(vertices, |v| {
+        (
+            [v.position, v.normal].map(|arr| arr.map(key_bits)),
+            v.tex_coord.map(key_bits),
+        )
+    })
+}
+
+/// Merges identical vertices, as determined by `key`, returning the unique vertices in the order
+/// they first appear and an index into them for every input vertex.
+fn deduplicate<T: Copy, K: Hash + Eq>(vertices: Vec<T>, key: impl Fn(&T) -> K) -> (Vec<T>, Vec<u32>) {
+    let mut unique = Vec::new();
+    let mut indices = Vec::with_capacity(vertices.len());
+    let mut lookup = HashMap::new();
+
+    for vertex in vertices {
+        let idx = *lookup.entry(key(&vertex)).or_insert_with(|| {
+            unique.push(vertex);
+            (unique.len() - 1) as u32
+        });
+        indices.push(idx);
+    }
+
+    (unique, indices)
+}
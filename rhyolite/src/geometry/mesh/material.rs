@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use nalgebra_glm::{vec3, Vec3};
+use vulkano::buffer::Subbuffer;
+use vulkano::descriptor_set::PersistentDescriptorSet;
+
+use super::mtl::MtlMaterial;
+use crate::renderer::staging::{IntoPersistentUniform, UniformSrc};
+use crate::shaders::albedo_frag;
+
+/// The surface properties of a `MeshObject`, which are stored in a uniform buffer on the GPU.
+/// The buffer is uploaded with `MeshRenderer::upload_material()`, and is only uploaded again
+/// after one of the properties changes.
+#[derive(Clone)]
+pub struct Material {
+    albedo: Vec3,
+    opacity: f32,
+    specular_intensity: f32,
+    shininess: f32,
+    subbuffer: Option<Subbuffer<albedo_frag::UMaterialData>>,
+    descriptor_set: Option<Arc<PersistentDescriptorSet>>,
+}
+
+impl Default for Material {
+    /// Gets a white material with no specular highlights.
+    fn default() -> Self {
+        Self::new(vec3(1.0, 1.0, 1.0), 0.0, 1.0)
+    }
+}
+
+impl Material {
+    pub fn new(albedo: Vec3, specular_intensity: f32, shininess: f32) -> Self {
+        Self {
+            albedo,
+            opacity: 1.0,
+            specular_intensity,
+            shininess,
+            subbuffer: None,
+            descriptor_set: None,
+        }
+    }
+
+    pub fn albedo(&self) -> &Vec3 {
+        &self.albedo
+    }
+    /// Gets how opaque the material is, from `0.0` to `1.0`. Only objects drawn with
+    /// `MeshRenderer::draw_transparent_auto()` can be see-through; other objects are always
+    /// opaque.
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+    pub fn specular_intensity(&self) -> f32 {
+        self.specular_intensity
+    }
+    pub fn shininess(&self) -> f32 {
+        self.shininess
+    }
+
+    /// Sets the base color of the material. Textured objects multiply the color sampled from
+    /// their texture by this color.
+    pub fn set_albedo(&mut self, albedo: Vec3) {
+        self.albedo = albedo;
+        self.invalidate();
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity;
+        self.invalidate();
+    }
+
+    pub fn set_specular_intensity(&mut self, specular_intensity: f32) {
+        self.specular_intensity = specular_intensity;
+        self.invalidate();
+    }
+
+    pub fn set_shininess(&mut self, shininess: f32) {
+        self.shininess = shininess;
+        self.invalidate();
+    }
+
+    /// Checks whether the material's current properties have been uploaded to the GPU.
+    pub fn is_uploaded(&self) -> bool {
+        self.descriptor_set.is_some()
+    }
+
+    pub(crate) fn descriptor_set(&self) -> Option<&Arc<PersistentDescriptorSet>> {
+        self.descriptor_set.as_ref()
+    }
+    pub(crate) fn set_descriptor_set(&mut self, descriptor_set: Arc<PersistentDescriptorSet>) {
+        self.descriptor_set = Some(descriptor_set);
+    }
+
+    /// Drops the uploaded buffer and descriptor set, so that they're recreated with the new
+    /// properties the next time the material is uploaded.
+    fn invalidate(&mut self) {
+        self.subbuffer = None;
+        self.descriptor_set = None;
+    }
+}
+
+impl From<&MtlMaterial> for Material {
+    /// Uses the diffuse color of an .mtl material as the albedo, and its specular color and
+    /// exponent as the specular intensity and shininess.
+    fn from(material: &MtlMaterial) -> Self {
+        let [r, g, b] = material.diffuse;
+        Self::new(vec3(r, g, b), material.specular_intensity(), material.shininess)
+    }
+}
+
+impl UniformSrc<albedo_frag::UMaterialData> for Material {
+    fn get_raw(&self) -> albedo_frag::UMaterialData {
+        albedo_frag::UMaterialData {
+            albedo: [self.albedo.x, self.albedo.y, self.albedo.z, self.opacity],
+            specular_intensity: self.specular_intensity,
+            shininess: self.shininess,
+        }
+    }
+}
+
+impl IntoPersistentUniform<albedo_frag::UMaterialData> for Material {
+    fn get_current_buffer(&self) -> Option<Subbuffer<albedo_frag::UMaterialData>> { self.subbuffer.clone() }
+    fn set_current_buffer(&mut self, buf: Subbuffer<albedo_frag::UMaterialData>) { self.subbuffer = Some(buf) }
+}
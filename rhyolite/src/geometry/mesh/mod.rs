@@ -1,61 +1,262 @@
-use nalgebra_glm::Vec3;
+use std::path::Path;
+
+use nalgebra_glm::{vec3, Vec3};
 use vulkano::buffer::{Buffer, BufferCreateInfo, Subbuffer};
 use vulkano::memory::allocator::{AllocationCreateInfo, MemoryUsage};
 use vulkano::pipeline::graphics::vertex_input::Vertex;
 use vulkano::buffer::BufferUsage;
 
-use crate::{transform::Transform};
+use crate::{transform::Transform, RhyoliteError};
 
 use crate::renderer::staging::{StagingBuffer, UniformSrc};
 use crate::renderer::Renderer;
 
 /// Utilities for loading vertex and normal data from .obj files
 pub mod loader;
-pub use loader::{BasicVertex, UnlitVertex};
+pub use loader::{
+    BasicVertex, LoadError, NormalGeneration, NormalWeighting, TexturedVertex, UnlitVertex,
+};
+
+/// The surface properties of mesh objects
+pub mod material;
+pub use material::Material;
+
+/// Utilities for loading materials from .mtl files
+pub mod mtl;
+pub use mtl::MtlMaterial;
+
+/// Utilities for importing scenes from glTF files
+#[cfg(feature = "gltf")]
+pub mod scene;
+#[cfg(feature = "gltf")]
+pub use scene::{GltfScene, SceneObject};
+
+/// Images that can be sampled when drawing textured objects
+pub mod texture;
+pub use texture::Texture;
 
 use loader::ModelBuilder;
 use crate::renderer::mesh::MeshRenderer;
-use crate::shaders::{albedo_vert, albedo_frag};
+use crate::shaders::albedo_vert;
 
 pub struct MeshObjectBuilder<T: Vertex> {
     vertices: Vec<T>,
+    indices: Option<Vec<u32>>,
     pub transform: Transform,
-    specular_intensity: f32,
-    shininess: f32,
+    material: Material,
 }
 
 impl MeshObjectBuilder<BasicVertex> {
+    /// Loads a `MeshObjectBuilder` from an .obj file, returning an error if the file can't be
+    /// read or parsed. Identical vertices are merged, so the built object is indexed. If the file
+    /// doesn't contain normals, smooth normals are generated with `NormalGeneration::default()`;
+    /// use `from_file_with_normals()` to choose how they're generated instead.
     pub fn from_file(
-        path: &'static str,
+        path: impl AsRef<Path>,
+        translate: &Vec3,
+        scale: &Vec3,
+        color: &Vec3,
+        specular: (f32, f32),
+    ) -> Result<MeshObjectBuilder<BasicVertex>, LoadError> {
+        let mut model = ModelBuilder::from_file(path, true)?;
+        if !model.has_normals() {
+            model.generate_normals(NormalGeneration::default());
+        }
+        Ok(Self::from_model(&model, translate, scale, color, specular))
+    }
+
+    /// Has the same functionality as `from_file()`, except that normals are always generated
+    /// with the specified method, replacing any normals in the file.
+    pub fn from_file_with_normals(
+        path: impl AsRef<Path>,
+        normals: NormalGeneration,
+        translate: &Vec3,
+        scale: &Vec3,
+        color: &Vec3,
+        specular: (f32, f32),
+    ) -> Result<MeshObjectBuilder<BasicVertex>, LoadError> {
+        let mut model = ModelBuilder::from_file(path, true)?;
+        model.generate_normals(normals);
+        Ok(Self::from_model(&model, translate, scale, color, specular))
+    }
+
+    /// Loads an .obj file and its materials, splitting it into one `MeshObjectBuilder` for each
+    /// material. Each builder's `Material` is converted from its .mtl material, and faces
+    /// without a material use `Material::default()`.
+    ///
+    /// Each builder starts with the same transform, so the objects should be moved together.
+    pub fn from_file_with_materials(
+        path: impl AsRef<Path>,
+        translate: &Vec3,
+        scale: &Vec3,
+    ) -> Result<Vec<MeshObjectBuilder<BasicVertex>>, LoadError> {
+        let mut model = ModelBuilder::from_file(path, true)?;
+        if !model.has_normals() {
+            model.generate_normals(NormalGeneration::default());
+        }
+
+        Ok(model
+            .submeshes()
+            .iter()
+            .map(|submesh| {
+                let material = submesh.material().map(Material::from).unwrap_or_default();
+                let (vertices, indices) = submesh.build_basic_indexed();
+                let transform = initial_transform(translate, scale);
+                MeshObjectBuilder::from_vertices(transform, vertices, material)
+                    .with_indices(indices)
+            })
+            .collect())
+    }
+
+    /// Creates an indexed `MeshObjectBuilder` from a loaded model.
+    pub fn from_model(
+        model: &ModelBuilder,
         translate: &Vec3,
         scale: &Vec3,
         color: &Vec3,
         specular: (f32, f32),
     ) -> MeshObjectBuilder<BasicVertex> {
-        let vertices = ModelBuilder::from_file(path, true).build_basic([color.x, color.y, color.z]);
-        let mut object_transform = Transform::identity();
-        object_transform.set_translation(translate);
-        object_transform.set_scale(scale);
-        MeshObjectBuilder::from_vertices(object_transform, vertices, specular.0, specular.1)
+        let (vertices, indices) = model.build_basic_indexed();
+        let transform = initial_transform(translate, scale);
+        let material = Material::new(*color, specular.0, specular.1);
+        MeshObjectBuilder::from_vertices(transform, vertices, material)
+            .with_indices(indices)
     }
 }
 
+impl MeshObjectBuilder<TexturedVertex> {
+    /// Loads a textured `MeshObjectBuilder` from an .obj file, in the same way as
+    /// `MeshObjectBuilder::from_file()`. The object's color comes from the texture it's drawn
+    /// with, so faces without texture coordinates are mapped to the top left of the texture, and
+    /// its material is white.
+    pub fn from_file_textured(
+        path: impl AsRef<Path>,
+        translate: &Vec3,
+        scale: &Vec3,
+        specular: (f32, f32),
+    ) -> Result<MeshObjectBuilder<TexturedVertex>, LoadError> {
+        let mut model = ModelBuilder::from_file(path, true)?;
+        if !model.has_normals() {
+            model.generate_normals(NormalGeneration::default());
+        }
+        Ok(Self::from_textured_model(&model, translate, scale, specular))
+    }
+
+    /// Has the same functionality as `MeshObjectBuilder::from_file_with_materials()`, except that
+    /// the builders are textured. Each builder is returned alongside its material, whose
+    /// `diffuse_map` can be loaded with `Texture::from_file()`. The diffuse color of the material
+    /// is used as the albedo of the builder's `Material`, which tints the texture.
+    pub fn from_file_textured_with_materials(
+        path: impl AsRef<Path>,
+        translate: &Vec3,
+        scale: &Vec3,
+    ) -> Result<Vec<(MeshObjectBuilder<TexturedVertex>, MtlMaterial)>, LoadError> {
+        let mut model = ModelBuilder::from_file(path, true)?;
+        if !model.has_normals() {
+            model.generate_normals(NormalGeneration::default());
+        }
+
+        Ok(model
+            .submeshes()
+            .iter()
+            .map(|submesh| {
+                let material = submesh.material().cloned().unwrap_or_default();
+                let (vertices, indices) = submesh.build_textured_indexed();
+                let transform = initial_transform(translate, scale);
+                let builder = MeshObjectBuilder::from_vertices(
+                    transform,
+                    vertices,
+                    Material::from(&material),
+                )
+                .with_indices(indices);
+                (builder, material)
+            })
+            .collect())
+    }
+
+    /// Creates an indexed, textured `MeshObjectBuilder` from a loaded model.
+    pub fn from_textured_model(
+        model: &ModelBuilder,
+        translate: &Vec3,
+        scale: &Vec3,
+        specular: (f32, f32),
+    ) -> MeshObjectBuilder<TexturedVertex> {
+        let (vertices, indices) = model.build_textured_indexed();
+        let transform = initial_transform(translate, scale);
+        let material = Material::new(vec3(1.0, 1.0, 1.0), specular.0, specular.1);
+        MeshObjectBuilder::from_vertices(transform, vertices, material)
+            .with_indices(indices)
+    }
+}
+
+impl MeshObjectBuilder<UnlitVertex> {
+    /// Loads an unlit `MeshObjectBuilder` from an .obj file, giving every vertex the same
+    /// `color`. Unlit objects aren't affected by lights, so the file doesn't need normals.
+    pub fn from_file_unlit(
+        path: impl AsRef<Path>,
+        translate: &Vec3,
+        scale: &Vec3,
+        color: &Vec3,
+    ) -> Result<MeshObjectBuilder<UnlitVertex>, LoadError> {
+        let model = ModelBuilder::from_file(path, true)?;
+        Ok(Self::from_unlit_model(&model, translate, scale, color))
+    }
+
+    /// Creates an unlit `MeshObjectBuilder` from a loaded model, giving every vertex the same
+    /// `color`.
+    pub fn from_unlit_model(
+        model: &ModelBuilder,
+        translate: &Vec3,
+        scale: &Vec3,
+        color: &Vec3,
+    ) -> MeshObjectBuilder<UnlitVertex> {
+        let vertices = model.build_unlit([color.x, color.y, color.z]);
+        let transform = initial_transform(translate, scale);
+        MeshObjectBuilder::from_vertices(transform, vertices, Material::default())
+    }
+}
+
+/// Creates the transform of a newly loaded object.
+fn initial_transform(translate: &Vec3, scale: &Vec3) -> Transform {
+    let mut transform = Transform::identity();
+    transform.set_translation(translate);
+    transform.set_scale(scale);
+    transform
+}
+
 impl<T: Vertex> MeshObjectBuilder<T> {
     pub(crate) fn from_vertices(
         transform: Transform,
         vertices: Vec<T>,
-        specular_intensity: f32,
-        shininess: f32,
+        material: Material,
     ) -> Self {
         Self {
             vertices,
+            indices: None,
             transform,
-            specular_intensity,
-            shininess,
+            material,
         }
     }
 
-    pub fn build(self, renderer: &MeshRenderer) -> MeshObject<T> {
+    /// Adds indices into the builder's vertices, so that the built object is drawn with an index
+    /// buffer.
+    pub fn with_indices(mut self, indices: Vec<u32>) -> Self {
+        self.indices = Some(indices);
+        self
+    }
+
+    /// Replaces the material of the built object.
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Uploads the builder's vertices, indices, and material to the GPU, creating a `MeshObject`.
+    pub fn build(self, renderer: &MeshRenderer) -> Result<MeshObject<T>, RhyoliteError> {
         let buffer_allocator = renderer.get_buffer_allocator();
         let base = renderer.get_base();
 
@@ -72,23 +273,38 @@ impl<T: Vertex> MeshObjectBuilder<T> {
             },
             self.vertices
                 .into_iter(),
-        )
-            .unwrap()
-            .into_device_local(num_vertices as u64, &buffer_allocator, &base);
+        )?
+            .into_device_local(num_vertices as u64, &buffer_allocator, &base)?;
+
+        let index_buffer = self.indices.map(|indices| -> Result<_, RhyoliteError> {
+            let num_indices = indices.len();
+            Buffer::from_iter(
+                &buffer_allocator,
+                BufferCreateInfo {
+                    usage: BufferUsage::TRANSFER_SRC | BufferUsage::INDEX_BUFFER,
+                    ..Default::default()
+                },
+                AllocationCreateInfo {
+                    usage: MemoryUsage::Upload,
+                    ..Default::default()
+                },
+                indices.into_iter(),
+            )?
+                .into_device_local(num_indices as u64, &buffer_allocator, &base)
+        }).transpose()?;
+
+        let mut material = self.material;
+        renderer.upload_material(&mut material)?;
 
-        MeshObject::from_vertex_buffer(
-            self.transform, 
-            vertex_buffer, 
-            self.specular_intensity, 
-            self.shininess
-        )
+        let mut object = MeshObject::from_vertex_buffer(self.transform, vertex_buffer, material);
+        object.index_buffer = index_buffer;
+        Ok(object)
     }
 }
 
 pub struct MeshObjectParams {
-    pub specular_intensity: f32,
-    pub shininess: f32,
     pub transform: Transform,
+    pub material: Material,
 }
 
 impl UniformSrc<albedo_vert::UModelData> for MeshObjectParams {
@@ -103,42 +319,52 @@ impl UniformSrc<albedo_vert::UModelData> for MeshObjectParams {
     }
 }
 
-impl UniformSrc<albedo_frag::USpecularData> for MeshObjectParams {
-    /// Gets the raw uniform data of this MeshObject, in the format of `albedo_vert::UModelData`.
-    fn get_raw(&self) -> albedo_frag::USpecularData {
-        albedo_frag::USpecularData { 
-            intensity: self.specular_intensity,
-            shininess: self.shininess,
-        }
-    }
-}
-
-/// An object, containing vertices and other data, that is rendered as a Mesh.
+/// An object, containing vertices and other data, that is rendered as a Mesh. Objects may
+/// optionally have an index buffer, in which case they're drawn with indexed draw calls.
 pub struct MeshObject<T: Vertex> {
     vertex_buffer: Subbuffer<[T]>,
+    index_buffer: Option<Subbuffer<[u32]>>,
     params: MeshObjectParams 
 }
 
 impl<T: Vertex> MeshObject<T> {
+    /// Creates an object from a vertex buffer. The material must be uploaded with
+    /// `MeshRenderer::upload_material()` before the object is drawn with the default
+    /// descriptors.
     pub fn from_vertex_buffer(
         transform: Transform,
         vertex_buffer: Subbuffer<[T]>,
-        specular_intensity: f32,
-        shininess: f32,
+        material: Material,
     ) -> Self {
         Self {
             params: MeshObjectParams {
                 transform,
-                specular_intensity,
-                shininess,
+                material,
             },
             vertex_buffer,
+            index_buffer: None,
         }
     }
 
+    /// Creates an indexed object, whose vertices are drawn in the order specified by
+    /// `index_buffer`.
+    pub fn from_indexed_buffers(
+        transform: Transform,
+        vertex_buffer: Subbuffer<[T]>,
+        index_buffer: Subbuffer<[u32]>,
+        material: Material,
+    ) -> Self {
+        let mut object = Self::from_vertex_buffer(transform, vertex_buffer, material);
+        object.index_buffer = Some(index_buffer);
+        object
+    }
+
     pub(crate) fn vertex_buffer(&self) -> &Subbuffer<[T]> {
         &self.vertex_buffer
     }
+    pub fn index_buffer(&self) -> Option<&Subbuffer<[u32]>> {
+        self.index_buffer.as_ref()
+    }
     pub(crate) fn params(&self) -> &MeshObjectParams {
         &self.params
     }
@@ -148,4 +374,25 @@ impl<T: Vertex> MeshObject<T> {
     pub fn transform_mut(&mut self) -> &mut Transform {
         &mut self.params.transform
     }
+    pub fn material(&self) -> &Material {
+        &self.params.material
+    }
+
+    /// Replaces the object's material, uploading it if it has changed since it was last
+    /// uploaded. To change a single property, clone the current material and modify it:
+    ///
+    /// ```ignore
+    /// let mut material = object.material().clone();
+    /// material.set_albedo(vec3(1.0, 0.0, 0.0));
+    /// object.set_material(material, &renderer)?;
+    /// ```
+    pub fn set_material(
+        &mut self,
+        mut material: Material,
+        renderer: &MeshRenderer,
+    ) -> Result<(), RhyoliteError> {
+        renderer.upload_material(&mut material)?;
+        self.params.material = material;
+        Ok(())
+    }
 }
\ No newline at end of file
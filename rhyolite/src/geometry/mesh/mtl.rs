@@ -0,0 +1,154 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use super::loader::LoadError;
+
+/// A material loaded from an .mtl file. Only the properties used by Rhyolite's default pipelines
+/// are loaded, and the rest are ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MtlMaterial {
+    /// The name of the material, as declared with `newmtl`
+    pub name: String,
+    /// The diffuse color of the material (`Kd`)
+    pub diffuse: [f32; 3],
+    /// The specular color of the material (`Ks`)
+    pub specular: [f32; 3],
+    /// The specular exponent of the material (`Ns`)
+    pub shininess: f32,
+    /// The path to the diffuse texture of the material (`map_Kd`), relative to the working
+    /// directory rather than the .mtl file
+    pub diffuse_map: Option<PathBuf>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            diffuse: [1.0, 1.0, 1.0],
+            specular: [0.0, 0.0, 0.0],
+            shininess: 1.0,
+            diffuse_map: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    /// Gets the intensity of the material's specular highlights, which is the average of the
+    /// components of its specular color.
+    pub fn specular_intensity(&self) -> f32 {
+        self.specular.iter().sum::<f32>() / 3.0
+    }
+
+    /// Loads all of the materials from an .mtl file at the specified path.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Vec<Self>, LoadError> {
+        let path = path.as_ref();
+        let data = File::open(path)?;
+        Self::from_reader(data, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Loads all of the materials from any source of .mtl data. Texture paths are resolved
+    /// relative to `directory`, which should usually be the directory containing the .mtl file.
+    pub fn from_reader(reader: impl Read, directory: impl AsRef<Path>) -> Result<Vec<Self>, LoadError> {
+        let buffered_data = BufReader::new(reader);
+        let mut materials: Vec<MtlMaterial> = Vec::new();
+
+        for (i, line) in buffered_data.lines().enumerate() {
+            let line = line?;
+            let parse_error = |message: String| LoadError::Parse { line: i + 1, message };
+
+            // Strip comments and surrounding whitespace
+            let line = line.split('#').next().unwrap_or("").trim();
+            let (keyword, val) = line
+                .split_once(char::is_whitespace)
+                .unwrap_or((line, ""));
+
+            if keyword == "newmtl" {
+                materials.push(MtlMaterial {
+                    name: String::from(val.trim()),
+                    ..Default::default()
+                });
+                continue;
+            }
+            if !matches!(keyword, "Kd" | "Ks" | "Ns" | "map_Kd") {
+                continue;
+            }
+
+            let material = materials
+                .last_mut()
+                .ok_or_else(|| parse_error(format!("\"{}\" appears before any \"newmtl\"", keyword)))?;
+            match keyword {
+                "Kd" => material.diffuse = parse_color(val).map_err(parse_error)?,
+                "Ks" => material.specular = parse_color(val).map_err(parse_error)?,
+                "Ns" => material.shininess = parse_float(val.trim()).map_err(parse_error)?,
+                "map_Kd" => {
+                    let file = texture_file_name(val).map_err(parse_error)?;
+                    material.diffuse_map = Some(directory.as_ref().join(file));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(materials)
+    }
+}
+
+/// Parses a color in the format `r g b`. If only `r` is specified, it's used for all three
+/// components.
+fn parse_color(input: &str) -> Result<[f32; 3], String> {
+    let components: Vec<f32> = input
+        .split_whitespace()
+        .map(parse_float)
+        .collect::<Result<_, _>>()?;
+    match components[..] {
+        [r] => Ok([r, r, r]),
+        [r, g, b] => Ok([r, g, b]),
+        _ => Err(format!("expected 1 or 3 color components, found {}", components.len())),
+    }
+}
+
+/// Gets the file name from the arguments of a texture map statement. Texture options (e.g.
+/// `-s 1 1 1`) precede the file name and are skipped, and everything after them is the file name,
+/// which may contain spaces.
+fn texture_file_name(input: &str) -> Result<&str, String> {
+    let mut rest = input.trim();
+    while let Some(option) = rest.split_whitespace().next().filter(|item| item.starts_with('-')) {
+        // The minimum and maximum number of values each option takes
+        let (min_values, max_values) = match option {
+            "-o" | "-s" | "-t" => (1, 3),
+            "-mm" => (2, 2),
+            "-blendu" | "-blendv" | "-bm" | "-boost" | "-cc" | "-clamp" | "-imfchan" | "-texres"
+            | "-type" => (1, 1),
+            _ => return Err(format!("unknown texture option \"{}\"", option)),
+        };
+        rest = rest[option.len()..].trim_start();
+
+        for i in 0..max_values {
+            let value = rest.split_whitespace().next();
+            // Optional values are always numbers, so anything else is the start of the file name
+            let is_file_name =
+                i >= min_values && !value.is_some_and(|v| v.parse::<f32>().is_ok());
+            match value {
+                Some(value) if !is_file_name => rest = rest[value.len()..].trim_start(),
+                None if i < min_values => {
+                    return Err(format!("texture option \"{}\" is missing a value", option))
+                }
+                _ => break,
+            }
+        }
+    }
+
+    if rest.is_empty() {
+        Err(String::from("\"map_Kd\" has no file name"))
+    } else {
+        Ok(rest)
+    }
+}
+
+fn parse_float(input: &str) -> Result<f32, String> {
+    input
+        .parse()
+        .map_err(|_| format!("unable to parse element \"{}\"", input))
+}
@@ -0,0 +1,543 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use gltf::camera::Projection;
+use gltf::khr_lights_punctual::Kind;
+use gltf::mesh::Mode;
+use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+use nalgebra_glm::{make_mat4, make_vec3, vec3, Mat4};
+use vulkano::sampler::{
+    Filter, Sampler, SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode, LOD_CLAMP_NONE,
+};
+
+use crate::camera::Camera;
+use crate::lighting::{DirectionalLight, PointLight, SpotLight};
+use crate::renderer::mesh::{DrawInfo, MeshRenderer};
+use crate::renderer::Renderer;
+use crate::transform::Transform;
+use crate::RhyoliteError;
+
+use super::loader::{LoadError, ModelBuilder, NormalGeneration};
+use super::{BasicVertex, Material, MeshObject, MeshObjectBuilder, Texture, TexturedVertex};
+
+/// The far clipping plane used for glTF cameras without one
+const DEFAULT_FAR_CLIPPING_PLANE: f32 = 1000.0;
+
+/// A scene imported from a glTF (.gltf or .glb) file, containing its objects, cameras, and
+/// lights. Everything in the scene is placed with the transform of the node it belongs to.
+pub struct GltfScene {
+    /// One object for each primitive of each mesh in the scene
+    pub objects: Vec<SceneObject>,
+    /// The scene's perspective cameras. Orthographic cameras aren't supported, and are skipped.
+    pub cameras: Vec<Camera>,
+    /// The scene's point lights
+    pub point_lights: Vec<PointLight>,
+    /// The scene's directional lights
+    pub directional_lights: Vec<DirectionalLight>,
+    /// The scene's spot lights
+    pub spot_lights: Vec<SpotLight>,
+}
+
+/// An object imported from a glTF file. Primitives with a base color texture and texture
+/// coordinates are imported as textured objects, and all others are colored with the base color
+/// of their material.
+pub enum SceneObject {
+    Basic(MeshObject<BasicVertex>),
+    Textured(MeshObject<TexturedVertex>, Texture),
+}
+
+impl SceneObject {
+    pub fn transform(&self) -> &Transform {
+        match self {
+            SceneObject::Basic(object) => object.transform(),
+            SceneObject::Textured(object, _) => object.transform(),
+        }
+    }
+    pub fn transform_mut(&mut self) -> &mut Transform {
+        match self {
+            SceneObject::Basic(object) => object.transform_mut(),
+            SceneObject::Textured(object, _) => object.transform_mut(),
+        }
+    }
+
+    /// Draws the object with the default lit or textured pipeline.
+    ///
+    /// # Errors
+    /// - Returns `RhyoliteError::StageOrder` if not called after a `start()` call or another draw
+    /// call for lit objects.
+    pub fn draw(&self, renderer: &mut MeshRenderer) -> Result<(), RhyoliteError> {
+        match self {
+            SceneObject::Basic(object) => renderer.draw_lit_auto(DrawInfo::Vertex { object }),
+            SceneObject::Textured(object, texture) => {
+                renderer.draw_textured_auto(DrawInfo::Vertex { object }, texture)
+            }
+        }
+    }
+}
+
+impl GltfScene {
+    /// Imports the default scene of a glTF file, or its first scene if it doesn't specify a
+    /// default. Buffers and images are only loaded from local files or embedded data.
+    ///
+    /// Materials are mapped onto Rhyolite's Phong shading as follows:
+    /// - The base color factor is used as the albedo of each object's `Material`.
+    /// - The base color texture is used as the texture of textured objects, tinted by the albedo.
+    /// - Smoother materials get brighter, tighter specular highlights, and metallic materials get
+    /// brighter highlights than dielectric ones.
+    ///
+    /// Primitives without normals are given flat normals, as required by the glTF specification.
+    /// Point and line primitives are skipped.
+    pub fn from_file(path: impl AsRef<Path>, renderer: &MeshRenderer) -> Result<Self, RhyoliteError> {
+        let (document, buffers, images) = gltf::import(path).map_err(LoadError::from)?;
+
+        let mut importer = Importer {
+            renderer,
+            buffers: &buffers,
+            images: &images,
+            textures: HashMap::new(),
+            scene: GltfScene {
+                objects: Vec::new(),
+                cameras: Vec::new(),
+                point_lights: Vec::new(),
+                directional_lights: Vec::new(),
+                spot_lights: Vec::new(),
+            },
+        };
+
+        if let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) {
+            walk_nodes(scene.nodes(), &Mat4::identity(), &mut |node, matrix| {
+                importer.import_node(node, matrix)
+            })?;
+        }
+
+        Ok(importer.scene)
+    }
+
+    /// Adds a light to the scene, placed with the world matrix of its node. glTF lights shine
+    /// down their node's -Z axis, and point and spot lights fade out at their range if they
+    /// have one.
+    fn add_light(&mut self, light: &gltf::khr_lights_punctual::Light, matrix: &Mat4) {
+        let color = make_vec3(&light.color());
+        let position = matrix.column(3).xyz();
+        let direction = -matrix.column(2).xyz().normalize();
+        match light.kind() {
+            Kind::Point => {
+                let mut point_light = PointLight::new(position, color, light.intensity());
+                if let Some(range) = light.range() {
+                    point_light = point_light.with_radius(range);
+                }
+                self.point_lights.push(point_light);
+            }
+            Kind::Directional => self
+                .directional_lights
+                .push(DirectionalLight::new(direction, color, light.intensity())),
+            Kind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => {
+                // glTF requires the outer cone to be wider than the inner one, but files that
+                // break this are widened by `SpotLight::new()` rather than rejected
+                let mut spot_light = SpotLight::new(
+                    position,
+                    direction,
+                    inner_cone_angle,
+                    outer_cone_angle,
+                    color,
+                    light.intensity(),
+                );
+                if let Some(range) = light.range() {
+                    spot_light = spot_light.with_range(range);
+                }
+                self.spot_lights.push(spot_light);
+            }
+        }
+    }
+
+    /// Draws every object in the scene with the default lit or textured pipeline.
+    ///
+    /// # Errors
+    /// - Returns `RhyoliteError::StageOrder` if not called after a `start()` call or another draw
+    /// call for lit objects.
+    pub fn draw_objects(&self, renderer: &mut MeshRenderer) -> Result<(), RhyoliteError> {
+        for object in &self.objects {
+            object.draw(renderer)?;
+        }
+        Ok(())
+    }
+}
+
+/// The state of a glTF import in progress
+struct Importer<'a> {
+    renderer: &'a MeshRenderer,
+    buffers: &'a [gltf::buffer::Data],
+    images: &'a [gltf::image::Data],
+    /// Textures that have already been uploaded, by glTF texture index
+    textures: HashMap<usize, Texture>,
+    scene: GltfScene,
+}
+
+impl Importer<'_> {
+    /// Imports a single node, given its world matrix. Its children are imported separately.
+    fn import_node(&mut self, node: &gltf::Node, matrix: &Mat4) -> Result<(), RhyoliteError> {
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                if let Some(object) = self.import_primitive(&primitive, matrix)? {
+                    self.scene.objects.push(object);
+                }
+            }
+        }
+
+        if let Some(camera) = node.camera() {
+            if let Projection::Perspective(perspective) = camera.projection() {
+                self.scene.cameras.push(Camera::new(
+                    Transform::from_matrix(matrix),
+                    perspective.yfov(),
+                    perspective.znear(),
+                    perspective.zfar().unwrap_or(DEFAULT_FAR_CLIPPING_PLANE),
+                ));
+            }
+        }
+
+        if let Some(light) = node.light() {
+            self.scene.add_light(&light, matrix);
+        }
+        Ok(())
+    }
+
+    /// Imports a single primitive of a mesh, returning `None` if it isn't made of triangles.
+    fn import_primitive(
+        &mut self,
+        primitive: &gltf::Primitive,
+        matrix: &Mat4,
+    ) -> Result<Option<SceneObject>, RhyoliteError> {
+        let buffers = self.buffers;
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+        let positions: Vec<[f32; 3]> = match reader.read_positions() {
+            Some(positions) => positions.collect(),
+            None => return Ok(None),
+        };
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        let indices = match triangle_list(primitive.mode(), indices) {
+            Some(indices) => indices,
+            None => return Ok(None),
+        };
+        let normals = reader.read_normals().map(|normals| normals.collect());
+
+        let material = primitive.material();
+        let pbr = material.pbr_metallic_roughness();
+        let base_color = pbr.base_color_factor();
+        let roughness = pbr.roughness_factor().clamp(0.0, 1.0);
+        let metallic = pbr.metallic_factor().clamp(0.0, 1.0);
+        let specular_intensity = (1.0 - roughness) * (0.5 + 0.5 * metallic);
+        // The Blinn-Phong exponent that best approximates a GGX distribution with this roughness
+        let shininess = (2.0 / roughness.powi(4).max(f32::EPSILON) - 2.0).clamp(1.0, 1024.0);
+        // The base color factor also tints the base color texture, if there is one
+        let albedo = vec3(base_color[0], base_color[1], base_color[2]);
+        let material = Material::new(albedo, specular_intensity, shininess);
+
+        // Cull mode depends on winding order, so mirrored nodes need to be inverted again, in
+        // addition to the inversion that's done for all models
+        let invert = matrix.fixed_view::<3, 3>(0, 0).determinant() >= 0.0;
+
+        let textured = pbr.base_color_texture().and_then(|info| {
+            let tex_coords = reader.read_tex_coords(info.tex_coord())?;
+            Some((info.texture(), tex_coords.into_f32()))
+        });
+
+        let (mut model, texture) = match textured {
+            Some((texture, tex_coords)) => {
+                // glTF texture coordinates start from the top left, unlike those in .obj files
+                let tex_coords = tex_coords.map(|[u, v]| [u, 1.0 - v]).collect();
+                let model = ModelBuilder::from_triangles(
+                    positions,
+                    normals,
+                    Some(tex_coords),
+                    &indices,
+                    invert,
+                );
+                (model, Some(self.texture(&texture)?))
+            }
+            None => {
+                let model = ModelBuilder::from_triangles(positions, normals, None, &indices, invert);
+                (model, None)
+            }
+        };
+        if !model.has_normals() {
+            model.generate_normals(NormalGeneration::Flat);
+        }
+
+        let transform = Transform::from_matrix(matrix);
+        let object = match texture {
+            Some(texture) => {
+                let (vertices, indices) = model.build_textured_indexed();
+                let object = MeshObjectBuilder::from_vertices(transform, vertices, material)
+                    .with_indices(indices)
+                    .build(self.renderer)?;
+                SceneObject::Textured(object, texture)
+            }
+            None => {
+                let (vertices, indices) = model.build_basic_indexed();
+                let object = MeshObjectBuilder::from_vertices(transform, vertices, material)
+                    .with_indices(indices)
+                    .build(self.renderer)?;
+                SceneObject::Basic(object)
+            }
+        };
+        Ok(Some(object))
+    }
+
+    /// Gets the texture with the specified glTF texture index, uploading it if it hasn't been
+    /// used yet.
+    fn texture(&mut self, texture: &gltf::Texture) -> Result<Texture, RhyoliteError> {
+        if let Some(loaded) = self.textures.get(&texture.index()) {
+            return Ok(loaded.clone());
+        }
+
+        let image = &self.images[texture.source().index()];
+        let data = to_rgba8(image);
+        let sampler = texture.sampler();
+        let filter = |linear: bool| if linear { Filter::Linear } else { Filter::Nearest };
+        let address_mode = |mode: WrappingMode| match mode {
+            WrappingMode::ClampToEdge => SamplerAddressMode::ClampToEdge,
+            WrappingMode::MirroredRepeat => SamplerAddressMode::MirroredRepeat,
+            WrappingMode::Repeat => SamplerAddressMode::Repeat,
+        };
+        let (min_filter, mipmap_mode) = match sampler.min_filter() {
+            Some(MinFilter::Nearest | MinFilter::NearestMipmapNearest) => {
+                (Filter::Nearest, SamplerMipmapMode::Nearest)
+            }
+            Some(MinFilter::NearestMipmapLinear) => (Filter::Nearest, SamplerMipmapMode::Linear),
+            Some(MinFilter::LinearMipmapNearest) => (Filter::Linear, SamplerMipmapMode::Nearest),
+            Some(MinFilter::Linear | MinFilter::LinearMipmapLinear) | None => {
+                (Filter::Linear, SamplerMipmapMode::Linear)
+            }
+        };
+        let sampler = Sampler::new(
+            self.renderer.get_base().get_device(),
+            SamplerCreateInfo {
+                mag_filter: filter(sampler.mag_filter() != Some(MagFilter::Nearest)),
+                min_filter,
+                mipmap_mode,
+                address_mode: [
+                    address_mode(sampler.wrap_s()),
+                    address_mode(sampler.wrap_t()),
+                    SamplerAddressMode::Repeat,
+                ],
+                lod: 0.0..=LOD_CLAMP_NONE,
+                ..Default::default()
+            },
+        )?;
+
+        let loaded = Texture::from_rgba8(image.width, image.height, data, self.renderer)?
+            .with_sampler(sampler);
+        self.textures.insert(texture.index(), loaded.clone());
+        Ok(loaded)
+    }
+}
+
+/// Visits each of the nodes and all of their descendants, parents before their children, along
+/// with their world matrices. `parent_matrix` is the world matrix of the nodes' parent.
+fn walk_nodes<'a, E>(
+    nodes: impl Iterator<Item = gltf::Node<'a>>,
+    parent_matrix: &Mat4,
+    visit: &mut impl FnMut(&gltf::Node<'a>, &Mat4) -> Result<(), E>,
+) -> Result<(), E> {
+    for node in nodes {
+        let matrix = parent_matrix * make_mat4(&node.transform().matrix().concat());
+        visit(&node, &matrix)?;
+        walk_nodes(node.children(), &matrix, visit)?;
+    }
+    Ok(())
+}
+
+/// Converts the indices of a primitive with the specified mode into a triangle list, returning
+/// `None` if the primitive isn't made of triangles.
+fn triangle_list(mode: Mode, indices: Vec<u32>) -> Option<Vec<u32>> {
+    match mode {
+        Mode::Triangles => Some(indices),
+        Mode::TriangleStrip => Some(
+            (0..indices.len().saturating_sub(2))
+                .flat_map(|i| {
+                    // Every other triangle in a strip has the opposite winding order
+                    if i % 2 == 0 {
+                        [indices[i], indices[i + 1], indices[i + 2]]
+                    } else {
+                        [indices[i + 1], indices[i], indices[i + 2]]
+                    }
+                })
+                .collect(),
+        ),
+        Mode::TriangleFan => Some(
+            (1..indices.len().saturating_sub(1))
+                .flat_map(|i| [indices[0], indices[i], indices[i + 1]])
+                .collect(),
+        ),
+        Mode::Points | Mode::Lines | Mode::LineLoop | Mode::LineStrip => None,
+    }
+}
+
+/// Converts a decoded glTF image to tightly packed RGBA8 data.
+fn to_rgba8(image: &gltf::image::Data) -> Vec<u8> {
+    use gltf::image::Format;
+
+    // Channels are stored in native byte order. 16-bit channels are narrowed to their most
+    // significant byte, and floating-point channels are clamped to [0, 1].
+    let (channels, channel_size, convert): (usize, usize, fn(&[u8]) -> u8) = match image.format {
+        Format::R8 => (1, 1, |c| c[0]),
+        Format::R8G8 => (2, 1, |c| c[0]),
+        Format::R8G8B8 => (3, 1, |c| c[0]),
+        Format::R8G8B8A8 => (4, 1, |c| c[0]),
+        Format::R16 => (1, 2, narrow_u16),
+        Format::R16G16 => (2, 2, narrow_u16),
+        Format::R16G16B16 => (3, 2, narrow_u16),
+        Format::R16G16B16A16 => (4, 2, narrow_u16),
+        Format::R32G32B32FLOAT => (3, 4, narrow_f32),
+        Format::R32G32B32A32FLOAT => (4, 4, narrow_f32),
+    };
+
+    image
+        .pixels
+        .chunks_exact(channels * channel_size)
+        .flat_map(|px| {
+            let channel = |i: usize| convert(&px[i * channel_size..(i + 1) * channel_size]);
+            match channels {
+                1 => [channel(0), channel(0), channel(0), 255],
+                2 => [channel(0), channel(1), 0, 255],
+                3 => [channel(0), channel(1), channel(2), 255],
+                _ => [channel(0), channel(1), channel(2), channel(3)],
+            }
+        })
+        .collect()
+}
+
+fn narrow_u16(bytes: &[u8]) -> u8 {
+    (u16::from_ne_bytes([bytes[0], bytes[1]]) >> 8) as u8
+}
+
+fn narrow_f32(bytes: &[u8]) -> u8 {
+    let value = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{rotate_y, scale, translate, vec4, Vec3};
+
+    fn assert_mat_close(a: &Mat4, b: &Mat4) {
+        assert!((a - b).abs().max() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn strips_and_fans_are_converted_to_lists() {
+        let indices = vec![0, 1, 2, 3, 4];
+        assert_eq!(
+            triangle_list(Mode::Triangles, indices.clone()),
+            Some(indices.clone())
+        );
+        assert_eq!(
+            triangle_list(Mode::TriangleStrip, indices.clone()),
+            Some(vec![0, 1, 2, 2, 1, 3, 2, 3, 4])
+        );
+        assert_eq!(
+            triangle_list(Mode::TriangleFan, indices.clone()),
+            Some(vec![0, 1, 2, 0, 2, 3, 0, 3, 4])
+        );
+        assert_eq!(triangle_list(Mode::TriangleStrip, vec![0, 1]), Some(vec![]));
+        assert_eq!(triangle_list(Mode::TriangleFan, vec![]), Some(vec![]));
+        assert_eq!(triangle_list(Mode::Lines, indices), None);
+    }
+
+    #[test]
+    fn transforms_reproduce_their_matrix() {
+        let matrix = translate(&Mat4::identity(), &vec3(1.0, -2.0, 3.0));
+        let matrix = rotate_y(&matrix, 0.75);
+        let matrix = scale(&matrix, &vec3(2.0, 0.5, -1.0));
+
+        let transform = Transform::from_matrix(&matrix);
+        assert_eq!(transform.get_translation(), vec3(1.0, -2.0, 3.0));
+        assert_mat_close(&transform.get_matrices().0, &matrix);
+
+        // Axes with no scale can't be split into a rotation, but the matrix is still preserved
+        let flat = scale(&matrix, &vec3(1.0, 0.0, 1.0));
+        assert_mat_close(&Transform::from_matrix(&flat).get_matrices().0, &flat);
+    }
+
+    #[test]
+    fn nodes_are_walked_with_world_matrices() {
+        let document = gltf::Gltf::from_slice(
+            br#"{
+                "asset": { "version": "2.0" },
+                "scene": 0,
+                "scenes": [{ "nodes": [0, 3] }],
+                "nodes": [
+                    { "name": "root", "translation": [1, 0, 0], "children": [1] },
+                    { "name": "child", "scale": [2, 2, 2], "children": [2] },
+                    { "name": "grandchild", "translation": [0, 1, 0] },
+                    { "name": "sibling" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let scene = document.default_scene().unwrap();
+
+        let mut visited = Vec::new();
+        walk_nodes(scene.nodes(), &Mat4::identity(), &mut |node, matrix| {
+            visited.push((node.name().unwrap().to_owned(), matrix * vec4(0.0, 0.0, 0.0, 1.0)));
+            Ok::<(), ()>(())
+        })
+        .unwrap();
+
+        let names: Vec<&str> = visited.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["root", "child", "grandchild", "sibling"]);
+        // The grandchild's translation is scaled by its parent before being moved by the root
+        assert_eq!(visited[2].1, vec4(1.0, 2.0, 0.0, 1.0));
+        assert_eq!(visited[3].1, vec4(0.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn lights_keep_their_kind() {
+        let document = gltf::Gltf::open(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/lights.gltf"
+        ))
+        .unwrap();
+        let mut scene = GltfScene {
+            objects: Vec::new(),
+            cameras: Vec::new(),
+            point_lights: Vec::new(),
+            directional_lights: Vec::new(),
+            spot_lights: Vec::new(),
+        };
+        walk_nodes(
+            document.default_scene().unwrap().nodes(),
+            &Mat4::identity(),
+            &mut |node, matrix| {
+                if let Some(light) = node.light() {
+                    scene.add_light(&light, matrix);
+                }
+                Ok::<(), ()>(())
+            },
+        )
+        .unwrap();
+
+        let assert_vec_close = |a: &Vec3, b: Vec3| assert!((a - b).norm() < 1e-5, "{} != {}", a, b);
+
+        assert_eq!(scene.directional_lights.len(), 1);
+        assert_vec_close(scene.directional_lights[0].direction(), vec3(0.0, -1.0, 0.0));
+
+        assert_eq!(scene.point_lights.len(), 1);
+        assert_eq!(*scene.point_lights[0].position(), vec3(0.0, 2.0, 0.0));
+        assert_eq!(scene.point_lights[0].radius(), Some(5.0));
+
+        assert_eq!(scene.spot_lights.len(), 1);
+        let spot = &scene.spot_lights[0];
+        assert_vec_close(spot.position(), vec3(1.0, 3.0, 0.0));
+        assert_vec_close(spot.direction(), vec3(0.0, -1.0, 0.0));
+        assert_eq!(spot.cone_angles(), (0.25, 0.5));
+        assert_eq!(spot.range(), Some(10.0));
+    }
+}
@@ -0,0 +1,100 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use vulkano::format::Format;
+use vulkano::image::view::ImageView;
+use vulkano::image::{ImageAccess, ImageDimensions, ImmutableImage, MipmapsCount};
+use vulkano::sampler::{Sampler, SamplerCreateInfo};
+
+use crate::renderer::mesh::MeshRenderer;
+use crate::renderer::staging::create_immutable_image;
+use crate::renderer::Renderer;
+use crate::RhyoliteError;
+
+use super::LoadError;
+
+/// An image stored on the GPU, paired with the sampler used to read it in shaders. Textures are
+/// stored in sRGB, so their colors are converted to linear values when they're sampled.
+#[derive(Clone)]
+pub struct Texture {
+    view: Arc<ImageView<ImmutableImage>>,
+    sampler: Arc<Sampler>,
+}
+
+impl Texture {
+    /// Loads a texture from an image file. Any format supported by the `image` crate with the
+    /// features enabled by Rhyolite (PNG and JPEG) can be loaded.
+    pub fn from_file(path: impl AsRef<Path>, renderer: &MeshRenderer) -> Result<Self, RhyoliteError> {
+        let image = image::open(path).map_err(LoadError::from)?.into_rgba8();
+        let (width, height) = image.dimensions();
+        Self::from_rgba8(width, height, image.into_raw(), renderer)
+    }
+
+    /// Creates a texture from tightly packed, sRGB-encoded RGBA8 data, ordered row by row from
+    /// the top left. Mipmaps are generated automatically, and the texture is sampled with
+    /// linear filtering and repeating texture coordinates.
+    ///
+    /// # Errors
+    /// - Returns `RhyoliteError::Allocation` if the length of `data` is not equal to
+    /// `width * height * 4`, or if the image can't be created.
+    pub fn from_rgba8(
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+        renderer: &MeshRenderer,
+    ) -> Result<Self, RhyoliteError> {
+        let expected_len = width as usize * height as usize * 4;
+        if data.len() != expected_len {
+            return Err(RhyoliteError::Allocation(
+                format!(
+                    "expected {} bytes of RGBA8 data for a {}x{} texture, found {}",
+                    expected_len,
+                    width,
+                    height,
+                    data.len()
+                )
+                .into(),
+            ));
+        }
+
+        let image = create_immutable_image(
+            data,
+            ImageDimensions::Dim2d {
+                width,
+                height,
+                array_layers: 1,
+            },
+            MipmapsCount::Log2,
+            Format::R8G8B8A8_SRGB,
+            &renderer.get_buffer_allocator(),
+            renderer.get_base(),
+        )?;
+
+        let sampler = Sampler::new(
+            renderer.get_base().get_device(),
+            SamplerCreateInfo::simple_repeat_linear(),
+        )?;
+
+        Ok(Self {
+            view: ImageView::new_default(image)?,
+            sampler,
+        })
+    }
+
+    /// Replaces the sampler used to read this texture, for example to use nearest-neighbor
+    /// filtering or clamp texture coordinates to the edges of the image.
+    pub fn with_sampler(mut self, sampler: Arc<Sampler>) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    pub fn view(&self) -> &Arc<ImageView<ImmutableImage>> {
+        &self.view
+    }
+    pub fn sampler(&self) -> &Arc<Sampler> {
+        &self.sampler
+    }
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.view.image().dimensions().width_height()
+    }
+}
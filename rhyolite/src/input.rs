@@ -16,7 +16,11 @@ impl Keyboard {
         }
     }
     pub(crate) fn add_input(&mut self, input: &winit::event::KeyboardInput) {
-        let keycode = input.virtual_keycode.unwrap();
+        // Keys that winit can't identify don't have a virtual keycode, and can't be queried
+        let keycode = match input.virtual_keycode {
+            Some(keycode) => keycode,
+            None => return,
+        };
 
         if input.state == ElementState::Pressed {
             // Check if the key is already held before inserting to prevent key repeats from registering
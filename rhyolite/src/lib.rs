@@ -3,10 +3,15 @@
 extern crate core;
 
 use core::time;
+use std::path::PathBuf;
 use std::time::Instant;
 
 use crate::input::Keyboard;
-use renderer::Renderer;
+use renderer::tonemap::Tonemapping;
+use renderer::{DeviceSelector, RenderConfig, Renderer};
+use vulkano::format::Format;
+use vulkano::image::SampleCount;
+use vulkano::swapchain::PresentMode;
 use winit::{
     event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
@@ -22,6 +27,7 @@ use crate::renderer::marched::MarchedRenderer;
 // pub use { vulkano, vulkano_shaders };
 
 pub mod camera;
+pub mod error;
 pub mod geometry;
 pub mod input;
 pub mod lighting;
@@ -29,45 +35,169 @@ pub mod renderer;
 pub mod shaders;
 pub mod transform;
 
-// TODO: implement frames in flight if not implemented in the tutorial
+pub use error::RhyoliteError;
+
 // TODO: replace PersistentDescriptorSet instances with a type expected to be shorter-lived
 
 /// The base struct of all Rhyolite operations.
 pub struct Rhyolite<T: Renderer> {
     pub renderer: T,
-    event_loop: Option<EventLoop<()>>,
+    event_loop: EventLoop<()>,
 }
 
 #[cfg(feature = "mesh")]
 impl Rhyolite<MeshRenderer> {
-    /// Creates a new Rhyolite Mesh renderer with a specified Winit event loop.
-    pub fn mesh() -> Rhyolite<MeshRenderer> {
-        let event_loop = EventLoop::new();
-        let renderer = MeshRenderer::new(&event_loop);
-        Rhyolite {
-            renderer,
-            event_loop: Some(event_loop),
-        }
+    /// Creates a new Rhyolite Mesh renderer with the default settings of `RhyoliteBuilder`.
+    pub fn mesh() -> Result<Rhyolite<MeshRenderer>, RhyoliteError> {
+        RhyoliteBuilder::new().mesh()
     }
 }
 
 #[cfg(feature = "marched")]
 impl Rhyolite<MarchedRenderer> {
-    /// Creates a new Rhyolite ray ray_marching renderer with a specified Winit event loop.
-    pub fn ray_marched() -> Rhyolite<MarchedRenderer> {
+    /// Creates a new Rhyolite ray ray_marching renderer with the default settings of
+    /// `RhyoliteBuilder`.
+    pub fn ray_marched() -> Result<Rhyolite<MarchedRenderer>, RhyoliteError> {
+        RhyoliteBuilder::new().ray_marched()
+    }
+}
+
+/// Configures the window and swapchain of a `Rhyolite` instance before it's created. Settings
+/// that aren't supported by the surface, such as an unavailable present mode, fall back to
+/// supported ones.
+///
+/// ```ignore
+/// let rhyolite = RhyoliteBuilder::new()
+///     .with_title("Fluids")
+///     .with_window_size(1280, 720)
+///     .with_present_mode(PresentMode::Mailbox)
+///     .mesh()?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RhyoliteBuilder {
+    config: RenderConfig,
+}
+
+impl RhyoliteBuilder {
+    /// Creates a builder with the default settings: a resizable 250x250 window titled
+    /// "rhyolite", with FIFO presentation (vsync) and a black clear color.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the inner size of the window, in logical pixels.
+    pub fn with_window_size(mut self, width: u32, height: u32) -> Self {
+        self.config.window_size = [width, height];
+        self
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.config.title = title.into();
+        self
+    }
+
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.config.resizable = resizable;
+        self
+    }
+
+    /// Makes the window a borderless fullscreen window on the current monitor.
+    pub fn with_fullscreen(mut self, fullscreen: bool) -> Self {
+        self.config.fullscreen = fullscreen;
+        self
+    }
+
+    /// Sets the present mode of the swapchain. `PresentMode::Fifo` waits for vertical sync,
+    /// `PresentMode::Mailbox` replaces queued frames with newer ones without tearing, and
+    /// `PresentMode::Immediate` presents frames as soon as they're finished.
+    pub fn with_present_mode(mut self, present_mode: PresentMode) -> Self {
+        self.config.present_mode = present_mode;
+        self
+    }
+
+    /// Sets the preferred format of the swapchain images.
+    pub fn with_surface_format(mut self, format: Format) -> Self {
+        self.config.surface_format = Some(format);
+        self
+    }
+
+    /// Sets the minimum number of swapchain images.
+    pub fn with_image_count(mut self, image_count: u32) -> Self {
+        self.config.image_count = Some(image_count);
+        self
+    }
+
+    /// Sets the color that's visible wherever nothing is drawn, in linear color space. See
+    /// `RenderConfig::clear_color`.
+    pub fn with_clear_color(mut self, clear_color: [f32; 4]) -> Self {
+        self.config.clear_color = clear_color;
+        self
+    }
+
+    /// Sets how the HDR lighting of each frame is mapped into the range of the final image.
+    pub fn with_tonemapping(mut self, tonemapping: Tonemapping) -> Self {
+        self.config.tonemapping = tonemapping;
+        self
+    }
+
+    /// Enables multisample anti-aliasing in the Mesh renderer, with `samples` samples per pixel.
+    /// The count is lowered automatically if the device doesn't support it.
+    pub fn with_msaa(mut self, samples: SampleCount) -> Self {
+        self.config.samples = samples;
+        self
+    }
+
+    /// Sets which physical device to render with. By default, discrete GPUs are preferred.
+    pub fn with_device(mut self, device: DeviceSelector) -> Self {
+        self.config.device = device;
+        self
+    }
+
+    /// Enables the Vulkan validation layer, which reports mistakes in Vulkan usage through the
+    /// `log` crate, and labels each rendering stage in the command buffer for debugging tools.
+    pub fn with_validation(mut self, validation: bool) -> Self {
+        self.config.validation = validation;
+        self
+    }
+
+    /// Sets the file that compiled pipelines are cached in between runs, which makes startup
+    /// faster after the first run.
+    pub fn with_pipeline_cache(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.pipeline_cache = Some(path.into());
+        self
+    }
+
+    pub fn config(&self) -> &RenderConfig {
+        &self.config
+    }
+
+    /// Creates a Rhyolite Mesh renderer with the builder's settings.
+    #[cfg(feature = "mesh")]
+    pub fn mesh(self) -> Result<Rhyolite<MeshRenderer>, RhyoliteError> {
         let event_loop = EventLoop::new();
-        let renderer = MarchedRenderer::new(&event_loop);
-        Rhyolite {
+        let renderer = MeshRenderer::new(&event_loop, &self.config)?;
+        Ok(Rhyolite {
             renderer,
-            event_loop: Some(event_loop),
-        }
+            event_loop,
+        })
+    }
+
+    /// Creates a Rhyolite ray marching renderer with the builder's settings.
+    #[cfg(feature = "marched")]
+    pub fn ray_marched(self) -> Result<Rhyolite<MarchedRenderer>, RhyoliteError> {
+        let event_loop = EventLoop::new();
+        let renderer = MarchedRenderer::new(&event_loop, &self.config)?;
+        Ok(Rhyolite {
+            renderer,
+            event_loop,
+        })
     }
 }
 
 impl<T: Renderer + 'static> Rhyolite<T> {
     /// Runs a FnMut closure with the Rhyolite instance. Events for program exiting, swapchain recreation on resize, and TimeState calculation are executed before
     /// the closure is called.
-    pub fn run<F>(mut self, mut handler: F)
+    pub fn run<F>(self, mut handler: F)
     where
         F: 'static
             + FnMut(
@@ -84,9 +214,8 @@ impl<T: Renderer + 'static> Rhyolite<T> {
 
         let mut occluded = false;
 
-        self.event_loop
-            .take()
-            .unwrap()
+        let Rhyolite { mut renderer, event_loop } = self;
+        event_loop
             .run(move |event, target, control_flow| {
                 let mut clear_inputs = false;
 
@@ -96,7 +225,9 @@ impl<T: Renderer + 'static> Rhyolite<T> {
                             *control_flow = ControlFlow::Exit;
                         }
                         WindowEvent::Resized(_) => {
-                            self.renderer.recreate_all_size_dependent();
+                            if let Err(e) = renderer.recreate_all_size_dependent() {
+                                log::error!("Failed to recreate size-dependent resources: {}", e);
+                            }
                         }
                         WindowEvent::KeyboardInput { input, .. } => {
                             keyboard.add_input(input);
@@ -124,7 +255,7 @@ impl<T: Renderer + 'static> Rhyolite<T> {
                     target,
                     control_flow,
                     &time_state,
-                    &mut self.renderer,
+                    &mut renderer,
                 );
 
                 if clear_inputs { keyboard.clear_inputs(); }
@@ -132,14 +263,6 @@ impl<T: Renderer + 'static> Rhyolite<T> {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct UnconfiguredError(&'static str);
-impl std::fmt::Display for UnconfiguredError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
 /// A struct representing various time-related values, automatically calculated by Rhyolite before each frame.
 /// * `current`: The amount of time elapsed since the start of the program, in seconds.
 /// * `delta`: The amount of time elapsed since the last frame, in seconds.
@@ -2,10 +2,17 @@ use nalgebra_glm::Vec3;
 use vulkano::buffer::Subbuffer;
 
 use crate::renderer::staging::{IntoPersistentUniform, UniformSrc};
-use crate::shaders::{ambient_frag, expand_vec3, marched_frag, point_frag};
+use crate::shaders::{
+    ambient_frag, directional_frag, expand_vec3, light_cull_comp, marched_frag, point_frag, spot_frag,
+};
 
 // TODO: ideally make the get_buffer thing a trait
 
+/// The smallest difference between the outer and inner angles of a spot light's cones, in radians.
+/// The shaders fade the light out with `smoothstep()` between the cosines of the angles, which is
+/// undefined if they're equal.
+const MIN_SPOT_LIGHT_FADE: f32 = 0.001;
+
 #[derive(Default, Clone)]
 pub struct AmbientLight {
     color: Vec3,
@@ -45,11 +52,45 @@ impl IntoPersistentUniform<ambient_frag::UAmbientLightData> for AmbientLight {
 }
 
 
+/// How the light of a `PointLight` falls off with distance.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Attenuation {
+    /// Physically based falloff, proportional to `1 / d²`.
+    #[default]
+    InverseSquare,
+    /// Falloff proportional to `1 / (constant + linear * d + quadratic * d²)`, which can keep
+    /// lights from blowing out nearby surfaces.
+    Polynomial {
+        constant: f32,
+        linear: f32,
+        quadratic: f32,
+    },
+}
+
+impl Attenuation {
+    /// Gets the constant, linear, and quadratic terms of the attenuation, padded to a vec4.
+    fn terms(&self) -> [f32; 4] {
+        match *self {
+            Attenuation::InverseSquare => [0.0, 0.0, 1.0, 0.0],
+            Attenuation::Polynomial {
+                constant,
+                linear,
+                quadratic,
+            } => [constant, linear, quadratic, 0.0],
+        }
+    }
+}
+
+/// A light that shines in every direction from a position. By default, its range is unlimited
+/// and it's drawn over the whole screen. Giving it a radius with `with_radius()` fades it out
+/// to nothing at that distance, and only the pixels within the radius are shaded.
 #[derive(Default, Clone)]
 pub struct PointLight {
     position: Vec3,
     color: Vec3,
     intensity: f32,
+    attenuation: Attenuation,
+    radius: Option<f32>,
     subbuffer: Option<Subbuffer<point_frag::UPointLightData>>,
 }
 
@@ -59,9 +100,32 @@ impl PointLight {
             position,
             color,
             intensity,
+            attenuation: Attenuation::InverseSquare,
+            radius: None,
             subbuffer: None,
         }
     }
+
+    /// Limits the range of the light to `radius`, beyond which it has no effect.
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = Some(radius);
+        self
+    }
+
+    /// Sets how the light falls off with distance.
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    pub fn position(&self) -> &Vec3 {
+        &self.position
+    }
+
+    /// Gets the distance beyond which the light has no effect, if its range is limited.
+    pub fn radius(&self) -> Option<f32> {
+        self.radius
+    }
 }
 
 impl UniformSrc<point_frag::UPointLightData> for PointLight {
@@ -69,7 +133,9 @@ impl UniformSrc<point_frag::UPointLightData> for PointLight {
         point_frag::UPointLightData {
             position: expand_vec3(&self.position),
             color: expand_vec3(&self.color),
+            attenuation: self.attenuation.terms(),
             intensity: self.intensity.into(),
+            radius: self.radius.unwrap_or(0.0).into(),
         }
     }
 }
@@ -80,13 +146,195 @@ impl IntoPersistentUniform<point_frag::UPointLightData> for PointLight {
     fn set_current_buffer(&mut self, buf: Subbuffer<point_frag::UPointLightData>) { self.subbuffer = Some(buf) }
 }
 
-#[cfg(feature = "marched")]
-impl From<point_frag::UPointLightData> for marched_frag::UPointLight {
+#[cfg(any(feature = "mesh", feature = "marched"))]
+impl From<point_frag::UPointLightData> for light_cull_comp::UPointLight {
     fn from(value: point_frag::UPointLightData) -> Self {
         Self {
             color: value.color,
             intensity: value.intensity,
             position: value.position,
+            attenuation: value.attenuation,
+            radius: value.radius,
+        }
+    }
+}
+
+/// A light infinitely far away, like the sun, which lights every surface from the same direction
+/// without falling off with distance.
+#[derive(Default, Clone)]
+pub struct DirectionalLight {
+    direction: Vec3,
+    color: Vec3,
+    intensity: f32,
+    subbuffer: Option<Subbuffer<directional_frag::UDirectionalLightData>>,
+}
+
+impl DirectionalLight {
+    /// Creates a directional light whose light travels along `direction`, which doesn't need to
+    /// be normalized.
+    pub fn new(direction: Vec3, color: Vec3, intensity: f32) -> Self {
+        Self {
+            direction,
+            color,
+            intensity,
+            subbuffer: None,
         }
     }
-}
\ No newline at end of file
+
+    /// Gets the direction that the light travels along.
+    pub fn direction(&self) -> &Vec3 {
+        &self.direction
+    }
+}
+
+impl UniformSrc<directional_frag::UDirectionalLightData> for DirectionalLight {
+    fn get_raw(&self) -> directional_frag::UDirectionalLightData {
+        directional_frag::UDirectionalLightData {
+            direction: expand_vec3(&self.direction),
+            color: expand_vec3(&self.color),
+            intensity: self.intensity.into(),
+        }
+    }
+}
+
+#[cfg(feature = "mesh")]
+impl IntoPersistentUniform<directional_frag::UDirectionalLightData> for DirectionalLight {
+    fn get_current_buffer(&self) -> Option<Subbuffer<directional_frag::UDirectionalLightData>> { self.subbuffer.clone() }
+    fn set_current_buffer(&mut self, buf: Subbuffer<directional_frag::UDirectionalLightData>) { self.subbuffer = Some(buf) }
+}
+
+#[cfg(feature = "marched")]
+impl From<directional_frag::UDirectionalLightData> for marched_frag::UDirectionalLight {
+    fn from(value: directional_frag::UDirectionalLightData) -> Self {
+        Self {
+            color: value.color,
+            intensity: value.intensity,
+            direction: value.direction,
+        }
+    }
+}
+
+/// A light that shines from a position in a cone around its direction, like a flashlight. Its
+/// light is at full strength inside the inner cone and fades out toward the edge of the outer
+/// cone, falling off with distance like a point light. Like a point light, its range is
+/// unlimited unless it's given one with `with_range()`.
+#[derive(Default, Clone)]
+pub struct SpotLight {
+    position: Vec3,
+    direction: Vec3,
+    inner_angle: f32,
+    outer_angle: f32,
+    color: Vec3,
+    intensity: f32,
+    range: Option<f32>,
+    subbuffer: Option<Subbuffer<spot_frag::USpotLightData>>,
+}
+
+impl SpotLight {
+    /// Creates a spot light at `position` that shines along `direction`, which doesn't need to be
+    /// normalized. The cone angles are in radians, measured from `direction` to the edges of the
+    /// cones. `outer_angle` is widened to be slightly larger than `inner_angle` if it isn't
+    /// already.
+    pub fn new(
+        position: Vec3,
+        direction: Vec3,
+        inner_angle: f32,
+        outer_angle: f32,
+        color: Vec3,
+        intensity: f32,
+    ) -> Self {
+        Self {
+            position,
+            direction,
+            inner_angle,
+            outer_angle: outer_angle.max(inner_angle + MIN_SPOT_LIGHT_FADE),
+            color,
+            intensity,
+            range: None,
+            subbuffer: None,
+        }
+    }
+
+    /// Limits the range of the light to `range`, beyond which it has no effect.
+    pub fn with_range(mut self, range: f32) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    pub fn position(&self) -> &Vec3 {
+        &self.position
+    }
+
+    /// Gets the direction that the light shines along.
+    pub fn direction(&self) -> &Vec3 {
+        &self.direction
+    }
+
+    /// Gets the angles between the light's direction and the edges of its inner and outer cones,
+    /// in radians.
+    pub fn cone_angles(&self) -> (f32, f32) {
+        (self.inner_angle, self.outer_angle)
+    }
+
+    /// Gets the distance beyond which the light has no effect, if its range is limited.
+    pub fn range(&self) -> Option<f32> {
+        self.range
+    }
+}
+
+impl UniformSrc<spot_frag::USpotLightData> for SpotLight {
+    fn get_raw(&self) -> spot_frag::USpotLightData {
+        spot_frag::USpotLightData {
+            position: expand_vec3(&self.position),
+            direction: expand_vec3(&self.direction),
+            color: expand_vec3(&self.color),
+            intensity: self.intensity.into(),
+            inner_cos: self.inner_angle.cos().into(),
+            outer_cos: self.outer_angle.cos().into(),
+            range: self.range.unwrap_or(0.0).into(),
+        }
+    }
+}
+
+#[cfg(feature = "mesh")]
+impl IntoPersistentUniform<spot_frag::USpotLightData> for SpotLight {
+    fn get_current_buffer(&self) -> Option<Subbuffer<spot_frag::USpotLightData>> { self.subbuffer.clone() }
+    fn set_current_buffer(&mut self, buf: Subbuffer<spot_frag::USpotLightData>) { self.subbuffer = Some(buf) }
+}
+
+#[cfg(feature = "marched")]
+impl From<spot_frag::USpotLightData> for marched_frag::USpotLight {
+    fn from(value: spot_frag::USpotLightData) -> Self {
+        Self {
+            position: value.position,
+            direction: value.direction,
+            color: value.color,
+            intensity: value.intensity,
+            inner_cos: value.inner_cos,
+            outer_cos: value.outer_cos,
+            range: value.range,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::vec3;
+
+    use super::*;
+
+    #[test]
+    fn spot_light_outer_cones_are_wider_than_inner_cones() {
+        let cone_angles = |inner, outer| {
+            let color = vec3(1.0, 1.0, 1.0);
+            SpotLight::new(Vec3::zeros(), vec3(0.0, 0.0, -1.0), inner, outer, color, 1.0).cone_angles()
+        };
+        assert_eq!(cone_angles(0.25, 0.5), (0.25, 0.5));
+        for (inner, outer) in [(0.5, 0.5), (0.5, 0.25), (0.0, 0.0)] {
+            let (inner_angle, outer_angle) = cone_angles(inner, outer);
+            assert_eq!(inner_angle, inner);
+            assert!(outer_angle > inner_angle);
+            assert!(outer_angle.cos() < inner_angle.cos());
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use vulkano::format::Format;
+
+/// An RGBA8 image read back from the final color attachment of a rendered frame. Captures are
+/// requested with `Renderer::capture_next_frame()` before a frame is finished, and retrieved with
+/// `Renderer::take_captured_frame()` once it has been.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameCapture {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl FrameCapture {
+    /// Creates a capture from tightly packed RGBA8 data, ordered row by row from the top left.
+    ///
+    /// # Panics
+    /// - Panics if the length of `data` is not equal to `width * height * 4`
+    pub fn from_rgba8(width: u32, height: u32, data: Vec<u8>) -> Self {
+        assert_eq!(data.len(), (width * height * 4) as usize);
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Converts raw image data copied out of an image with the specified format into an RGBA8
+    /// capture. Values are copied as they are stored, so sRGB images remain sRGB-encoded and
+    /// floating-point images are clamped to `[0, 1]` without tonemapping.
+    pub(crate) fn from_raw(
+        format: Format,
+        dimensions: [u32; 2],
+        raw: &[u8],
+    ) -> Result<Self, UnsupportedFormatError> {
+        let data = match format {
+            Format::R8G8B8A8_UNORM | Format::R8G8B8A8_SRGB | Format::A8B8G8R8_UNORM_PACK32
+            | Format::A8B8G8R8_SRGB_PACK32 => raw.to_vec(),
+            Format::B8G8R8A8_UNORM | Format::B8G8R8A8_SRGB => raw
+                .chunks_exact(4)
+                .flat_map(|px| [px[2], px[1], px[0], px[3]])
+                .collect(),
+            Format::A2B10G10R10_UNORM_PACK32 | Format::A2R10G10B10_UNORM_PACK32 => raw
+                .chunks_exact(4)
+                .flat_map(|px| {
+                    let packed = u32::from_le_bytes([px[0], px[1], px[2], px[3]]);
+                    let channel = |shift: u32| ((packed >> shift) & 0x3FF) as f32 / 1023.0;
+                    let (r, g, b) = if format == Format::A2B10G10R10_UNORM_PACK32 {
+                        (channel(0), channel(10), channel(20))
+                    } else {
+                        (channel(20), channel(10), channel(0))
+                    };
+                    let a = (packed >> 30) as f32 / 3.0;
+                    [r, g, b, a].map(unorm_to_u8)
+                })
+                .collect(),
+            Format::R16G16B16A16_SFLOAT => raw
+                .chunks_exact(8)
+                .flat_map(|px| {
+                    [0, 2, 4, 6].map(|i| unorm_to_u8(f16_to_f32(u16::from_le_bytes([px[i], px[i + 1]]))))
+                })
+                .collect(),
+            Format::R32G32B32A32_SFLOAT => raw
+                .chunks_exact(16)
+                .flat_map(|px| {
+                    [0, 4, 8, 12].map(|i| {
+                        unorm_to_u8(f32::from_le_bytes([px[i], px[i + 1], px[i + 2], px[i + 3]]))
+                    })
+                })
+                .collect(),
+            _ => return Err(UnsupportedFormatError(format)),
+        };
+
+        Ok(Self::from_rgba8(dimensions[0], dimensions[1], data))
+    }
+
+    /// Returns `true` if images of the specified format can be converted into a `FrameCapture`.
+    pub fn supports_format(format: Format) -> bool {
+        // The block size is all that's needed to check whether conversion succeeds
+        let block_size = format.block_size().unwrap_or(0) as usize;
+        Self::from_raw(format, [1, 1], &vec![0; block_size]).is_ok()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    pub fn dimensions(&self) -> [u32; 2] {
+        [self.width, self.height]
+    }
+
+    /// Gets the raw RGBA8 data of the capture, ordered row by row from the top left.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    pub fn into_raw(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Gets the RGBA value of the pixel at the specified coordinates.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]
+    }
+
+    /// Writes the capture to a PNG file, including its alpha channel.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> image::ImageResult<()> {
+        image::save_buffer_with_format(
+            path,
+            &self.data,
+            self.width,
+            self.height,
+            image::ColorType::Rgba8,
+            image::ImageFormat::Png,
+        )
+    }
+
+    /// Writes the capture to a binary (P6) PPM file. PPM has no alpha channel, so alpha is
+    /// discarded.
+    pub fn save_ppm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for px in self.data.chunks_exact(4) {
+            writer.write_all(&px[..3])?;
+        }
+        writer.flush()
+    }
+}
+
+/// An error returned when a frame is captured from an image whose format can't be converted to
+/// RGBA8.
+#[derive(Debug, Clone)]
+pub struct UnsupportedFormatError(pub Format);
+impl std::fmt::Display for UnsupportedFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "frames with the format {:?} can't be captured", self.0)
+    }
+}
+impl std::error::Error for UnsupportedFormatError {}
+
+fn unorm_to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Converts a half-precision float, stored as its raw bits, to a single-precision float.
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1F) as i32;
+    let mantissa = (bits & 0x3FF) as f32;
+
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1F if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1F => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
@@ -0,0 +1,307 @@
+use std::sync::Arc;
+
+use nalgebra_glm::Mat4;
+use vulkano::buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer};
+use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
+use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
+use vulkano::memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator};
+use vulkano::padded::Padded;
+use vulkano::pipeline::{ComputePipeline, Pipeline, PipelineBindPoint};
+
+use crate::lighting::PointLight;
+use crate::renderer::staging::UniformSrc;
+use crate::shaders::light_cull_comp;
+use crate::RhyoliteError;
+
+use super::{RenderBase, FRAMES_IN_FLIGHT};
+
+/// The width and height of the screen tiles that point lights are sorted into, in pixels.
+pub const TILE_SIZE: u32 = 16;
+/// The most point lights that can affect a single tile. Once a tile is full, the remaining lights
+/// are left out of it.
+pub const MAX_LIGHTS_PER_TILE: u32 = 255;
+
+/// A light in the storage buffer read by the culling and lighting shaders
+type RawPointLight = Padded<light_cull_comp::UPointLight, 8>;
+
+/// A list of point lights that are uploaded into a single storage buffer each frame, then sorted
+/// into screen tiles by a compute pass. Lighting passes only shade each pixel with the lights in
+/// its tile, which makes hundreds of small lights affordable.
+pub(crate) struct LightList {
+    pipeline: Arc<ComputePipeline>,
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    lights: Vec<RawPointLight>,
+    /// The tile lists of each frame slot, along with the dimensions they were allocated for
+    tiles: Vec<Option<(Subbuffer<[u32]>, [u32; 2])>>,
+}
+
+/// The lights of a frame after they've been sorted into tiles, which lighting passes read from
+pub(crate) struct CulledLights {
+    pub lights: Subbuffer<[RawPointLight]>,
+    pub tiles: Subbuffer<[u32]>,
+    pub tiles_per_row: u32,
+}
+
+impl CulledLights {
+    /// Gets the descriptors for a lighting pass that reads the lights from `lights_binding` and
+    /// the tiles from the binding after it.
+    pub fn descriptors(&self, lights_binding: u32) -> [WriteDescriptorSet; 2] {
+        [
+            WriteDescriptorSet::buffer(lights_binding, self.lights.clone()),
+            WriteDescriptorSet::buffer(lights_binding + 1, self.tiles.clone()),
+        ]
+    }
+}
+
+impl LightList {
+    pub fn new(base: &RenderBase) -> Result<Self, RhyoliteError> {
+        let shader = light_cull_comp::load(base.get_device())?;
+        let pipeline = ComputePipeline::new(
+            base.get_device(),
+            shader.entry_point("main").unwrap(),
+            &(),
+            Some(base.pipeline_cache().clone()),
+            |_| {},
+        )?;
+
+        Ok(Self {
+            pipeline,
+            memory_allocator: base.get_memory_allocator(),
+            lights: vec![],
+            tiles: (0..FRAMES_IN_FLIGHT).map(|_| None).collect(),
+        })
+    }
+
+    /// Replaces the lights in the list, which are uploaded the next time the list is culled.
+    pub fn set_lights<'a>(&mut self, lights: impl IntoIterator<Item = &'a PointLight>) {
+        self.lights = lights
+            .into_iter()
+            .map(|light| Padded::from(light_cull_comp::UPointLight::from(light.get_raw())))
+            .collect();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// Uploads the lights and records the compute pass that sorts them into tiles. Must be called
+    /// after the frame slot has been waited on, and before the render pass begins.
+    pub fn cull(
+        &mut self,
+        base: &mut RenderBase,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        view_projection: Mat4,
+    ) -> Result<CulledLights, RhyoliteError> {
+        let dimensions = base.get_scissor().dimensions;
+        let tile_counts = [
+            (dimensions[0] + TILE_SIZE - 1) / TILE_SIZE,
+            (dimensions[1] + TILE_SIZE - 1) / TILE_SIZE,
+        ];
+        let tiles = self.tiles_for_frame(base.frame_index(), dimensions, tile_counts)?;
+
+        // Empty buffers can't be bound, so there's always at least one light in the buffer
+        let lights = base
+            .subbuffer_allocator()
+            .allocate_slice::<RawPointLight>(self.lights.len().max(1) as u64)?;
+        lights.write()?[..self.lights.len()].clone_from_slice(&self.lights);
+
+        let layout = self.pipeline.layout().set_layouts().get(0).unwrap().clone();
+        let set = PersistentDescriptorSet::new(
+            descriptor_set_allocator,
+            layout,
+            [
+                WriteDescriptorSet::buffer(0, lights.clone()),
+                WriteDescriptorSet::buffer(1, tiles.clone()),
+            ],
+        )?;
+
+        base.commands_mut()?
+            .bind_pipeline_compute(self.pipeline.clone())
+            .bind_descriptor_sets(
+                PipelineBindPoint::Compute,
+                self.pipeline.layout().clone(),
+                0,
+                set,
+            )
+            .push_constants(
+                self.pipeline.layout().clone(),
+                0,
+                light_cull_comp::UCullData {
+                    view_projection: view_projection.into(),
+                    dimensions,
+                    light_count: self.lights.len() as u32,
+                },
+            )
+            .dispatch([tile_counts[0], tile_counts[1], 1])?;
+
+        Ok(CulledLights {
+            lights,
+            tiles,
+            tiles_per_row: tile_counts[0],
+        })
+    }
+
+    /// Gets the tile lists of a frame slot, reallocating them if the screen size has changed.
+    /// Each slot has its own lists, so that culling a frame doesn't overwrite the lists of a
+    /// frame that's still being drawn.
+    fn tiles_for_frame(
+        &mut self,
+        frame_idx: usize,
+        dimensions: [u32; 2],
+        tile_counts: [u32; 2],
+    ) -> Result<Subbuffer<[u32]>, RhyoliteError> {
+        if let Some((tiles, tile_dimensions)) = &self.tiles[frame_idx] {
+            if *tile_dimensions == dimensions {
+                return Ok(tiles.clone());
+            }
+        }
+
+        let len = (tile_counts[0] * tile_counts[1] * (MAX_LIGHTS_PER_TILE + 1)) as u64;
+        let tiles = Buffer::new_slice::<u32>(
+            &self.memory_allocator,
+            BufferCreateInfo {
+                // The lists can be copied out, so that tests can check them
+                usage: BufferUsage::STORAGE_BUFFER | BufferUsage::TRANSFER_SRC,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::DeviceOnly,
+                ..Default::default()
+            },
+            len,
+        )?;
+        self.tiles[frame_idx] = Some((tiles.clone(), dimensions));
+        Ok(tiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra_glm::{look_at, perspective, vec3, Vec3};
+    use vulkano::command_buffer::CopyBufferInfo;
+    use vulkano::device::physical::PhysicalDeviceType;
+    use vulkano::sync::{self, GpuFuture};
+
+    use super::*;
+    use crate::renderer::{DeviceSelector, RenderConfig};
+
+    const DIMENSIONS: [u32; 2] = [64, 64];
+    /// The number of tiles covering `DIMENSIONS`
+    const TILE_COUNT: usize = 16;
+
+    /// A camera at the origin looking down -Z with a 90 degree field of view
+    fn view_projection() -> Mat4 {
+        let view = look_at(&vec3(0.0, 0.0, 0.0), &vec3(0.0, 0.0, -1.0), &vec3(0.0, 1.0, 0.0));
+        perspective(1.0, std::f32::consts::FRAC_PI_2, 0.1, 100.0) * view
+    }
+
+    fn light(position: Vec3, radius: f32) -> PointLight {
+        PointLight::new(position, vec3(1.0, 1.0, 1.0), 1.0).with_radius(radius)
+    }
+
+    /// Runs the culling pass over `lights` on a CPU device, then reads back the indices of the
+    /// lights sorted into each tile, in row-major order. The indices of each tile are sorted,
+    /// since the pass adds them in whichever order its invocations finish.
+    fn cull(lights: &[PointLight]) -> Vec<Vec<u32>> {
+        let config = RenderConfig {
+            window_size: DIMENSIONS,
+            device: DeviceSelector::Type(PhysicalDeviceType::Cpu),
+            ..Default::default()
+        };
+        let mut base = RenderBase::headless(&config).unwrap();
+        base.viewport.dimensions = [DIMENSIONS[0] as f32, DIMENSIONS[1] as f32];
+        let descriptor_set_allocator = StandardDescriptorSetAllocator::new(base.get_device());
+
+        let mut light_list = LightList::new(&base).unwrap();
+        light_list.set_lights(lights);
+        let culled = light_list
+            .cull(&mut base, &descriptor_set_allocator, view_projection())
+            .unwrap();
+
+        let readback = Buffer::new_slice::<u32>(
+            &base.get_memory_allocator(),
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Download,
+                ..Default::default()
+            },
+            culled.tiles.len(),
+        )
+        .unwrap();
+        base.commands_mut()
+            .unwrap()
+            .copy_buffer(CopyBufferInfo::buffers(culled.tiles, readback.clone()))
+            .unwrap();
+        let commands = base.commands.take().unwrap().build().unwrap();
+        sync::now(base.get_device())
+            .then_execute(base.graphics_queue.clone(), commands)
+            .unwrap()
+            .then_signal_fence_and_flush()
+            .unwrap()
+            .wait(None)
+            .unwrap();
+
+        let tiles = readback.read().unwrap();
+        assert_eq!(tiles.len(), TILE_COUNT * (MAX_LIGHTS_PER_TILE + 1) as usize);
+        tiles
+            .chunks((MAX_LIGHTS_PER_TILE + 1) as usize)
+            .map(|tile| {
+                let mut indices = tile[1..=tile[0] as usize].to_vec();
+                indices.sort();
+                indices
+            })
+            .collect()
+    }
+
+    #[test]
+    #[ignore = "requires a Vulkan implementation such as lavapipe"]
+    fn small_lights_only_overlap_nearby_tiles() {
+        // Each light covers the four tiles around the point it's in front of
+        let tiles = cull(&[light(vec3(0.0, 0.0, -10.0), 1.0), light(vec3(-7.5, -7.5, -10.0), 1.0)]);
+        for (i, tile) in tiles.iter().enumerate() {
+            let expected: &[u32] = match i {
+                0 => &[1],
+                5 | 6 | 9 | 10 => &[0],
+                _ => &[],
+            };
+            assert_eq!(tile, expected, "tile {}", i);
+        }
+    }
+
+    #[test]
+    #[ignore = "requires a Vulkan implementation such as lavapipe"]
+    fn offscreen_lights_overlap_no_tiles() {
+        // Lights entirely past an edge of the screen must not be clamped into the edge tiles
+        let tiles = cull(&[
+            light(vec3(30.0, 0.0, -10.0), 1.0),
+            light(vec3(-30.0, 0.0, -10.0), 1.0),
+            light(vec3(0.0, 30.0, -10.0), 1.0),
+            light(vec3(0.0, -30.0, -10.0), 1.0),
+        ]);
+        assert!(tiles.iter().all(|tile| tile.is_empty()));
+    }
+
+    #[test]
+    #[ignore = "requires a Vulkan implementation such as lavapipe"]
+    fn unbounded_and_surrounding_lights_overlap_every_tile() {
+        let tiles = cull(&[
+            PointLight::new(vec3(30.0, 0.0, -10.0), vec3(1.0, 1.0, 1.0), 1.0),
+            // The camera is inside of this light, so some of its corners are behind the camera
+            light(vec3(0.0, 0.0, -1.0), 2.0),
+        ]);
+        assert_eq!(tiles.len(), TILE_COUNT);
+        assert!(tiles.iter().all(|tile| tile == &[0, 1]));
+    }
+
+    #[test]
+    #[ignore = "requires a Vulkan implementation such as lavapipe"]
+    fn full_tiles_leave_out_the_remaining_lights() {
+        let lights = vec![PointLight::new(vec3(0.0, 0.0, -10.0), vec3(1.0, 1.0, 1.0), 1.0); 300];
+        for tile in cull(&lights) {
+            assert_eq!(tile.len(), MAX_LIGHTS_PER_TILE as usize);
+        }
+    }
+}
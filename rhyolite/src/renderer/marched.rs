@@ -1,33 +1,37 @@
 use std::mem::MaybeUninit;
 use std::sync::Arc;
 
-use vulkano::buffer::allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo};
 use vulkano::buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer};
+use vulkano::command_buffer::SubpassContents;
 use vulkano::descriptor_set::WriteDescriptorSet;
 use vulkano::descriptor_set::{allocator::StandardDescriptorSetAllocator, PersistentDescriptorSet};
 use vulkano::device::Device;
-use vulkano::format::Format;
+use vulkano::format::{ClearValue, Format};
 use vulkano::image::view::ImageView;
-use vulkano::image::{ImageAccess, SwapchainImage};
+use vulkano::image::{AttachmentImage, ImageAccess, SampleCount};
 use vulkano::memory::allocator::{AllocationCreateInfo, FreeListAllocator, GenericMemoryAllocator, MemoryUsage, StandardMemoryAllocator};
 use vulkano::padded::Padded;
 use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
 use vulkano::pipeline::graphics::vertex_input::Vertex;
 use vulkano::pipeline::graphics::viewport::{Viewport, ViewportState};
+use vulkano::pipeline::cache::PipelineCache;
 use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint};
 use vulkano::render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass};
 
 use crate::geometry::dummy::DummyVertex;
 use crate::geometry::marched::Metaball;
-use crate::lighting::{AmbientLight, PointLight};
+use crate::lighting::{AmbientLight, DirectionalLight, PointLight, SpotLight};
 use crate::renderer::staging::{IntoPersistentUniform, StagingBuffer, UniformSrc};
 use crate::shaders::{marched_frag, ShaderModulePair};
 
-use crate::UnconfiguredError;
+use crate::RhyoliteError;
 
-use super::{RenderBase, Renderer};
+use super::light_list::{CulledLights, LightList};
+use super::tonemap::{TonemapPass, HDR_FORMAT};
+use super::{RenderBase, RenderConfig, Renderer};
 
-const MAX_POINT_LIGHTS: usize = 16;
+const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+const MAX_SPOT_LIGHTS: usize = 16;
 const MAX_METABALLS: usize = 1024;
 
 pub struct MarchedRenderer {
@@ -37,24 +41,45 @@ pub struct MarchedRenderer {
 
     buffer_allocator: Arc<StandardMemoryAllocator>,
     descriptor_set_allocator: StandardDescriptorSetAllocator,
-    subbuffer_allocator: SubbufferAllocator,
 
     vp_set: Option<Arc<PersistentDescriptorSet>>,
     geometry_set: Option<Arc<PersistentDescriptorSet>>,
-    lighting_data: Option<MarchedLightingData>, // Contains a PersistentDescriptorSet and a point light count
+    lighting_data: Option<MarchedLightingData>, // Contains a PersistentDescriptorSet
+
+    /// The point lights of the scene, which are sorted into screen tiles at the start of each
+    /// frame
+    light_list: LightList,
+    culled_lights: Option<CulledLights>,
 
     dummy_vertex_buf: Subbuffer<[DummyVertex]>,
 
     pipeline: Arc<GraphicsPipeline>,
+    tonemap_pass: TonemapPass,
     framebuffers: Vec<Arc<Framebuffer>>,
+    /// The attachment that the scene is shaded into, before it's tonemapped
+    hdr_buffer: Arc<ImageView<AttachmentImage>>,
 
     objects: Vec<Metaball>,
 }
 
 impl MarchedRenderer {
-    pub fn new(event_loop: &winit::event_loop::EventLoop<()>) -> Self {
-        let mut base = RenderBase::new(&event_loop);
-        let render_pass = get_render_pass(&base.device, base.swapchain.image_format());
+    /// Creates a new `MarchedRenderer` that draws to a window created with the specified event
+    /// loop and configuration.
+    pub fn new(
+        event_loop: &winit::event_loop::EventLoop<()>,
+        config: &RenderConfig,
+    ) -> Result<Self, RhyoliteError> {
+        Self::from_base(RenderBase::new(&event_loop, config)?)
+    }
+
+    /// Creates a new `MarchedRenderer` that draws to an offscreen image of
+    /// `config.window_size` pixels, without creating a window.
+    pub fn headless(config: &RenderConfig) -> Result<Self, RhyoliteError> {
+        Self::from_base(RenderBase::headless(config)?)
+    }
+
+    fn from_base(mut base: RenderBase) -> Result<Self, RhyoliteError> {
+        let render_pass = get_render_pass(&base.device, base.image_format())?;
 
         // Buffer allocators
         // Generic allocator for framebuffer attachments, descriptor sets, vertex buffers, etc.
@@ -65,56 +90,73 @@ impl MarchedRenderer {
         // TODO: use a descriptor pool instead of a descriptor set allocator
         let descriptor_set_allocator = StandardDescriptorSetAllocator::new(base.device.clone());
 
-        let subbuffer_allocator = SubbufferAllocator::new(
-            buffer_allocator.clone(),
-            SubbufferAllocatorCreateInfo {
-                arena_size: 512, // TODO: FIND THE ACTUAL VALUE
-                buffer_usage: BufferUsage::UNIFORM_BUFFER,
-                memory_usage: MemoryUsage::Upload,
-                ..Default::default()
-            },
-        );
-
         // Create a dummy vertex buffer used for full-screen shaders
-        let dummy_vertex_buf = DummyVertex::buf(&buffer_allocator, &base);
+        let dummy_vertex_buf = DummyVertex::buf(&buffer_allocator, &base)?;
+
+        // The viewport and scissor are set when each frame starts, so the pipeline doesn't need to
+        // be recreated when the window is resized
+        let pipeline = get_pipeline(&render_pass, &base.device, base.pipeline_cache())?;
+        let light_list = LightList::new(&base)?;
+        let tonemap_pass = TonemapPass::new(
+            Subpass::from(render_pass.clone(), 1).unwrap(),
+            &base,
+            SampleCount::Sample1,
+        )?;
 
         // Includes framebuffers and other attachments that aren't stored
-        let (framebuffers, pipeline) = window_size_dependent_setup(
-            &base.images,
+        let (framebuffers, hdr_buffer) = window_size_dependent_setup(
+            &buffer_allocator,
+            &base.render_targets(),
             render_pass.clone(),
             &mut base.viewport,
-            &base.device,
-        );
+        )?;
 
-        Self {
+        Ok(Self {
             base,
 
             render_pass,
 
             buffer_allocator,
             descriptor_set_allocator,
-            subbuffer_allocator,
 
             vp_set: None,
             geometry_set: None,
             lighting_data: None,
 
+            light_list,
+            culled_lights: None,
+
             dummy_vertex_buf,
 
             pipeline,
+            tonemap_pass,
             framebuffers,
+            hdr_buffer,
 
             objects: vec![],
-        }
+        })
     }
 
     /// Starts the rendering process, configuring buffers for the camera, recreating the swapchain
     /// if necessary, and performing necessary acquisition of rendering resources.
-    pub fn start(&mut self, camera: &mut crate::camera::Camera) {
+    pub fn start(&mut self, camera: &mut crate::camera::Camera) -> Result<(), RhyoliteError> {
         if !camera.is_configured() {
             camera.configure(self.get_window_size());
         }
 
+        if self.base.should_recreate_swapchain {
+            camera.configure(self.get_window_size());
+            self.recreate_all_size_dependent()?;
+        }
+
+        // The HDR buffer is cleared to transparent black, and the tonemapping pass fills the
+        // pixels that nothing was drawn to with the clear color
+        let clear_values = vec![None, Some(ClearValue::Float([0.0, 0.0, 0.0, 0.0]))];
+
+        // Beginning the frame waits for the frame slot, so its subbuffers must be allocated
+        // afterward
+        self.base.begin_frame()?;
+
         let vp_layout = self
             .pipeline
             .layout()
@@ -122,33 +164,43 @@ impl MarchedRenderer {
             .get(0)
             .unwrap()
             .clone();
-        let vp_subbuffer = camera.get_vp_subbuffer(&self.subbuffer_allocator).unwrap();
-        self.vp_set = Some(
-            PersistentDescriptorSet::new(
+        let vp_subbuffer = camera.get_vp_subbuffer(self.base.subbuffer_allocator())?;
+        self.vp_set = Some(PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            vp_layout,
+            [WriteDescriptorSet::buffer(0, vp_subbuffer)],
+        )?);
+
+        // Point lights are sorted into tiles by a compute pass, which has to be recorded before
+        // the render pass begins. The shader always reads the tiles, so they're culled even if
+        // there are no lights.
+        self.culled_lights = None;
+        if !self.base.render_error {
+            let (view, projection) = camera.view_projection()?;
+            self.base.begin_stage_label("Light culling")?;
+            self.culled_lights = Some(self.light_list.cull(
+                &mut self.base,
                 &self.descriptor_set_allocator,
-                vp_layout,
-                [WriteDescriptorSet::buffer(0, vp_subbuffer)],
-            )
-            .unwrap(),
-        );
-
-        if self.base.should_recreate_swapchain {
-            camera.configure(self.get_window_size());
-            self.recreate_all_size_dependent();
+                projection * view,
+            )?);
+            self.base.end_stage_label()?;
         }
 
-        self.base.start(&mut self.framebuffers);
+        self.base.begin_render_pass(&self.framebuffers, clear_values)
     }
 
     /// Finishes the rendering process and draws to the screen.
-    pub fn finish(&mut self) -> Result<(), UnconfiguredError>{
+    /// # Errors
+    /// Returns `RhyoliteError::Unconfigured` if the frame wasn't started, or if the geometry or
+    /// lighting hasn't been configured with `add_objects()` and `config_lighting()`.
+    pub fn finish(&mut self) -> Result<(), RhyoliteError> {
         if self.base.render_error {
             return Ok(());
         }
 
         let geometry_set = match self.geometry_set.as_ref() {
             Some(gs) => gs,
-            None => { return Err(UnconfiguredError(
+            None => { return Err(RhyoliteError::Unconfigured(
                 "Geometry descriptor set is not configured! It must be configured with \
                 `add_objects()` before the finish() function is called."
             )); }
@@ -156,50 +208,89 @@ impl MarchedRenderer {
 
         let lighting_data = match self.lighting_data.as_ref() {
             Some(ld) => ld,
-            None => { return Err(UnconfiguredError(
+            None => { return Err(RhyoliteError::Unconfigured(
                 "Lighting descriptor set is not configured! It must be configured with \
                 `config_lighting()` before the finish() function is called."
             )); }
         };
 
+        let vp_set = self.vp_set.clone().ok_or(RhyoliteError::Unconfigured(
+            "The frame hasn't been started. Call `start()` before the finish() function is called.",
+        ))?;
+        let culled_lights = self.culled_lights.as_ref().ok_or(RhyoliteError::Unconfigured(
+            "The frame hasn't been started. Call `start()` before the finish() function is called.",
+        ))?;
+
+        let tile_layout = self.pipeline.layout().set_layouts().get(3).unwrap().clone();
+        let tile_set = PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            tile_layout,
+            culled_lights.descriptors(0),
+        )?;
+
+        self.base.begin_stage_label("Marched")?;
         self.base
-            .commands_mut()
+            .commands_mut()?
             .bind_pipeline_graphics(self.pipeline.clone())
             .bind_descriptor_sets(
                 PipelineBindPoint::Graphics,
                 self.pipeline.layout().clone(),
                 0,
-                (
-                    self.vp_set.as_ref().unwrap().clone(),
-                    lighting_data.set.clone(),
-                    geometry_set,
-                ),
+                (vp_set, lighting_data.set.clone(), geometry_set, tile_set),
+            )
+            .push_constants(
+                self.pipeline.layout().clone(),
+                0,
+                marched_frag::UTileData {
+                    tiles_per_row: culled_lights.tiles_per_row,
+                },
             )
             .bind_vertex_buffers(0, self.dummy_vertex_buf.clone())
-            .draw(self.dummy_vertex_buf.len() as u32, 1, 0, 0)
-            .unwrap();
+            .draw(self.dummy_vertex_buf.len() as u32, 1, 0, 0)?
+            .next_subpass(SubpassContents::Inline)?;
 
-        self.base.finish();
-
-        Ok(())
+        self.tonemap_pass.draw(
+            &mut self.base,
+            &self.descriptor_set_allocator,
+            self.hdr_buffer.clone(),
+            &self.dummy_vertex_buf,
+        )?;
+        self.base.finish()
     }
 
     /// Configures the lighting descriptor set of the scene. Buffers created are device-only, so
     /// this should not be run often.
-    pub fn config_lighting(&mut self, point_lights: &mut Vec<PointLight>, ambient_light: &mut AmbientLight) {
-        let point_light_count = point_lights.len();
-
-        let point_light_data = unsafe {
-            to_partially_init_arr::<MAX_POINT_LIGHTS, Padded<marched_frag::UPointLight, 12>>(
-                point_lights.iter()
-                    .map(|pl| {
-                        let raw: marched_frag::UPointLight = pl.get_raw().into();
+    ///
+    /// Point lights are sorted into screen tiles each frame, so there's no limit on how many
+    /// there can be.
+    ///
+    /// # Errors
+    /// Returns `RhyoliteError::TooManyLights` if there are more than 4 directional lights or 16
+    /// spot lights.
+    pub fn config_lighting(
+        &mut self,
+        point_lights: &mut Vec<PointLight>,
+        directional_lights: &mut Vec<DirectionalLight>,
+        spot_lights: &mut Vec<SpotLight>,
+        ambient_light: &mut AmbientLight,
+    ) -> Result<(), RhyoliteError> {
+        self.light_list.set_lights(point_lights.iter());
+
+        let directional_light_data = unsafe {
+            to_partially_init_arr::<MAX_DIRECTIONAL_LIGHTS, Padded<marched_frag::UDirectionalLight, 12>>(
+                directional_lights.iter()
+                    .map(|dl| {
+                        let raw: marched_frag::UDirectionalLight = dl.get_raw().into();
                         Padded::from(raw)
                     })
             )
-        };
+        }
+        .ok_or(RhyoliteError::TooManyLights {
+            kind: "directional",
+            max: MAX_DIRECTIONAL_LIGHTS,
+        })?;
 
-        let point_light_buf: Subbuffer<marched_frag::UPointLightsData> = Buffer::from_data(
+        let directional_light_buf: Subbuffer<marched_frag::UDirectionalLightsData> = Buffer::from_data(
             &self.buffer_allocator,
             BufferCreateInfo {
                 usage: BufferUsage::TRANSFER_SRC | BufferUsage::UNIFORM_BUFFER,
@@ -209,14 +300,40 @@ impl MarchedRenderer {
                 usage: MemoryUsage::Upload,
                 ..Default::default()
             },
-            marched_frag::UPointLightsData {
-                data: point_light_data,
-                len: point_light_count as u32,
+            marched_frag::UDirectionalLightsData {
+                data: directional_light_data,
+                len: directional_lights.len() as u32,
             }
-        )
-            .unwrap()
-            .into_device_local(1, &self.buffer_allocator, self.get_base());
+        )?
+            .into_device_local(1, &self.buffer_allocator, self.get_base())?;
 
+        let spot_light_data = unsafe {
+            // Spot lights fill a whole number of vec4s, so they don't need padding
+            to_partially_init_arr::<MAX_SPOT_LIGHTS, marched_frag::USpotLight>(
+                spot_lights.iter().map(|sl| sl.get_raw().into())
+            )
+        }
+        .ok_or(RhyoliteError::TooManyLights {
+            kind: "spot",
+            max: MAX_SPOT_LIGHTS,
+        })?;
+
+        let spot_light_buf: Subbuffer<marched_frag::USpotLightsData> = Buffer::from_data(
+            &self.buffer_allocator,
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_SRC | BufferUsage::UNIFORM_BUFFER,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Upload,
+                ..Default::default()
+            },
+            marched_frag::USpotLightsData {
+                data: spot_light_data,
+                len: spot_lights.len() as u32,
+            }
+        )?
+            .into_device_local(1, &self.buffer_allocator, self.get_base())?;
 
         let layout = self.pipeline.layout().set_layouts().get(1).unwrap().clone();
         let set = PersistentDescriptorSet::new(
@@ -225,24 +342,29 @@ impl MarchedRenderer {
             [
                 WriteDescriptorSet::buffer(
                     0,
-                    point_light_buf
+                    ambient_light.get_buffer(&self.buffer_allocator, self.get_base())?,
                 ),
                 WriteDescriptorSet::buffer(
                     1,
-                    ambient_light.get_buffer(&self.buffer_allocator, self.get_base()),
+                    directional_light_buf
+                ),
+                WriteDescriptorSet::buffer(
+                    2,
+                    spot_light_buf
                 ),
             ],
-        ).unwrap();
+        )?;
 
-        self.lighting_data = Some(MarchedLightingData {
-            point_light_count,
-            set,
-        });
+        self.lighting_data = Some(MarchedLightingData { set });
+        Ok(())
     }
 
     /// Adds metaball objects to the scene. Metaball objects do not persist between frames, so
     /// this function must be called on a per-frame basis.
-    pub fn add_objects(&mut self, objects: &Vec<Metaball>) {
+    ///
+    /// # Errors
+    /// Returns `RhyoliteError::TooManyObjects` if there are more than 1024 metaballs.
+    pub fn add_objects(&mut self, objects: &Vec<Metaball>) -> Result<(), RhyoliteError> {
         let objects: Vec<Padded<marched_frag::UMetaball, 12>> = objects
             .iter()
             .map(|obj| {
@@ -253,139 +375,154 @@ impl MarchedRenderer {
         let len = objects.len() as u32;
         let data = unsafe {
             to_partially_init_arr::<MAX_METABALLS, Padded<marched_frag::UMetaball, 12>>(objects)
-        };
+        }
+        .ok_or(RhyoliteError::TooManyObjects { max: MAX_METABALLS })?;
 
-        let metaball_buf = self.subbuffer_allocator.allocate_unsized(MAX_METABALLS as u64).unwrap();
-        *metaball_buf.write().unwrap() = marched_frag::UMetaballData { data, len };
+        let metaball_buf = self.base.subbuffer_allocator().allocate_unsized(MAX_METABALLS as u64)?;
+        *metaball_buf.write()? = marched_frag::UMetaballData { data, len };
 
         let layout = self.pipeline.layout().set_layouts().get(2).unwrap().clone();
         self.geometry_set = Some(PersistentDescriptorSet::new(
             &self.descriptor_set_allocator,
             layout.clone(),
             [WriteDescriptorSet::buffer(0, metaball_buf.clone())],
-        ).expect("Unable to create geometry descriptor set"));
+        )?);
+        Ok(())
     }
 }
 
 impl Renderer for MarchedRenderer {
-    /// Recreates the ray_marching renderer's framebuffers, pipeline, and swapchain, all of which depend
-    /// on the window size.
-    fn recreate_all_size_dependent(&mut self) {
-        self.base.recreate_swapchain();
-        // TODO: use a different allocator?
-        let (framebuffers, pipeline) = window_size_dependent_setup(
-            &self.base.images,
+    /// Recreates the ray_marching renderer's framebuffers, HDR buffer, and swapchain, all of
+    /// which depend on the window size.
+    fn recreate_all_size_dependent(&mut self) -> Result<(), RhyoliteError> {
+        self.base.recreate_swapchain()?;
+        let (framebuffers, hdr_buffer) = window_size_dependent_setup(
+            &self.buffer_allocator,
+            &self.base.render_targets(),
             self.render_pass.clone(),
             &mut self.base.viewport,
-            &self.base.device,
-        );
+        )?;
         self.framebuffers = framebuffers;
-        self.pipeline = pipeline;
+        self.hdr_buffer = hdr_buffer;
+        Ok(())
     }
     fn get_base(&self) -> &RenderBase {
         &self.base
     }
+    fn get_base_mut(&mut self) -> &mut RenderBase {
+        &mut self.base
+    }
 }
 
-/// Sets up the framebuffers and graphics pipeline based on the size of the viewport
+/// Sets up the framebuffers based on the size of the viewport
 fn window_size_dependent_setup(
-    images: &[Arc<SwapchainImage>],
+    allocator: &StandardMemoryAllocator,
+    images: &[Arc<dyn ImageAccess>],
     render_pass: Arc<RenderPass>,
     viewport: &mut Viewport,
-    device: &Arc<Device>,
-) -> (Vec<Arc<Framebuffer>>, Arc<GraphicsPipeline>) {
+) -> Result<(Vec<Arc<Framebuffer>>, Arc<ImageView<AttachmentImage>>), RhyoliteError> {
     let dimensions = images[0].dimensions().width_height();
     viewport.dimensions = [dimensions[0] as f32, dimensions[1] as f32];
 
+    let hdr_buffer = ImageView::new_default(
+        AttachmentImage::transient_input_attachment(allocator, dimensions, HDR_FORMAT)?,
+    )?;
+
     let framebuffers = images
         .iter()
         .map(|image| {
-            let view = ImageView::new_default(image.clone()).unwrap();
-            Framebuffer::new(
+            let view = ImageView::new_default(image.clone())?;
+            Ok(Framebuffer::new(
                 render_pass.clone(),
                 FramebufferCreateInfo {
-                    attachments: vec![view],
+                    attachments: vec![view, hdr_buffer.clone()],
                     ..Default::default()
                 },
-            )
-            .unwrap()
+            )?)
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>, RhyoliteError>>()?;
 
-    let pipeline = get_pipeline(&render_pass, dimensions, device);
-
-    (framebuffers, pipeline)
+    Ok((framebuffers, hdr_buffer))
 }
 
 /// Gets the render pass to use with the ray_marching renderer. In Vulkan, a render pass is the set of
 /// attachments, the way they are used, and the rendering work that is performed using them.
-pub(crate) fn get_render_pass(device: &Arc<Device>, final_format: Format) -> Arc<RenderPass> {
-    vulkano::single_pass_renderpass!(
+pub(crate) fn get_render_pass(
+    device: &Arc<Device>,
+    final_format: Format,
+) -> Result<Arc<RenderPass>, RhyoliteError> {
+    Ok(vulkano::ordered_passes_renderpass!(
         device.clone(),
         attachments: {
+            // Every pixel is written by the tonemapping pass, so there's no need to clear it
             final_color: {
-                load: Clear,
+                load: DontCare,
                 store: Store,
                 format: final_format,
                 samples: 1,
+            },
+            hdr: {
+                load: Clear,
+                store: DontCare,
+                format: HDR_FORMAT,
+                samples: 1,
             }
         },
-        pass: {
-            color: [final_color],
-            depth_stencil: {}
-        }
-    )
-    .unwrap()
+        passes: [
+            {
+                color: [hdr],
+                depth_stencil: {},
+                input: []
+            },
+            {
+                color: [final_color],
+                depth_stencil: {},
+                input: [hdr]
+            }
+        ]
+    )?)
 }
 
-/// Gets the graphics pipeline containing the ray_marching vertex and fragment shaders.
+/// Gets the graphics pipeline containing the ray_marching vertex and fragment shaders, created
+/// through `cache`. The pipeline uses a dynamic viewport and scissor, which are set when each
+/// frame starts.
 pub fn get_pipeline(
     render_pass: &Arc<RenderPass>,
-    dimensions: [u32; 2],
     device: &Arc<Device>,
-) -> Arc<GraphicsPipeline> {
-    let shaders = ShaderModulePair::marched_default(device);
+    cache: &Arc<PipelineCache>,
+) -> Result<Arc<GraphicsPipeline>, RhyoliteError> {
+    let shaders = ShaderModulePair::marched_default(device)?;
 
-    GraphicsPipeline::start()
+    Ok(GraphicsPipeline::start()
         .vertex_input_state(DummyVertex::per_vertex())
         .vertex_shader(shaders.vert.entry_point("main").unwrap(), ())
         .input_assembly_state(InputAssemblyState::new())
-        .viewport_state(ViewportState::viewport_fixed_scissor_irrelevant([
-            Viewport {
-                origin: [0.0, 0.0],
-                dimensions: [dimensions[0] as f32, dimensions[1] as f32],
-                depth_range: 0.0..1.0,
-            },
-        ]))
+        .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
         .fragment_shader(shaders.frag.entry_point("main").unwrap(), ())
         .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
-        .build(device.clone())
-        .unwrap()
+        .build_with_cache(cache.clone())
+        .build(device.clone())?)
 }
 
 struct MarchedLightingData {
-    point_light_count: usize,
     set: Arc<PersistentDescriptorSet>,
 }
 
 /// A helper function that creates a partially uninitialized array, useful for creating a variable
 /// number of objects when a fixed-length array is required.
 ///
-/// # Panics
-/// - Panics if the number of elements in `values` exceeds `MAX_LEN`
-pub unsafe fn to_partially_init_arr<const MAX_LEN: usize, T>(values: impl IntoIterator<Item = T>) -> [T; MAX_LEN] {
+/// Returns `None` if the number of elements in `values` exceeds `MAX_LEN`. The elements that
+/// were already written are leaked rather than dropped.
+pub unsafe fn to_partially_init_arr<const MAX_LEN: usize, T>(values: impl IntoIterator<Item = T>) -> Option<[T; MAX_LEN]> {
     let mut uninit_array: MaybeUninit<[T; MAX_LEN]> = MaybeUninit::uninit();
     let mut ptr_i = uninit_array.as_mut_ptr() as *mut T;
 
     for (i, val) in values.into_iter().enumerate() {
-        if i + 1 == MAX_LEN {
-            panic!(
-                "Overflowed maximum capacity of partially initialized array: {}",
-                MAX_LEN
-            )
+        if i == MAX_LEN {
+            return None;
         }
         ptr_i.write(val);
         ptr_i = ptr_i.add(1);
     }
-    uninit_array.assume_init()
+    Some(uninit_array.assume_init())
 }
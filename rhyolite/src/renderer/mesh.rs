@@ -1,44 +1,50 @@
 use crate::camera::Camera;
 use crate::geometry::dummy::DummyVertex;
-use crate::geometry::mesh::loader::BasicVertex;
-use crate::geometry::mesh::{MeshObject, MeshObjectBuilder, MeshObjectParams};
-use crate::lighting::{AmbientLight, PointLight};
+use crate::geometry::mesh::loader::{BasicVertex, TexturedVertex, UnlitVertex};
+use crate::geometry::mesh::{Material, MeshObject, MeshObjectBuilder, MeshObjectParams, Texture};
+use crate::lighting::{AmbientLight, DirectionalLight, PointLight, SpotLight};
 use crate::renderer::staging::{IntoPersistentUniform, UniformSrc};
-use crate::shaders::{albedo_frag, Shaders, albedo_vert};
+use crate::shaders::{ShaderModulePair, Shaders, albedo_vert, ambient_frag, tiled_point_frag, transparent_frag};
+use crate::RhyoliteError;
 
 use vulkano;
-use vulkano::buffer::{BufferUsage, Subbuffer};
-use vulkano::command_buffer::{DrawIndirectCommand, SubpassContents, PipelineExecutionError};
+use vulkano::buffer::Subbuffer;
+use vulkano::command_buffer::{DrawIndexedIndirectCommand, DrawIndirectCommand, SubpassContents};
 use vulkano::descriptor_set::allocator::{StandardDescriptorSetAllocator, StandardDescriptorSetAlloc};
 use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet, DescriptorSetsCollection};
 use vulkano::device::Device;
-use vulkano::format::Format;
+use vulkano::format::{ClearValue, Format};
 use vulkano::image::view::ImageView;
-use vulkano::image::{AttachmentImage, ImageAccess, SwapchainImage};
-use vulkano::memory::allocator::{MemoryAllocator, MemoryUsage, StandardMemoryAllocator};
+use vulkano::image::{AttachmentImage, ImageAccess, SampleCount};
+use vulkano::memory::allocator::{MemoryAllocator, StandardMemoryAllocator};
 use vulkano::pipeline::graphics::color_blend::{
     AttachmentBlend, BlendFactor, BlendOp, ColorBlendState,
 };
-use vulkano::pipeline::graphics::depth_stencil::DepthStencilState;
+use vulkano::pipeline::graphics::depth_stencil::{CompareOp, DepthState, DepthStencilState};
 use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
+use vulkano::pipeline::graphics::multisample::MultisampleState;
 use vulkano::pipeline::graphics::rasterization::{CullMode, RasterizationState};
 use vulkano::pipeline::graphics::vertex_input::Vertex;
-use vulkano::pipeline::graphics::viewport::{Viewport, ViewportState};
-use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint};
+use vulkano::pipeline::graphics::viewport::{Scissor, Viewport, ViewportState};
+use vulkano::pipeline::cache::PipelineCache;
+use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint, StateMode};
 use vulkano::render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass};
 use winit::event_loop::EventLoop;
 
+use nalgebra_glm::{vec2, vec4, Mat4, Vec3};
 use std::sync::Arc;
-use vulkano::buffer::allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo};
+use vulkano::buffer::allocator::SubbufferAllocator;
 
-use super::{RenderBase, Renderer};
+use super::light_list::{CulledLights, LightList};
+use super::tonemap::{TonemapPass, HDR_FORMAT};
+use super::{RenderBase, RenderConfig, Renderer};
 
 // TODO: see if draw can be called ONCE after binding multiple vertex buffers
 // TODO: somehow support custom descriptors in `draw_object()`
 
 // TODO: implement instancing???
 pub enum DrawInfo<'a, T: Vertex> {
-    /// What you'll use most of the time. Draws a MeshObject.
+    /// What you'll use most of the time. Draws a MeshObject, using its index buffer if it has one.
     Vertex { object: &'a MeshObject<T> },
     /// Executes a graphics pipeline with a specified number of vertices, without binding a vertex buffer.
     /// Useful if you're using a custom uniform buffer instead of vertex data.
@@ -49,7 +55,17 @@ pub enum DrawInfo<'a, T: Vertex> {
     IndirectVertex { indirect_commands: Subbuffer<[DrawIndirectCommand]>, object: &'a MeshObject<T> },
     /// Executes a graphics pipeline with an indirectly-generated draw command, without binding a vertex buffer.
     /// Useful if you're using a custom uniform buffer instead of vertex data.
-    IndirectBlank { indirect_commands: Subbuffer<[DrawIndirectCommand]>}
+    IndirectBlank { indirect_commands: Subbuffer<[DrawIndirectCommand]>},
+    /// Draws the vertices of a MeshObject in the order specified by a separate index buffer,
+    /// rather than the object's own.
+    Indexed { object: &'a MeshObject<T>, index_buffer: Subbuffer<[u32]> },
+    /// Draws the vertices of a MeshObject with an index buffer and an indirectly-generated draw
+    /// command, usually both generated by a compute shader.
+    IndexedIndirect {
+        indirect_commands: Subbuffer<[DrawIndexedIndirectCommand]>,
+        object: &'a MeshObject<T>,
+        index_buffer: Subbuffer<[u32]>,
+    },
 }
 
 /// An enum representing the sequential stages of rendering necessary for construction of the
@@ -57,35 +73,44 @@ pub enum DrawInfo<'a, T: Vertex> {
 /// in the following order:
 /// 1. Albedo
 /// 2. Ambient
-/// 3. Point (optional)
+/// 3. Point, Directional, and Spot, in any order (optional)
 /// 4. Unlit (optional)
+/// 5. Transparent (optional)
 #[derive(Debug, Clone, PartialEq)]
 enum RenderStage {
     Stopped,
     Albedo,
     Ambient,
     Point,
+    Directional,
+    Spot,
     Unlit,
+    Transparent,
 }
 
 impl RenderStage {
     /// Advances this `RenderStage`'s value to match that of new_stage.
-    /// # Panics
+    /// # Errors
     /// Since advancement between `RenderStage`s is meant to be manually implemented rather than
-    /// determined programmatically, the purpose of this function is to panic if the stages are
-    /// called out of order. The function will panic in these circumstances:
+    /// determined programmatically, the purpose of this function is to return
+    /// `RhyoliteError::StageOrder` if the stages are called out of order, leaving the current
+    /// stage unchanged. The function will return an error in these circumstances:
     /// 1. Trying to enter `RenderStage::Albedo` when the current stage is something other than
     /// `RenderStage::Stopped` or `RenderStage::Albedo`
     /// 2. Trying to enter `RenderStage::Ambient` when the current stage is something other than
     /// `RenderStage::Albedo`
-    /// 3. Trying to enter `RenderStage::Point` when the current stage is something other than
-    /// `RenderStage::Ambient` or `RenderStage::Point`
+    /// 3. Trying to enter one of the light stages (`RenderStage::Point`,
+    /// `RenderStage::Directional`, or `RenderStage::Spot`) when the current stage is something
+    /// other than `RenderStage::Ambient` or a light stage
     /// 4. Trying to enter `RenderStage::Unlit` when the current stage is something other than
-    /// `RenderStage::Ambient`, `RenderStage::Point`, or `RenderStage::Unlit`
-    /// 5. Trying to enter `RenderStage::Stopped` (usually by calling the renderer's `finish()`
-    /// function) when the current stage is something other than `RenderStage::Ambient`,
-    /// `RenderStage::Point`, or `RenderStage::Unlit`
-    fn update(&mut self, new_stage: RenderStage) {
+    /// `RenderStage::Ambient`, a light stage, or `RenderStage::Unlit`
+    /// 5. Trying to enter `RenderStage::Transparent` when the current stage is something other
+    /// than `RenderStage::Ambient`, a light stage, `RenderStage::Unlit`, or
+    /// `RenderStage::Transparent`
+    /// 6. Trying to enter `RenderStage::Stopped` (usually by calling the renderer's `finish()`
+    /// function) when the current stage is something other than `RenderStage::Ambient`, a light
+    /// stage, `RenderStage::Unlit`, or `RenderStage::Transparent`
+    fn update(&mut self, new_stage: RenderStage) -> Result<(), RhyoliteError> {
         let mut out_of_order = false;
         match new_stage {
             RenderStage::Albedo => match self {
@@ -101,32 +126,67 @@ impl RenderStage {
                 }
                 _ => out_of_order = true,
             },
-            RenderStage::Point => match self {
-                RenderStage::Ambient => {
-                    *self = RenderStage::Point;
+            RenderStage::Point | RenderStage::Directional | RenderStage::Spot => match self {
+                RenderStage::Ambient
+                | RenderStage::Point
+                | RenderStage::Directional
+                | RenderStage::Spot => {
+                    *self = new_stage.clone();
                 }
-                RenderStage::Point => (),
                 _ => out_of_order = true,
             },
             RenderStage::Unlit => match self {
-                RenderStage::Ambient | RenderStage::Point => {
+                RenderStage::Ambient
+                | RenderStage::Point
+                | RenderStage::Directional
+                | RenderStage::Spot => {
                     *self = RenderStage::Unlit;
                 }
                 RenderStage::Unlit => (),
                 _ => out_of_order = true,
             },
+            RenderStage::Transparent => match self {
+                RenderStage::Ambient
+                | RenderStage::Point
+                | RenderStage::Directional
+                | RenderStage::Spot
+                | RenderStage::Unlit => {
+                    *self = RenderStage::Transparent;
+                }
+                RenderStage::Transparent => (),
+                _ => out_of_order = true,
+            },
             RenderStage::Stopped => match self {
-                RenderStage::Ambient | RenderStage::Point | RenderStage::Unlit => {
+                RenderStage::Ambient
+                | RenderStage::Point
+                | RenderStage::Directional
+                | RenderStage::Spot
+                | RenderStage::Unlit
+                | RenderStage::Transparent => {
                     *self = RenderStage::Stopped;
                 }
                 _ => out_of_order = true,
             },
         }
         if out_of_order {
-            panic!(
-                "can't enter {:?} stage after {:?} stage, rendering stopped",
-                new_stage, self
-            )
+            return Err(RhyoliteError::StageOrder {
+                attempted: new_stage.name(),
+                current: self.name(),
+            });
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            RenderStage::Stopped => "Stopped",
+            RenderStage::Albedo => "Albedo",
+            RenderStage::Ambient => "Ambient",
+            RenderStage::Point => "Point",
+            RenderStage::Directional => "Directional",
+            RenderStage::Spot => "Spot",
+            RenderStage::Unlit => "Unlit",
+            RenderStage::Transparent => "Transparent",
         }
     }
 }
@@ -138,13 +198,22 @@ pub struct MeshRenderer {
 
     buffer_allocator: Arc<StandardMemoryAllocator>,
     descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
-    subbuffer_allocator: SubbufferAllocator,
 
     vp_set: Option<Arc<PersistentDescriptorSet>>,
+    /// The view and projection matrices of the camera of the current frame
+    view_projection: Option<(Mat4, Mat4)>,
 
     dummy_vertex_buf: Subbuffer<[DummyVertex]>,
 
+    /// The point lights drawn with `draw_point_lights()`, and the tiles they were sorted into
+    /// for the current frame
+    light_list: LightList,
+    culled_lights: Option<CulledLights>,
+    /// The ambient light of the current frame, which transparent objects are also lit by
+    ambient_buffer: Option<Subbuffer<ambient_frag::UAmbientLightData>>,
+
     pipelines: Pipelines,
+    tonemap_pass: TonemapPass,
     framebuffers: Vec<Arc<Framebuffer>>,
     attachment_buffers: AttachmentBuffers,
 
@@ -152,12 +221,22 @@ pub struct MeshRenderer {
 }
 
 impl MeshRenderer {
-    pub fn new(event_loop: &EventLoop<()>) -> Self {
-        let mut base = RenderBase::new(&event_loop);
+    /// Creates a new `MeshRenderer` that draws to a window created with the specified event loop
+    /// and configuration.
+    pub fn new(event_loop: &EventLoop<()>, config: &RenderConfig) -> Result<Self, RhyoliteError> {
+        Self::from_base(RenderBase::new(&event_loop, config)?)
+    }
 
+    /// Creates a new `MeshRenderer` that draws to an offscreen image of `config.window_size`
+    /// pixels, without creating a window.
+    pub fn headless(config: &RenderConfig) -> Result<Self, RhyoliteError> {
+        Self::from_base(RenderBase::headless(config)?)
+    }
+
+    fn from_base(mut base: RenderBase) -> Result<Self, RhyoliteError> {
         // Declare the render pass, a structure that lets us define how the rendering process should work. Tells the hardware
         // where it can expect to find input and where it can store output
-        let render_pass = get_render_pass(&base.device, base.swapchain.image_format());
+        let render_pass = get_render_pass(&base.device, base.image_format(), base.samples())?;
         // let pipelines = Pipelines::new(&render_pass, &device);
 
         // Buffer allocators
@@ -167,56 +246,87 @@ impl MeshRenderer {
         // TODO: use a descriptor pool instead of a descriptor set allocator
         let descriptor_set_allocator = Arc::from(StandardDescriptorSetAllocator::new(base.device.clone()));
 
-        // A buffer pool for all data used in rendering, including ambient lights, point lights, albedo, unlit, and view/projection matrices
-        let subbuffer_allocator = SubbufferAllocator::new(
-            buffer_allocator.clone(),
-            SubbufferAllocatorCreateInfo {
-                arena_size: 512, // TODO: FIND THE ACTUAL VALUE
-                buffer_usage: BufferUsage::UNIFORM_BUFFER,
-                memory_usage: MemoryUsage::Upload,
-                ..Default::default()
-            },
-        );
-
         // Create a dummy vertex buffer used for full-screen shaders
-        let dummy_vertex_buf = DummyVertex::buf(&buffer_allocator, &base);
+        let dummy_vertex_buf = DummyVertex::buf(&buffer_allocator, &base)?;
+
+        // The viewport and scissor are set when each frame starts, so the pipelines don't need to
+        // be recreated when the window is resized
+        let pipelines = Pipelines::new(
+            &render_pass,
+            &base.device,
+            base.pipeline_cache(),
+            base.samples(),
+        )?;
+        let light_list = LightList::new(&base)?;
+        let tonemap_pass = TonemapPass::new(
+            Subpass::from(render_pass.clone(), 3).unwrap(),
+            &base,
+            base.samples(),
+        )?;
 
         // Includes framebuffers and other attachments that aren't stored
-        let (framebuffers, attachment_buffers, pipelines) = window_size_dependent_setup(
+        let (framebuffers, attachment_buffers) = window_size_dependent_setup(
             &buffer_allocator,
-            &base.images,
+            &base.render_targets(),
             render_pass.clone(),
             &mut base.viewport,
-            &base.device,
-        );
+            base.samples(),
+        )?;
 
-        Self {
+        Ok(Self {
             base,
 
             render_pass,
 
             buffer_allocator,
             descriptor_set_allocator,
-            subbuffer_allocator,
 
             vp_set: None,
+            view_projection: None,
 
             dummy_vertex_buf,
 
+            light_list,
+            culled_lights: None,
+            ambient_buffer: None,
+
             pipelines,
+            tonemap_pass,
             framebuffers,
             attachment_buffers,
 
             render_stage: RenderStage::Stopped,
-        }
+        })
     }
 
     /// Starts the rendering process for the current frame
-    pub fn start_render_pass(&mut self, camera: &mut Camera) {
+    pub fn start_render_pass(&mut self, camera: &mut Camera) -> Result<(), RhyoliteError> {
         if !camera.is_configured() {
             camera.configure(self.get_window_size());
         }
 
+        if self.base.should_recreate_swapchain {
+            camera.configure(self.get_window_size());
+            self.recreate_all_size_dependent()?;
+        }
+
+        // The albedo buffer is cleared with an alpha of 0, which the ambient pass uses to skip
+        // the pixels where no objects were drawn. The HDR buffer is cleared to transparent
+        // black, and the tonemapping pass fills those pixels with the clear color.
+        let clear_values = vec![
+            None,
+            Some(ClearValue::Float([0.0, 0.0, 0.0, 0.0])),
+            Some(ClearValue::Float([0.0, 0.0, 0.0, 0.0])),
+            Some(ClearValue::Float([0.0, 0.0, 0.0, 1.0])),
+            Some(ClearValue::Float([0.0, 0.0, 0.0, 1.0])),
+            Some(ClearValue::Float([0.0, 0.0, 0.0, 1.0])),
+            Some(ClearValue::Depth(1f32)),
+        ];
+
+        // Beginning the frame waits for the frame slot, so its subbuffers must be allocated
+        // afterward
+        self.base.begin_frame()?;
+
         let vp_layout = self
             .pipelines
             .albedo
@@ -225,33 +335,79 @@ impl MeshRenderer {
             .get(0)
             .unwrap()
             .clone();
-        let vp_subbuffer = camera.get_vp_subbuffer(&self.subbuffer_allocator).unwrap();
-        self.vp_set = Some(
-            PersistentDescriptorSet::new(
+        let vp_subbuffer = camera.get_vp_subbuffer(self.base.subbuffer_allocator())?;
+        self.vp_set = Some(PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            vp_layout,
+            [WriteDescriptorSet::buffer(0, vp_subbuffer)],
+        )?);
+        let (view, projection) = camera.view_projection()?;
+        self.view_projection = Some((view, projection));
+
+        // Point lights are sorted into tiles by a compute pass, which has to be recorded before
+        // the render pass begins. Transparent objects always read the tiles, so they're culled
+        // even if there are no lights.
+        self.culled_lights = None;
+        self.ambient_buffer = None;
+        if !self.base.render_error {
+            self.base.begin_stage_label("Light culling")?;
+            self.culled_lights = Some(self.light_list.cull(
+                &mut self.base,
                 &self.descriptor_set_allocator,
-                vp_layout,
-                [WriteDescriptorSet::buffer(0, vp_subbuffer)],
-            )
-            .unwrap(),
-        );
-
-        if self.base.should_recreate_swapchain {
-            camera.configure(self.get_window_size());
-            self.recreate_all_size_dependent();
+                projection * view,
+            )?);
+            self.base.end_stage_label()?;
         }
 
-        self.base.start(&mut self.framebuffers);
+        self.base.begin_render_pass(&self.framebuffers, clear_values)
+    }
+
+    /// Sets the point lights drawn by `draw_point_lights()`, starting with the next frame. Unlike
+    /// `draw_point_light()`, which shades the whole screen (or the light's scissor rectangle)
+    /// once per light, the lights are sorted into screen tiles by a compute pass, and each pixel
+    /// is only shaded by the lights that can reach its tile.
+    pub fn set_point_lights<'a>(&mut self, lights: impl IntoIterator<Item = &'a PointLight>) {
+        self.light_list.set_lights(lights);
+    }
+
+    /// Moves on to the specified stage, labeling its commands if it's different from the current
+    /// one
+    fn enter_stage(&mut self, stage: RenderStage) -> Result<(), RhyoliteError> {
+        let previous = self.render_stage.clone();
+        self.render_stage.update(stage.clone())?;
+        if stage != previous && stage != RenderStage::Stopped && !self.base.render_error {
+            self.base.begin_stage_label(stage.name())?;
+        }
+        Ok(())
     }
 
     /// Finishes the rendering process and draws to the screen
-    /// # Panics
-    /// Panics if not called after a `draw_object_unlit()` call or a `draw_point()` call
-    pub fn end_render_pass(&mut self) {
+    /// # Errors
+    /// Returns `RhyoliteError::StageOrder` if not called after a `draw_ambient_light()` call or a
+    /// light's draw call
+    pub fn end_render_pass(&mut self) -> Result<(), RhyoliteError> {
         if self.base.render_error {
-            return;
+            return Ok(());
+        }
+        let previous = self.render_stage.clone();
+        self.enter_stage(RenderStage::Stopped)?;
+
+        // The transparent subpass is skipped if nothing transparent was drawn
+        if previous != RenderStage::Transparent {
+            self.base
+                .commands_mut()?
+                .next_subpass(SubpassContents::Inline)?;
         }
-        self.render_stage.update(RenderStage::Stopped);
-        self.base.finish();
+        self.base
+            .commands_mut()?
+            .next_subpass(SubpassContents::Inline)?;
+        self.tonemap_pass.draw(
+            &mut self.base,
+            &self.descriptor_set_allocator,
+            self.attachment_buffers.hdr_buffer.clone(),
+            &self.dummy_vertex_buf,
+        )?;
+        self.base.finish()
     }
 
     /// Gets the descriptors used with the default lit graphics pipeline. These descriptors are formatted as follows:
@@ -265,20 +421,28 @@ impl MeshRenderer {
     /// | binding 0: UModelData
     /// | | model (mat4)
     /// | | normals (mat4)
-    /// | binding 1: USpecularData
-    /// | | intensity (float)
+    /// set 2: material_set
+    /// | binding 0: UMaterialData
+    /// | | albedo (vec4)
+    /// | | specular_intensity (float)
     /// | | shininess (float)
     /// ```
+    ///
+    /// The material set is stored in the object's `Material`, so it's only recreated when the
+    /// material changes.
+    ///
+    /// # Errors
+    /// - Returns `RhyoliteError::Unconfigured` if the material of `params` hasn't been uploaded
+    /// with `upload_material()`, or if the render pass hasn't been started.
     pub fn default_lit_descriptors(
         &self, params: &MeshObjectParams
-    ) -> (Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>, Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>) {
-        let model_subbuffer: Subbuffer<albedo_vert::UModelData> = self.subbuffer_allocator.allocate_sized().unwrap();
-        *model_subbuffer.write().unwrap() = params.get_raw();
-
-        // TODO: Do this with textures instead!!!!!!!!! Not a subbuffer!!!!!!!!!
-        // or at least store the buffer instead of recreating it every frame.....
-        let specular_subbuffer: Subbuffer<albedo_frag::USpecularData> = self.subbuffer_allocator.allocate_sized().unwrap();
-        *specular_subbuffer.write().unwrap() = params.get_raw();
+    ) -> Result<(
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+    ), RhyoliteError> {
+        let model_subbuffer: Subbuffer<albedo_vert::UModelData> = self.base.subbuffer_allocator().allocate_sized()?;
+        *model_subbuffer.write()? = params.get_raw();
 
         let albedo_layout = self
             .pipelines
@@ -291,13 +455,199 @@ impl MeshRenderer {
         let albedo_set = PersistentDescriptorSet::new(
             &self.descriptor_set_allocator,
             albedo_layout.clone(),
-            [
-                WriteDescriptorSet::buffer(0, model_subbuffer),
-                WriteDescriptorSet::buffer(1, specular_subbuffer),
-            ],
-        ).unwrap();
+            [WriteDescriptorSet::buffer(0, model_subbuffer)],
+        )?;
+
+        let material_set = params
+            .material
+            .descriptor_set()
+            .ok_or(RhyoliteError::Unconfigured(
+                "The material must be uploaded with `upload_material()` before it's drawn"
+            ))?
+            .clone();
         
-        (self.vp_set.as_ref().unwrap().clone(), albedo_set.clone())
+        Ok((self.vp_set()?, albedo_set, material_set))
+    }
+
+    /// Gets the descriptors used with the default unlit graphics pipeline. These descriptors are
+    /// formatted as follows:
+    ///
+    /// ```txt
+    /// set 0: vp_set
+    /// | binding 0: UCamData
+    /// | | view (mat4)
+    /// | | projection (mat4)
+    /// set 1: model_set
+    /// | binding 0: UModelData
+    /// | | model (mat4)
+    /// | | normals (mat4, unused)
+    /// ```
+    ///
+    /// Unlit objects take their color from their vertices, so their material isn't used.
+    ///
+    /// # Errors
+    /// - Returns `RhyoliteError::Unconfigured` if the render pass hasn't been started.
+    pub fn default_unlit_descriptors(
+        &self, params: &MeshObjectParams
+    ) -> Result<(
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+    ), RhyoliteError> {
+        let model_subbuffer: Subbuffer<albedo_vert::UModelData> = self.base.subbuffer_allocator().allocate_sized()?;
+        *model_subbuffer.write()? = params.get_raw();
+
+        let model_layout = self
+            .pipelines
+            .unlit
+            .layout()
+            .set_layouts()
+            .get(1)
+            .unwrap()
+            .clone();
+        let model_set = PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            model_layout,
+            [WriteDescriptorSet::buffer(0, model_subbuffer)],
+        )?;
+
+        Ok((self.vp_set()?, model_set))
+    }
+
+    /// Gets the descriptors used with the default transparent graphics pipeline. The first three
+    /// sets are identical to those returned by `default_lit_descriptors`, followed by the lights
+    /// of the current frame:
+    ///
+    /// ```txt
+    /// set 3: lighting_set
+    /// | binding 0: UAmbientLightData
+    /// | | color (vec4)
+    /// | | intensity (float)
+    /// | binding 1: UPointLights (storage buffer)
+    /// | | data (UPointLight[])
+    /// | binding 2: UTileLights (storage buffer)
+    /// | | data (uint[])
+    /// ```
+    ///
+    /// The pipeline also expects a `UTileData` push constant with the number of tiles in each row
+    /// of the screen.
+    ///
+    /// # Errors
+    /// - Returns `RhyoliteError::Unconfigured` if the material of `params` hasn't been uploaded
+    /// with `upload_material()`, if the render pass hasn't been started, or if no ambient light
+    /// has been drawn this frame.
+    pub fn default_transparent_descriptors(
+        &self, params: &MeshObjectParams
+    ) -> Result<(
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+    ), RhyoliteError> {
+        let (vp_set, albedo_set, material_set) = self.default_lit_descriptors(params)?;
+
+        let ambient_buffer = self.ambient_buffer.clone().ok_or(RhyoliteError::Unconfigured(
+            "An ambient light must be drawn with `draw_ambient_light()` before transparent objects"
+        ))?;
+        let lighting_layout = self
+            .pipelines
+            .transparent
+            .layout()
+            .set_layouts()
+            .get(3)
+            .unwrap()
+            .clone();
+        let lighting_set = PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            lighting_layout,
+            [WriteDescriptorSet::buffer(0, ambient_buffer)]
+                .into_iter()
+                .chain(self.culled_lights()?.descriptors(1)),
+        )?;
+
+        Ok((vp_set, albedo_set, material_set, lighting_set))
+    }
+
+    /// Gets the descriptors used with the default textured graphics pipeline. The first three
+    /// sets are identical to those returned by `default_lit_descriptors`, followed by the texture:
+    ///
+    /// ```txt
+    /// set 3: texture_set
+    /// | binding 0: albedo_texture (sampler2D)
+    /// ```
+    ///
+    /// # Errors
+    /// - Returns `RhyoliteError::Unconfigured` if the material of `params` hasn't been uploaded
+    /// with `upload_material()`, or if the render pass hasn't been started.
+    pub fn default_textured_descriptors(
+        &self, params: &MeshObjectParams, texture: &Texture
+    ) -> Result<(
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+        Arc<PersistentDescriptorSet<StandardDescriptorSetAlloc>>,
+    ), RhyoliteError> {
+        let (vp_set, albedo_set, material_set) = self.default_lit_descriptors(params)?;
+
+        let texture_layout = self
+            .pipelines
+            .albedo_textured
+            .layout()
+            .set_layouts()
+            .get(3)
+            .unwrap()
+            .clone();
+        let texture_set = PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            texture_layout,
+            [WriteDescriptorSet::image_view_sampler(
+                0,
+                texture.view().clone(),
+                texture.sampler().clone(),
+            )],
+        )?;
+
+        Ok((vp_set, albedo_set, material_set, texture_set))
+    }
+
+    /// Uploads a material's uniform buffer and creates the descriptor set used to draw it with
+    /// the default pipelines. Nothing is uploaded if the material hasn't changed since it was
+    /// last uploaded.
+    pub fn upload_material(&self, material: &mut Material) -> Result<(), RhyoliteError> {
+        if material.is_uploaded() {
+            return Ok(());
+        }
+
+        let material_layout = self
+            .pipelines
+            .albedo
+            .layout()
+            .set_layouts()
+            .get(2)
+            .unwrap()
+            .clone();
+        let material_set = PersistentDescriptorSet::new(
+            &self.descriptor_set_allocator,
+            material_layout,
+            [WriteDescriptorSet::buffer(0, material.get_buffer(&self.buffer_allocator, &self.base)?)],
+        )?;
+
+        material.set_descriptor_set(material_set);
+        Ok(())
+    }
+
+    /// Gets the descriptor set of the camera, which is created when the render pass is started.
+    fn vp_set(&self) -> Result<Arc<PersistentDescriptorSet>, RhyoliteError> {
+        self.vp_set.clone().ok_or(RhyoliteError::Unconfigured(
+            "The render pass must be started with `start_render_pass()` before drawing"
+        ))
+    }
+
+    /// Gets the point lights of the current frame, which are culled when the render pass is
+    /// started.
+    fn culled_lights(&self) -> Result<&CulledLights, RhyoliteError> {
+        self.culled_lights.as_ref().ok_or(RhyoliteError::Unconfigured(
+            "The render pass must be started with `start_render_pass()` before drawing"
+        ))
     }
 
     /// The base operations for drawing. Meant to be paired with `draw_lit` and `draw_unlit`. If there is an error during
@@ -306,10 +656,11 @@ impl MeshRenderer {
         &mut self, data: DrawInfo<impl Vertex>, 
         pipeline: Arc<GraphicsPipeline>, 
         descriptors: impl DescriptorSetsCollection
-    ) -> Result<(), PipelineExecutionError> {
-        self.base.commands_mut().bind_pipeline_graphics(pipeline.clone());
+    ) -> Result<(), RhyoliteError> {
+        let commands = self.base.commands_mut()?;
+        commands.bind_pipeline_graphics(pipeline.clone());
 
-        self.base.commands_mut().bind_descriptor_sets(
+        commands.bind_descriptor_sets(
             PipelineBindPoint::Graphics,
             pipeline.layout().clone(),
             0,
@@ -318,23 +669,40 @@ impl MeshRenderer {
 
         match data {
             DrawInfo::Vertex { object } => {
-                self.base.commands_mut()
-                    .bind_vertex_buffers(0, object.vertex_buffer().clone())
-                    .draw(object.vertex_buffer().len() as u32, 1, 0, 0)?
+                let commands = commands
+                    .bind_vertex_buffers(0, object.vertex_buffer().clone());
+                match object.index_buffer() {
+                    Some(index_buffer) => commands
+                        .bind_index_buffer(index_buffer.clone())
+                        .draw_indexed(index_buffer.len() as u32, 1, 0, 0, 0)?,
+                    None => commands.draw(object.vertex_buffer().len() as u32, 1, 0, 0)?,
+                }
             },
             DrawInfo::Blank { vertex_count, .. } =>{
-                self.base.commands_mut()
+                commands
                     .draw(vertex_count, 1, 0, 0)?
             },
             DrawInfo::IndirectVertex { indirect_commands, object } => {
-                self.base.commands_mut()
+                commands
                     .bind_vertex_buffers(0, object.vertex_buffer().clone())
                     .draw_indirect(indirect_commands)?
             },
             DrawInfo::IndirectBlank { indirect_commands, .. } => {
-                self.base.commands_mut()
+                commands
                     .draw_indirect(indirect_commands)?
             },
+            DrawInfo::Indexed { object, index_buffer } => {
+                commands
+                    .bind_vertex_buffers(0, object.vertex_buffer().clone())
+                    .bind_index_buffer(index_buffer.clone())
+                    .draw_indexed(index_buffer.len() as u32, 1, 0, 0, 0)?
+            },
+            DrawInfo::IndexedIndirect { indirect_commands, object, index_buffer } => {
+                commands
+                    .bind_vertex_buffers(0, object.vertex_buffer().clone())
+                    .bind_index_buffer(index_buffer)
+                    .draw_indexed_indirect(indirect_commands)?
+            },
         };
         Ok(())
     }
@@ -344,15 +712,17 @@ impl MeshRenderer {
 
     /// Draws an object that will later be lit. If `pipeline` is `None`, the function uses the default lit pipeline. 
     /// 
-    /// # Panics
-    /// - Panics if not called after a `start()` call or another `draw_object()` call.
+    /// # Errors
+    /// - Returns `RhyoliteError::StageOrder` if not called after a `start()` call or another
+    /// `draw_lit()` call, and an error if the pipeline can't be executed, such as with an
+    /// incorrect binding.
     pub fn draw_lit(
         &mut self,
         info: DrawInfo<impl Vertex>, 
         pipeline: Arc<GraphicsPipeline>, 
         descriptors: impl DescriptorSetsCollection,
-    ) -> Result<(), PipelineExecutionError> {
-        self.render_stage.update(RenderStage::Albedo);
+    ) -> Result<(), RhyoliteError> {
+        self.enter_stage(RenderStage::Albedo)?;
         if self.base.render_error {
             return Ok(());
         }
@@ -365,33 +735,48 @@ impl MeshRenderer {
     /// Has the same functionality as `draw_lit()`, with the only difference being the fact that it will attempt to
     /// use the default lit pipeline and descriptor sets (see `default_lit_descriptors`) rather than a custom one. 
     /// 
-    /// # Panics
-    /// - This function should **NEVER** be used with `DrawInfo::Blank` or `DrawInfo::IndirectBlank`. If it is, it 
-    /// will immediately panic.
+    /// # Errors
+    /// - Returns the same errors as `draw_lit()` and `default_lit_descriptors()`.
+    /// - Returns `RhyoliteError::Unconfigured` if `info` is `DrawInfo::Blank` or `DrawInfo::IndirectBlank`,
+    /// since neither has an object to get the descriptors from.
     pub fn draw_lit_auto (
         &mut self,
         info: DrawInfo<impl Vertex>,
-    ) {
+    ) -> Result<(), RhyoliteError> {
         let pipeline = self.pipelines.albedo.clone();
         
-        let descriptors = self.default_lit_descriptors(
-            match &info {
-                DrawInfo::Vertex { object } | DrawInfo::IndirectVertex { object, .. } => object.params(),
-                DrawInfo::Blank { .. } | DrawInfo::IndirectBlank { .. } => panic!("No descriptor sets were added or bound. This should probably be implemented (or check if () implements DescriptorSetsCollection)")
-            }
-        );
+        let descriptors = self.default_lit_descriptors(auto_params(&info)?)?;
 
-        self.draw_lit(info, pipeline, descriptors).unwrap(); // Unwrap is safe to use here because bindings are correct
+        self.draw_lit(info, pipeline, descriptors)
+    }
+
+    /// Has the same functionality as `draw_lit_auto()`, but draws an object with texture
+    /// coordinates using the default textured pipeline, which samples its color from `texture`.
+    ///
+    /// # Errors
+    /// - Returns the same errors as `draw_lit()` and `default_textured_descriptors()`.
+    /// - Returns `RhyoliteError::Unconfigured` if `info` is `DrawInfo::Blank` or `DrawInfo::IndirectBlank`,
+    /// since neither has an object to get the descriptors from.
+    pub fn draw_textured_auto(
+        &mut self,
+        info: DrawInfo<TexturedVertex>,
+        texture: &Texture,
+    ) -> Result<(), RhyoliteError> {
+        let pipeline = self.pipelines.albedo_textured.clone();
+
+        let descriptors = self.default_textured_descriptors(auto_params(&info)?, texture)?;
+
+        self.draw_lit(info, pipeline, descriptors)
     }
 
     /// Draws an ambient light, which adds global illumination to the entire scene
-    /// # Panics
-    /// Panics if not called after a `draw_object()` call
-    pub fn draw_ambient_light(&mut self, light: &mut AmbientLight) {
+    /// # Errors
+    /// Returns `RhyoliteError::StageOrder` if not called after a `draw_lit()` call
+    pub fn draw_ambient_light(&mut self, light: &mut AmbientLight) -> Result<(), RhyoliteError> {
         if self.base.render_error {
-            return;
+            return Ok(());
         }
-        self.render_stage.update(RenderStage::Ambient);
+        self.enter_stage(RenderStage::Ambient)?;
 
         let ambient_layout = self
             .pipelines
@@ -401,21 +786,21 @@ impl MeshRenderer {
             .get(0)
             .unwrap();
 
+        let light_buffer = light.get_buffer(&self.buffer_allocator, &self.base)?;
         let ambient_set = PersistentDescriptorSet::new(
             &self.descriptor_set_allocator,
             ambient_layout.clone(),
             [
                 WriteDescriptorSet::image_view(0, self.attachment_buffers.albedo_buffer.clone()),
-                WriteDescriptorSet::buffer(1, light.get_buffer(&self.buffer_allocator, &self.base)),
+                WriteDescriptorSet::buffer(1, light_buffer.clone()),
             ],
-        )
-        .unwrap();
+        )?;
+        self.ambient_buffer = Some(light_buffer);
 
         // Add ambient light commands to the command buffer
         self.base
-            .commands_mut()
-            .next_subpass(SubpassContents::Inline)
-            .unwrap()
+            .commands_mut()?
+            .next_subpass(SubpassContents::Inline)?
             .bind_pipeline_graphics(self.pipelines.ambient.clone())
             .bind_descriptor_sets(
                 PipelineBindPoint::Graphics,
@@ -424,79 +809,318 @@ impl MeshRenderer {
                 ambient_set.clone(),
             )
             .bind_vertex_buffers(0, self.dummy_vertex_buf.clone())
-            .draw(self.dummy_vertex_buf.len() as u32, 1, 0, 0)
-            .unwrap();
+            .draw(self.dummy_vertex_buf.len() as u32, 1, 0, 0)?;
+        Ok(())
     }
 
     /// Draws a point light with a specified color and position
-    /// # Panics
-    /// Panics if not called after a `draw_ambient()` call or `another draw_point()` call
-    pub fn draw_point_light(&mut self, light: &mut PointLight) {
+    /// # Errors
+    /// Returns `RhyoliteError::StageOrder` if not called after a `draw_ambient_light()` call or
+    /// another light's draw call
+    pub fn draw_point_light(&mut self, light: &mut PointLight) -> Result<(), RhyoliteError> {
         if self.base.render_error {
-            return;
+            return Ok(());
         }
-        self.render_stage.update(RenderStage::Point);
+        self.enter_stage(RenderStage::Point)?;
 
-        let point_layout = self
-            .pipelines
-            .point
+        // Lights with a limited range only shade the pixels that they can reach
+        let scissor = match light.radius() {
+            Some(radius) => match self.sphere_scissor(light.position(), radius) {
+                Some(scissor) => Some(scissor),
+                None => return Ok(()),
+            },
+            None => None,
+        };
+
+        let light_buffer = light.get_buffer(&self.buffer_allocator, &self.base)?;
+        self.draw_deferred_light(
+            self.pipelines.point.clone(),
+            [WriteDescriptorSet::buffer(4, light_buffer)],
+            scissor,
+        )
+    }
+
+    /// Draws all of the point lights set with `set_point_lights()` in a single pass. Does nothing
+    /// if no point lights have been set.
+    /// # Errors
+    /// Returns `RhyoliteError::StageOrder` if not called after a `draw_ambient_light()` call or
+    /// another light's draw call
+    pub fn draw_point_lights(&mut self) -> Result<(), RhyoliteError> {
+        if self.base.render_error {
+            return Ok(());
+        }
+        self.enter_stage(RenderStage::Point)?;
+
+        if self.light_list.is_empty() {
+            return Ok(());
+        }
+        let culled = self.culled_lights()?;
+        let (descriptors, tiles_per_row) = (culled.descriptors(4), culled.tiles_per_row);
+
+        let pipeline = self.pipelines.tiled_point.clone();
+        self.base.commands_mut()?.push_constants(
+            pipeline.layout().clone(),
+            0,
+            tiled_point_frag::UTileData { tiles_per_row },
+        );
+        self.draw_deferred_light(pipeline, descriptors, None)
+    }
+
+    /// Draws a directional light, which lights every surface from the same direction regardless
+    /// of its distance
+    /// # Errors
+    /// Returns `RhyoliteError::StageOrder` if not called after a `draw_ambient_light()` call or
+    /// another light's draw call
+    pub fn draw_directional_light(&mut self, light: &mut DirectionalLight) -> Result<(), RhyoliteError> {
+        if self.base.render_error {
+            return Ok(());
+        }
+        self.enter_stage(RenderStage::Directional)?;
+
+        let light_buffer = light.get_buffer(&self.buffer_allocator, &self.base)?;
+        self.draw_deferred_light(
+            self.pipelines.directional.clone(),
+            [WriteDescriptorSet::buffer(4, light_buffer)],
+            None,
+        )
+    }
+
+    /// Draws a spot light, which lights the surfaces inside of its cone
+    /// # Errors
+    /// Returns `RhyoliteError::StageOrder` if not called after a `draw_ambient_light()` call or
+    /// another light's draw call
+    pub fn draw_spot_light(&mut self, light: &mut SpotLight) -> Result<(), RhyoliteError> {
+        if self.base.render_error {
+            return Ok(());
+        }
+        self.enter_stage(RenderStage::Spot)?;
+
+        let light_buffer = light.get_buffer(&self.buffer_allocator, &self.base)?;
+        self.draw_deferred_light(
+            self.pipelines.spot.clone(),
+            [WriteDescriptorSet::buffer(4, light_buffer)],
+            None,
+        )
+    }
+
+    /// Draws a full-screen lighting pass with `pipeline`, which reads the G-buffer attachments
+    /// from bindings 0 to 3 of set 1, and the light's buffers from the `light_descriptors` after
+    /// them. If there's a `scissor`, only the pixels inside of it are shaded.
+    fn draw_deferred_light(
+        &mut self,
+        pipeline: Arc<GraphicsPipeline>,
+        light_descriptors: impl IntoIterator<Item = WriteDescriptorSet>,
+        scissor: Option<Scissor>,
+    ) -> Result<(), RhyoliteError> {
+        let light_layout = pipeline
             .layout()
             .set_layouts()
             .get(1)
             .unwrap()
             .clone();
 
-        let point_set = PersistentDescriptorSet::new(
+        let light_set = PersistentDescriptorSet::new(
             &self.descriptor_set_allocator,
-            point_layout.clone(),
+            light_layout,
             [
                 WriteDescriptorSet::image_view(0, self.attachment_buffers.albedo_buffer.clone()),
                 WriteDescriptorSet::image_view(1, self.attachment_buffers.normal_buffer.clone()),
                 WriteDescriptorSet::image_view(2, self.attachment_buffers.frag_pos_buffer.clone()),
                 WriteDescriptorSet::image_view(3, self.attachment_buffers.specular_buffer.clone()),
-                WriteDescriptorSet::buffer(4, light.get_buffer(&self.buffer_allocator, &self.base)),
-            ],
-        )
-        .unwrap();
+            ]
+            .into_iter()
+            .chain(light_descriptors),
+        )?;
+        let vp_set = self.vp_set()?;
 
         self.base
-            .commands_mut()
-            .bind_pipeline_graphics(self.pipelines.point.clone())
+            .commands_mut()?
+            .bind_pipeline_graphics(pipeline.clone())
             .bind_descriptor_sets(
                 PipelineBindPoint::Graphics,
-                self.pipelines.point.layout().clone(),
+                pipeline.layout().clone(),
                 0,
-                (self.vp_set.as_ref().unwrap().clone(), point_set),
+                (vp_set, light_set),
             )
-            .bind_vertex_buffers(0, self.dummy_vertex_buf.clone())
-            .draw(self.dummy_vertex_buf.len() as u32, 1, 0, 0)
-            .unwrap();
+            .bind_vertex_buffers(0, self.dummy_vertex_buf.clone());
+
+        // The scissor is restored afterward, since every other pass covers the whole screen
+        let full_scissor = self.base.get_scissor();
+        let commands = self.base.commands_mut()?;
+        match scissor {
+            Some(scissor) => {
+                commands
+                    .set_scissor(0, [scissor])
+                    .draw(self.dummy_vertex_buf.len() as u32, 1, 0, 0)?
+                    .set_scissor(0, [full_scissor]);
+            }
+            None => {
+                commands.draw(self.dummy_vertex_buf.len() as u32, 1, 0, 0)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the scissor rectangle that contains a sphere on screen, by projecting the corners of
+    /// its bounding box in view space. Returns `None` if the sphere is entirely off screen, and
+    /// the full scissor if the camera is inside of it or it's behind the camera.
+    fn sphere_scissor(&self, center: &Vec3, radius: f32) -> Option<Scissor> {
+        let full_scissor = self.base.get_scissor();
+        let (view, projection) = match self.view_projection {
+            Some(view_projection) => view_projection,
+            None => return Some(full_scissor),
+        };
+
+        let view_center = view * vec4(center.x, center.y, center.z, 1.0);
+        let mut min = vec2(f32::MAX, f32::MAX);
+        let mut max = vec2(f32::MIN, f32::MIN);
+        for i in 0..8 {
+            let corner = |bit: u32| if i & bit == 0 { -radius } else { radius };
+            let clip = projection * (view_center + vec4(corner(1), corner(2), corner(4), 0.0));
+            // Corners behind the camera don't project onto the screen in a useful way
+            if clip.w <= f32::EPSILON {
+                return Some(full_scissor);
+            }
+            let ndc = vec2(clip.x / clip.w, clip.y / clip.w);
+            min = min.inf(&ndc);
+            max = max.sup(&ndc);
+        }
+
+        if max.x < -1.0 || min.x > 1.0 || max.y < -1.0 || min.y > 1.0 {
+            return None;
+        }
+
+        // Converts from normalized device coordinates to pixels, in the same way as the viewport
+        let [width, height] = full_scissor.dimensions;
+        let to_pixels = |ndc: f32, size: u32| (ndc.clamp(-1.0, 1.0) * 0.5 + 0.5) * size as f32;
+        let origin = [
+            to_pixels(min.x, width).floor() as u32,
+            to_pixels(min.y, height).floor() as u32,
+        ];
+        let end = [
+            to_pixels(max.x, width).ceil() as u32,
+            to_pixels(max.y, height).ceil() as u32,
+        ];
+        Some(Scissor {
+            origin,
+            dimensions: [end[0] - origin[0], end[1] - origin[1]],
+        })
     }
 
-    /*
-    /// Draws an object with an unlit shader by rendering it after shadows are drawn
-    /// # Panics
-    /// Panics if not called after a `draw_point()` call or another `draw_object_unlit()` call
-    pub fn draw_unlit<T: Vertex>(&mut self, info: DrawInfo<T>, pipeline: impl Into<Option<Arc<GraphicsPipeline>>>) -> Result<(), PipelineExecutionError> {
-        self.render_stage.update(RenderStage::Unlit);
+    /// Draws an object that isn't affected by lights, such as a debug marker or an emitter. Unlit
+    /// objects are drawn in the lighting subpass after all lights, and are tested against the
+    /// depth of the lit objects, so they can be hidden behind them.
+    ///
+    /// # Errors
+    /// - Returns `RhyoliteError::StageOrder` if not called after a `draw_ambient_light()` call, a
+    /// light's draw call, or another `draw_unlit()` call, and an error if the pipeline can't be
+    /// executed, such as with an incorrect binding.
+    pub fn draw_unlit(
+        &mut self,
+        info: DrawInfo<impl Vertex>,
+        pipeline: Arc<GraphicsPipeline>,
+        descriptors: impl DescriptorSetsCollection,
+    ) -> Result<(), RhyoliteError> {
         if self.base.render_error {
             return Ok(());
         }
+        self.enter_stage(RenderStage::Unlit)?;
 
-        let pipeline: Arc<GraphicsPipeline> = match pipeline.into() {
-            Some(custom_pipeline) => custom_pipeline,
-            None => self.pipelines.unlit.clone(),
-        };
+        self.draw(info, pipeline, descriptors)
+    }
+
+    /// Has the same functionality as `draw_unlit()`, but draws an `UnlitVertex` object using the
+    /// default unlit pipeline and descriptor sets (see `default_unlit_descriptors`).
+    ///
+    /// # Errors
+    /// - Returns the same errors as `draw_unlit()` and `default_unlit_descriptors()`.
+    /// - Returns `RhyoliteError::Unconfigured` if `info` is `DrawInfo::Blank` or `DrawInfo::IndirectBlank`,
+    /// since neither has an object to get the descriptors from.
+    pub fn draw_unlit_auto(&mut self, info: DrawInfo<UnlitVertex>) -> Result<(), RhyoliteError> {
+        let pipeline = self.pipelines.unlit.clone();
 
-        if let DrawInfo::Vertex(obj) = info {
-            self.bind_default_lit_descriptors(obj.params())
+        let descriptors = self.default_unlit_descriptors(auto_params(&info)?)?;
+
+        self.draw_unlit(info, pipeline, descriptors)
+    }
+
+    /// Draws a blended object in a forward pass after all lights and unlit objects, so that the
+    /// objects behind it show through. Transparent objects are tested against the depth of the
+    /// opaque objects, but don't write their own depth, so they must be drawn back to front to
+    /// blend correctly.
+    ///
+    /// # Errors
+    /// - Returns `RhyoliteError::StageOrder` if not called after a `draw_ambient_light()` call, a
+    /// light's draw call, a `draw_unlit()` call, or another `draw_transparent()` call, and an
+    /// error if the pipeline can't be executed, such as with an incorrect binding.
+    pub fn draw_transparent(
+        &mut self,
+        info: DrawInfo<impl Vertex>,
+        pipeline: Arc<GraphicsPipeline>,
+        descriptors: impl DescriptorSetsCollection,
+    ) -> Result<(), RhyoliteError> {
+        if self.base.render_error {
+            return Ok(());
+        }
+        let previous = self.render_stage.clone();
+        self.enter_stage(RenderStage::Transparent)?;
+
+        // Transparent objects are drawn in their own subpass, so that they're blended over the
+        // final lit image
+        if previous != RenderStage::Transparent {
+            self.base
+                .commands_mut()?
+                .next_subpass(SubpassContents::Inline)?;
         }
 
-        self.draw(info, pipeline)?;
-        
-        return Ok(());
+        self.draw(info, pipeline, descriptors)
+    }
+
+    /// Has the same functionality as `draw_transparent()`, but draws several objects with the
+    /// default transparent pipeline and descriptor sets (see `default_transparent_descriptors`),
+    /// which light them with the ambient light and the point lights set with
+    /// `set_point_lights()`. The objects are sorted back to front by the translation of their
+    /// transforms, and the opacity of each object comes from its material.
+    ///
+    /// # Errors
+    /// - Returns the same errors as `draw_transparent()` and `default_transparent_descriptors()`.
+    /// - Returns `RhyoliteError::Unconfigured` if any of `infos` is `DrawInfo::Blank` or `DrawInfo::IndirectBlank`,
+    /// since neither has an object to get the descriptors from.
+    pub fn draw_transparent_auto<'a>(
+        &mut self,
+        infos: impl IntoIterator<Item = DrawInfo<'a, BasicVertex>>,
+    ) -> Result<(), RhyoliteError> {
+        if self.base.render_error {
+            return Ok(());
+        }
+        let (view, _) = self.view_projection.ok_or(RhyoliteError::Unconfigured(
+            "The render pass must be started with `start_render_pass()` before drawing"
+        ))?;
+
+        // The camera looks down -z in view space, so the farthest objects have the lowest depth
+        let mut infos: Vec<(f32, DrawInfo<'a, BasicVertex>)> = infos
+            .into_iter()
+            .map(|info| {
+                let translation = auto_params(&info)?.transform.get_translation();
+                let depth = (view * vec4(translation.x, translation.y, translation.z, 1.0)).z;
+                Ok((depth, info))
+            })
+            .collect::<Result<_, RhyoliteError>>()?;
+        infos.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let pipeline = self.pipelines.transparent.clone();
+        let tiles_per_row = self.culled_lights()?.tiles_per_row;
+        for (_, info) in infos {
+            let descriptors = self.default_transparent_descriptors(auto_params(&info)?)?;
+            self.base.commands_mut()?.push_constants(
+                pipeline.layout().clone(),
+                0,
+                transparent_frag::UTileData { tiles_per_row },
+            );
+            self.draw_transparent(info, pipeline.clone(), descriptors)?;
+        }
+        Ok(())
     }
-    */
 
     fn get_render_stage(&self) -> &RenderStage {
         &self.render_stage
@@ -514,102 +1138,104 @@ impl MeshRenderer {
     pub fn get_render_pass(&self) -> Arc<RenderPass> {
         self.render_pass.clone()
     }
+    /// Gets the uniform subbuffer allocator of the current frame slot. See
+    /// `RenderBase::subbuffer_allocator()`.
     pub fn get_subbuffer_allocator(&self) -> &SubbufferAllocator {
-        &self.subbuffer_allocator
-    }
-
-    pub fn get_base_mut(&mut self) -> &mut RenderBase {
-        &mut self.base
+        self.base.subbuffer_allocator()
     }
 }
 
 impl Renderer for MeshRenderer {
-    /// Recreates all of the structures dependent on the window size, including the framebuffers,
-    /// attachment buffers, swapchain, and pipelines
-    fn recreate_all_size_dependent(&mut self) {
-        self.base.recreate_swapchain();
+    /// Recreates all of the structures dependent on the window size, which are the framebuffers,
+    /// attachment buffers, and swapchain. The pipelines use a dynamic viewport, so they're kept.
+    fn recreate_all_size_dependent(&mut self) -> Result<(), RhyoliteError> {
+        self.base.recreate_swapchain()?;
         // TODO: use a different allocator?
-        let (framebuffers, attachment_buffers, pipelines) = window_size_dependent_setup(
+        let (framebuffers, attachment_buffers) = window_size_dependent_setup(
             &self.buffer_allocator,
-            &self.base.images,
+            &self.base.render_targets(),
             self.render_pass.clone(),
             &mut self.base.viewport,
-            &self.base.device,
-        );
+            self.base.samples(),
+        )?;
         self.framebuffers = framebuffers;
         self.attachment_buffers = attachment_buffers;
-        self.pipelines = pipelines;
+        Ok(())
     }
 
     fn get_base(&self) -> &RenderBase {
         &self.base
     }
+    fn get_base_mut(&mut self) -> &mut RenderBase {
+        &mut self.base
+    }
+}
+
+/// Gets the parameters of the object drawn by `info`, for use with the default descriptors.
+///
+/// # Errors
+/// - Returns `RhyoliteError::Unconfigured` if `info` is `DrawInfo::Blank` or `DrawInfo::IndirectBlank`,
+/// since neither has an object.
+fn auto_params<'a, T: Vertex>(info: &DrawInfo<'a, T>) -> Result<&'a MeshObjectParams, RhyoliteError> {
+    match info {
+        DrawInfo::Vertex { object }
+        | DrawInfo::IndirectVertex { object, .. }
+        | DrawInfo::Indexed { object, .. }
+        | DrawInfo::IndexedIndirect { object, .. } => Ok(object.params()),
+        DrawInfo::Blank { .. } | DrawInfo::IndirectBlank { .. } => Err(RhyoliteError::Unconfigured(
+            "Drawing with the default descriptors requires a DrawInfo with an object"
+        )),
+    }
 }
 
 pub(crate) struct AttachmentBuffers {
+    pub hdr_buffer: Arc<ImageView<AttachmentImage>>,
     pub albedo_buffer: Arc<ImageView<AttachmentImage>>,
     pub normal_buffer: Arc<ImageView<AttachmentImage>>,
     pub frag_pos_buffer: Arc<ImageView<AttachmentImage>>,
     pub specular_buffer: Arc<ImageView<AttachmentImage>>,
 }
 
-/// Sets up the framebuffers based on the size of the viewport.
+/// Sets up the framebuffers based on the size of the viewport. Every attachment except for the
+/// final image has `samples` samples.
 fn window_size_dependent_setup(
     allocator: &(impl MemoryAllocator + ?Sized),
-    images: &[Arc<SwapchainImage>],
+    images: &[Arc<dyn ImageAccess>],
     render_pass: Arc<RenderPass>,
     viewport: &mut Viewport,
-    device: &Arc<Device>,
-) -> (Vec<Arc<Framebuffer>>, AttachmentBuffers, Pipelines) {
+    samples: SampleCount,
+) -> Result<(Vec<Arc<Framebuffer>>, AttachmentBuffers), RhyoliteError> {
     let dimensions = images[0].dimensions().width_height();
     viewport.dimensions = [dimensions[0] as f32, dimensions[1] as f32];
 
-    let depth_buffer = ImageView::new_default(
-        AttachmentImage::transient(allocator, dimensions, Format::D16_UNORM).unwrap(),
-    )
-    .unwrap();
-    let albedo_buffer = ImageView::new_default(
-        AttachmentImage::transient_input_attachment(
-            allocator,
-            dimensions,
-            Format::A2B10G10R10_UNORM_PACK32,
-        )
-        .unwrap(),
-    )
-    .unwrap();
-    let normal_buffer = ImageView::new_default(
-        AttachmentImage::transient_input_attachment(
-            allocator,
-            dimensions,
-            Format::R16G16B16A16_SFLOAT,
-        )
-        .unwrap(),
-    )
-    .unwrap();
-    let frag_pos_buffer = ImageView::new_default(
-        AttachmentImage::transient_input_attachment(
-            allocator,
-            dimensions,
-            Format::R16G16B16A16_SFLOAT,
-        )
-        .unwrap(),
-    )
-    .unwrap();
-    let specular_buffer = ImageView::new_default(
-        AttachmentImage::transient_input_attachment(allocator, dimensions, Format::R16G16_SFLOAT)
-            .unwrap(),
-    )
-    .unwrap();
+    let input_attachment = |format| -> Result<_, RhyoliteError> {
+        Ok(ImageView::new_default(AttachmentImage::transient_multisampled_input_attachment(
+            allocator, dimensions, samples, format,
+        )?)?)
+    };
+
+    let hdr_buffer = input_attachment(HDR_FORMAT)?;
+    let depth_buffer = ImageView::new_default(AttachmentImage::transient_multisampled(
+        allocator,
+        dimensions,
+        samples,
+        Format::D16_UNORM,
+    )?)?;
+    let albedo_buffer = input_attachment(Format::A2B10G10R10_UNORM_PACK32)?;
+    let normal_buffer = input_attachment(Format::R16G16B16A16_SFLOAT)?;
+    let frag_pos_buffer = input_attachment(Format::R16G16B16A16_SFLOAT)?;
+    let specular_buffer = input_attachment(Format::R16G16_SFLOAT)?;
 
     let framebuffers = images
         .iter()
         .map(|image| {
-            let view = ImageView::new_default(image.clone()).unwrap();
-            Framebuffer::new(
+            let view = ImageView::new_default(image.clone())?;
+            Ok(Framebuffer::new(
                 render_pass.clone(),
                 FramebufferCreateInfo {
                     attachments: vec![
                         view,
+                        hdr_buffer.clone(),
                         albedo_buffer.clone(),
                         normal_buffer.clone(),
                         frag_pos_buffer.clone(),
@@ -618,185 +1244,246 @@ fn window_size_dependent_setup(
                     ],
                     ..Default::default()
                 },
-            )
-            .unwrap()
+            )?)
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>, RhyoliteError>>()?;
 
     let attachment_buffers = AttachmentBuffers {
+        hdr_buffer: hdr_buffer.clone(),
         albedo_buffer: albedo_buffer.clone(),
         normal_buffer: normal_buffer.clone(),
         frag_pos_buffer: frag_pos_buffer.clone(),
         specular_buffer: specular_buffer.clone(),
     };
 
-    let pipelines = Pipelines::new(&render_pass, dimensions, device);
-
-    (framebuffers, attachment_buffers, pipelines)
+    Ok((framebuffers, attachment_buffers))
 }
 
 struct Pipelines {
     albedo: Arc<GraphicsPipeline>,
+    albedo_textured: Arc<GraphicsPipeline>,
     point: Arc<GraphicsPipeline>,
+    tiled_point: Arc<GraphicsPipeline>,
+    directional: Arc<GraphicsPipeline>,
+    spot: Arc<GraphicsPipeline>,
     ambient: Arc<GraphicsPipeline>,
     unlit: Arc<GraphicsPipeline>,
+    transparent: Arc<GraphicsPipeline>,
 }
 
 impl Pipelines {
-    pub fn new(render_pass: &Arc<RenderPass>, dimensions: [u32; 2], device: &Arc<Device>) -> Self {
-        let shaders = Shaders::mesh_default(device);
+    /// Creates the default pipelines through `cache`, all of which use a dynamic viewport and
+    /// scissor, and rasterize `samples` samples per pixel.
+    pub fn new(
+        render_pass: &Arc<RenderPass>,
+        device: &Arc<Device>,
+        cache: &Arc<PipelineCache>,
+        samples: SampleCount,
+    ) -> Result<Self, RhyoliteError> {
+        let shaders = Shaders::mesh_default(device, samples != SampleCount::Sample1)?;
+        let multisample_state = MultisampleState {
+            rasterization_samples: samples,
+            ..Default::default()
+        };
 
         // Declare the render pass, a structure that lets us define how the rendering process should work. Tells the hardware
         // where it can expect to find input and where it can store output
         let albedo_pass = Subpass::from(render_pass.clone(), 0).unwrap();
         let lighting_pass = Subpass::from(render_pass.clone(), 1).unwrap();
+        let transparent_pass = Subpass::from(render_pass.clone(), 2).unwrap();
 
         // Render pipelines
         let albedo = GraphicsPipeline::start()
             .vertex_input_state(BasicVertex::per_vertex())
             .vertex_shader(shaders.albedo.vert.entry_point("main").unwrap(), ())
             .input_assembly_state(InputAssemblyState::new())
-            .viewport_state(ViewportState::viewport_fixed_scissor_irrelevant([
-                Viewport {
-                    origin: [0.0, 0.0],
-                    dimensions: [dimensions[0] as f32, dimensions[1] as f32],
-                    depth_range: 0.0..1.0,
-                },
-            ]))
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .multisample_state(multisample_state.clone())
             .fragment_shader(shaders.albedo.frag.entry_point("main").unwrap(), ())
             .depth_stencil_state(DepthStencilState::simple_depth_test())
             .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
+            .render_pass(albedo_pass.clone())
+            .build_with_cache(cache.clone())
+            .build(device.clone())?;
+
+        let albedo_textured = GraphicsPipeline::start()
+            .vertex_input_state(TexturedVertex::per_vertex())
+            .vertex_shader(shaders.albedo_textured.vert.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .multisample_state(multisample_state.clone())
+            .fragment_shader(shaders.albedo_textured.frag.entry_point("main").unwrap(), ())
+            .depth_stencil_state(DepthStencilState::simple_depth_test())
+            .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
             .render_pass(albedo_pass)
-            .build(device.clone())
-            .unwrap();
+            .build_with_cache(cache.clone())
+            .build(device.clone())?;
+
+        // Every light adds its contribution to the lit image
+        let light_pipeline = |pair: &ShaderModulePair| {
+            deferred_light_pipeline(pair, lighting_pass.clone(), multisample_state.clone(), device, cache)
+        };
+        let point = light_pipeline(&shaders.point)?;
+        let tiled_point = light_pipeline(&shaders.tiled_point)?;
+        let directional = light_pipeline(&shaders.directional)?;
+        let spot = light_pipeline(&shaders.spot)?;
+        let ambient = light_pipeline(&shaders.ambient)?;
 
-        let point = GraphicsPipeline::start()
-            .vertex_input_state(DummyVertex::per_vertex())
-            .vertex_shader(shaders.point.vert.entry_point("main").unwrap(), ())
+        let unlit = GraphicsPipeline::start()
+            .vertex_input_state(UnlitVertex::per_vertex())
+            .vertex_shader(shaders.unlit.vert.entry_point("main").unwrap(), ())
             .input_assembly_state(InputAssemblyState::new())
-            .viewport_state(ViewportState::viewport_fixed_scissor_irrelevant([
-                Viewport {
-                    origin: [0.0, 0.0],
-                    dimensions: [dimensions[0] as f32, dimensions[1] as f32],
-                    depth_range: 0.0..1.0,
-                },
-            ]))
-            .fragment_shader(shaders.point.frag.entry_point("main").unwrap(), ())
-            .color_blend_state(
-                ColorBlendState::new(lighting_pass.num_color_attachments()).blend(
-                    AttachmentBlend {
-                        color_op: BlendOp::Add,
-                        color_source: BlendFactor::One,
-                        color_destination: BlendFactor::One,
-                        alpha_op: BlendOp::Max,
-                        alpha_source: BlendFactor::One,
-                        alpha_destination: BlendFactor::One,
-                    },
-                ),
-            )
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .multisample_state(multisample_state.clone())
+            .fragment_shader(shaders.unlit.frag.entry_point("main").unwrap(), ())
+            .depth_stencil_state(DepthStencilState::simple_depth_test())
             .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
             .render_pass(lighting_pass.clone())
-            .build(device.clone())
-            .unwrap();
+            .build_with_cache(cache.clone())
+            .build(device.clone())?;
 
-        let ambient = GraphicsPipeline::start()
-            .vertex_input_state(DummyVertex::per_vertex())
-            .vertex_shader(shaders.ambient.vert.entry_point("main").unwrap(), ())
+        // Transparent objects are hidden behind opaque ones, but don't hide each other
+        let transparent = GraphicsPipeline::start()
+            .vertex_input_state(BasicVertex::per_vertex())
+            .vertex_shader(shaders.transparent.vert.entry_point("main").unwrap(), ())
             .input_assembly_state(InputAssemblyState::new())
-            .viewport_state(ViewportState::viewport_fixed_scissor_irrelevant([
-                Viewport {
-                    origin: [0.0, 0.0],
-                    dimensions: [dimensions[0] as f32, dimensions[1] as f32],
-                    depth_range: 0.0..1.0,
-                },
-            ]))
-            .fragment_shader(shaders.ambient.frag.entry_point("main").unwrap(), ())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .multisample_state(multisample_state.clone())
+            .fragment_shader(shaders.transparent.frag.entry_point("main").unwrap(), ())
+            .depth_stencil_state(DepthStencilState {
+                depth: Some(DepthState {
+                    enable_dynamic: false,
+                    write_enable: StateMode::Fixed(false),
+                    compare_op: StateMode::Fixed(CompareOp::Less),
+                }),
+                ..Default::default()
+            })
+            // The alpha of the HDR buffer is how much of each pixel is covered, which has to
+            // stay premultiplied for the clear color to be filled in correctly
             .color_blend_state(
-                ColorBlendState::new(lighting_pass.num_color_attachments()).blend(
+                ColorBlendState::new(transparent_pass.num_color_attachments()).blend(
                     AttachmentBlend {
                         color_op: BlendOp::Add,
-                        color_source: BlendFactor::One,
-                        color_destination: BlendFactor::One,
-                        alpha_op: BlendOp::Max,
+                        color_source: BlendFactor::SrcAlpha,
+                        color_destination: BlendFactor::OneMinusSrcAlpha,
+                        alpha_op: BlendOp::Add,
                         alpha_source: BlendFactor::One,
-                        alpha_destination: BlendFactor::One,
+                        alpha_destination: BlendFactor::OneMinusSrcAlpha,
                     },
                 ),
             )
             .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
-            .render_pass(lighting_pass.clone())
-            .build(device.clone())
-            .unwrap();
-
-        let unlit = GraphicsPipeline::start()
-            .vertex_input_state(BasicVertex::per_vertex())
-            .vertex_shader(shaders.unlit.vert.entry_point("main").unwrap(), ())
-            .input_assembly_state(InputAssemblyState::new())
-            .viewport_state(ViewportState::viewport_fixed_scissor_irrelevant([
-                Viewport {
-                    origin: [0.0, 0.0],
-                    dimensions: [dimensions[0] as f32, dimensions[1] as f32],
-                    depth_range: 0.0..1.0,
-                },
-            ]))
-            .fragment_shader(shaders.unlit.frag.entry_point("main").unwrap(), ())
-            .depth_stencil_state(DepthStencilState::simple_depth_test())
-            .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
-            .render_pass(lighting_pass.clone())
-            .build(device.clone())
-            .unwrap();
+            .render_pass(transparent_pass)
+            .build_with_cache(cache.clone())
+            .build(device.clone())?;
 
-        Self {
+        Ok(Self {
             albedo,
+            albedo_textured,
             point,
+            tiled_point,
+            directional,
+            spot,
             ambient,
             unlit,
-        }
+            transparent,
+        })
     }
 }
 
+/// Creates the pipeline of a light drawn in the lighting subpass, which reads the G-buffer and
+/// adds the light's contribution to the HDR attachment.
+fn deferred_light_pipeline(
+    shaders: &ShaderModulePair,
+    subpass: Subpass,
+    multisample_state: MultisampleState,
+    device: &Arc<Device>,
+    cache: &Arc<PipelineCache>,
+) -> Result<Arc<GraphicsPipeline>, RhyoliteError> {
+    let pipeline = GraphicsPipeline::start()
+        .vertex_input_state(DummyVertex::per_vertex())
+        .vertex_shader(shaders.vert.entry_point("main").unwrap(), ())
+        .input_assembly_state(InputAssemblyState::new())
+        .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+        .multisample_state(multisample_state)
+        .fragment_shader(shaders.frag.entry_point("main").unwrap(), ())
+        .color_blend_state(
+            ColorBlendState::new(subpass.num_color_attachments()).blend(
+                AttachmentBlend {
+                    color_op: BlendOp::Add,
+                    color_source: BlendFactor::One,
+                    color_destination: BlendFactor::One,
+                    alpha_op: BlendOp::Max,
+                    alpha_source: BlendFactor::One,
+                    alpha_destination: BlendFactor::One,
+                },
+            ),
+        )
+        .rasterization_state(RasterizationState::new().cull_mode(CullMode::Back))
+        .render_pass(subpass)
+        .build_with_cache(cache.clone())
+        .build(device.clone())?;
+
+    Ok(pipeline)
+}
+
 /// Gets the render pass to use with the Mesh renderer. In Vulkan, a render pass is the set of
 /// attachments, the way they are used, and the rendering work that is performed using them.
-fn get_render_pass(device: &Arc<Device>, final_format: Format) -> Arc<RenderPass> {
-    vulkano::ordered_passes_renderpass!(
+///
+/// Every attachment except for the final image has `samples` samples. The lighting subpass reads
+/// each sample of the G-buffer separately, and the tonemapping subpass resolves the HDR
+/// attachment into the final image.
+fn get_render_pass(
+    device: &Arc<Device>,
+    final_format: Format,
+    samples: SampleCount,
+) -> Result<Arc<RenderPass>, RhyoliteError> {
+    Ok(vulkano::ordered_passes_renderpass!(
         device.clone(),
         attachments: {
+            // Every pixel is written by the tonemapping pass, so there's no need to clear it
             final_color: {
-                load: Clear,
+                load: DontCare,
                 store: Store,
                 format: final_format,
                 samples: 1,
             },
+            hdr: {
+                load: Clear,
+                store: DontCare,
+                format: HDR_FORMAT,
+                samples: samples,
+            },
             albedo: {
                 load: Clear,
                 store: DontCare,
                 format: Format::A2B10G10R10_UNORM_PACK32,
-                samples: 1,
+                samples: samples,
             },
             normals: {
                 load: Clear,
                 store: DontCare,
                 format: Format::R16G16B16A16_SFLOAT,
-                samples: 1,
+                samples: samples,
             },
             frag_pos: {
                 load: Clear,
                 store: DontCare,
                 format: Format::R16G16B16A16_SFLOAT,
-                samples: 1,
+                samples: samples,
             },
             // TODO: textures would typically be used for specular instead of renderpass attachments
             specular: {
                 load: Clear,
                 store: DontCare,
                 format: Format::R16G16_SFLOAT,
-                samples: 1,
+                samples: samples,
             },
             depth: {
                 load: Clear,
                 store: DontCare,
                 format: Format::D16_UNORM,
-                samples: 1,
+                samples: samples,
             }
         },
         passes: [
@@ -806,11 +1493,20 @@ fn get_render_pass(device: &Arc<Device>, final_format: Format) -> Arc<RenderPass
                 input: []
             },
             {
-                color: [final_color],
+                color: [hdr],
                 depth_stencil: {depth},
                 input: [albedo, normals, frag_pos, specular]
+            },
+            {
+                color: [hdr],
+                depth_stencil: {depth},
+                input: []
+            },
+            {
+                color: [final_color],
+                depth_stencil: {},
+                input: [hdr]
             }
         ]
-    )
-    .unwrap()
+    )?)
 }
@@ -4,44 +4,166 @@ use vulkano::command_buffer::allocator::{
     StandardCommandBufferAlloc, StandardCommandBufferAllocator,
     StandardCommandBufferAllocatorCreateInfo,
 };
+use vulkano::buffer::allocator::{SubbufferAllocator, SubbufferAllocatorCreateInfo};
+use vulkano::buffer::{Buffer, BufferCreateInfo, BufferUsage, Subbuffer};
 use vulkano::command_buffer::{
-    AutoCommandBufferBuilder, CommandBufferUsage, PrimaryAutoCommandBuffer, RenderPassBeginInfo,
-    SubpassContents,
+    AutoCommandBufferBuilder, CommandBufferUsage, CopyImageToBufferInfo, PrimaryAutoCommandBuffer,
+    RenderPassBeginInfo, SubpassContents,
+};
+use vulkano::instance::debug::{
+    DebugUtilsLabel, DebugUtilsMessageSeverity, DebugUtilsMessageType, DebugUtilsMessenger,
+    DebugUtilsMessengerCreateInfo, Message,
 };
 use vulkano::device::physical::{PhysicalDevice, PhysicalDeviceType};
 use vulkano::device::{
-    Device, DeviceCreateInfo, DeviceExtensions, Queue, QueueCreateInfo, QueueFlags,
+    Device, DeviceCreateInfo, DeviceExtensions, Features, Queue, QueueCreateInfo, QueueFlags,
 };
-use vulkano::format::ClearValue;
-use vulkano::image::SwapchainImage;
-use vulkano::instance::{Instance, InstanceCreateInfo};
+use vulkano::format::{ClearValue, Format};
+use vulkano::image::{AttachmentImage, ImageAccess, ImageUsage, SampleCount, SampleCounts, SwapchainImage};
+use vulkano::instance::{Instance, InstanceCreateInfo, InstanceExtensions};
 use vulkano::library::VulkanLibrary;
-use vulkano::pipeline::graphics::viewport::Viewport;
+use vulkano::memory::allocator::{AllocationCreateInfo, MemoryUsage, StandardMemoryAllocator};
+use vulkano::pipeline::cache::PipelineCache;
+use vulkano::pipeline::graphics::viewport::{Scissor, Viewport};
 use vulkano::render_pass::Framebuffer;
 use vulkano::swapchain::{
-    AcquireError, Surface, Swapchain, SwapchainAcquireFuture, SwapchainCreateInfo,
+    AcquireError, PresentMode, Surface, Swapchain, SwapchainAcquireFuture, SwapchainCreateInfo,
     SwapchainCreationError, SwapchainPresentInfo,
 };
+use vulkano::sync::future::FenceSignalFuture;
 use vulkano::sync::{FlushError, GpuFuture};
 use vulkano::Version;
 use vulkano_win;
 use winit::event_loop::EventLoop;
-use winit::window::{Window, WindowBuilder};
+use winit::window::{Fullscreen, Window, WindowBuilder};
 
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use winit::dpi::LogicalSize;
 
+pub mod capture;
+#[cfg(any(feature = "mesh", feature = "marched"))]
+pub mod light_list;
 #[cfg(feature = "marched")]
 pub mod marched;
 #[cfg(feature = "mesh")]
 pub mod mesh;
+mod pipeline_cache;
+pub mod post_process;
 pub mod staging;
+pub mod tonemap;
+
+use crate::RhyoliteError;
+use capture::{FrameCapture, UnsupportedFormatError};
+use post_process::{PostEffect, PostProcess};
+use tonemap::Tonemapping;
+
+/// The name of the Khronos validation layer, which is enabled by `RenderConfig::validation`.
+const VALIDATION_LAYER: &str = "VK_LAYER_KHRONOS_validation";
+
+/// The number of frames that can be in flight at once. While the GPU draws one frame, the CPU
+/// can record the next, as long as fewer than this many frames are unfinished.
+pub const FRAMES_IN_FLIGHT: usize = 2;
+
+/// Settings for the device, window, and swapchain of a `RenderBase`. These are usually set
+/// through a `RhyoliteBuilder`.
+#[derive(Debug, Clone)]
+pub struct RenderConfig {
+    /// The inner size of the window, in logical pixels, or the size of the offscreen image of a
+    /// headless `RenderBase`
+    pub window_size: [u32; 2],
+    pub title: String,
+    pub resizable: bool,
+    /// Whether the window covers the current monitor, as a borderless fullscreen window
+    pub fullscreen: bool,
+    /// The present mode of the swapchain. If the surface doesn't support it,
+    /// `PresentMode::Fifo` is used instead, which is always supported.
+    pub present_mode: PresentMode,
+    /// The format of the swapchain images. If it's `None` or the surface doesn't support it, the
+    /// first format supported by the surface is used.
+    pub surface_format: Option<Format>,
+    /// The minimum number of swapchain images, clamped to the range supported by the surface.
+    /// If it's `None`, the surface's minimum image count is used.
+    pub image_count: Option<u32>,
+    /// The color that's visible wherever nothing is drawn. Like light colors, it's in linear
+    /// color space, and it's encoded to sRGB along with the rest of the final image, so an
+    /// sRGB color picked in an image editor needs to be converted to linear first.
+    pub clear_color: [f32; 4],
+    /// How the HDR lighting of each frame is mapped into the range of the final image. It can
+    /// also be changed while rendering with `RenderBase::set_tonemapping()`.
+    pub tonemapping: Tonemapping,
+    /// The number of samples per pixel of the Mesh renderer's attachments, for multisample
+    /// anti-aliasing. It's capped to the highest count that the device supports, and MSAA is
+    /// disabled on devices without sample rate shading, which is needed to light each sample.
+    pub samples: SampleCount,
+    /// Which physical device to render with.
+    pub device: DeviceSelector,
+    /// Whether to enable the Khronos validation layer and `VK_EXT_debug_utils`. Messages from
+    /// Vulkan are reported through the `log` crate, and each rendering stage is labeled in the
+    /// command buffer for tools like RenderDoc. Validation slows down rendering considerably, so
+    /// it's meant for debugging.
+    pub validation: bool,
+    /// The file that compiled pipelines are cached in between runs. The cache is loaded when the
+    /// `RenderBase` is created and saved when it's dropped. If it's `None`, pipelines are only
+    /// cached for the lifetime of the `RenderBase`.
+    pub pipeline_cache: Option<PathBuf>,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            window_size: [250, 250],
+            title: String::from(env!("CARGO_PKG_NAME")),
+            resizable: true,
+            fullscreen: false,
+            present_mode: PresentMode::Fifo,
+            surface_format: None,
+            image_count: None,
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            tonemapping: Tonemapping::default(),
+            samples: SampleCount::Sample1,
+            device: DeviceSelector::Auto,
+            validation: false,
+            pipeline_cache: None,
+        }
+    }
+}
+
+/// Chooses the physical device that a `RenderBase` renders with. Only devices that support the
+/// extensions and queues needed for rendering are considered.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum DeviceSelector {
+    /// Prefers discrete GPUs, followed by integrated GPUs, virtual GPUs, and CPU devices.
+    #[default]
+    Auto,
+    /// The first device whose name contains this string, ignoring case.
+    Name(String),
+    /// The device at this index, in the order that the Vulkan implementation enumerates them.
+    Index(usize),
+    /// The first device of this type. `PhysicalDeviceType::Cpu` selects a software renderer like
+    /// lavapipe, which gives the same results on any machine.
+    Type(PhysicalDeviceType),
+}
 
 pub trait Renderer {
-    fn recreate_all_size_dependent(&mut self);
+    fn recreate_all_size_dependent(&mut self) -> Result<(), RhyoliteError>;
     fn get_base(&self) -> &RenderBase;
+    fn get_base_mut(&mut self) -> &mut RenderBase;
     fn get_window_size(&self) -> [i32; 2] {
-        self.get_base().window.inner_size().into()
+        let dimensions = self.get_base().get_dimensions();
+        [dimensions[0] as i32, dimensions[1] as i32]
+    }
+
+    /// Requests that the final color of the next finished frame be copied back to the CPU. See
+    /// `RenderBase::capture_next_frame()`.
+    fn capture_next_frame(&mut self) -> Result<(), RhyoliteError> {
+        self.get_base_mut().capture_next_frame()
+    }
+
+    /// Takes the most recently captured frame, if there is one. See
+    /// `RenderBase::take_captured_frame()`.
+    fn take_captured_frame(&mut self) -> Option<FrameCapture> {
+        self.get_base_mut().take_captured_frame()
     }
 }
 
@@ -53,21 +175,42 @@ pub trait Renderer {
 /// - GPU synchronization
 /// - Swapchain recreation (if necessary)
 /// - Management and execution of command buffers
+///
+/// A `RenderBase` created with `RenderBase::headless()` has no window, surface, or swapchain.
+/// Instead, it renders into an offscreen `AttachmentImage` of a fixed size, which makes it usable
+/// on machines without a display.
+///
+/// All of the built-in pipelines are created through the `PipelineCache` of the base, which can be
+/// saved to and loaded from a file (see `RenderConfig::pipeline_cache`). Custom pipelines should
+/// be created with it as well, using `RenderBase::pipeline_cache()`.
+///
+/// Full-screen effects like bloom and FXAA can be applied to each finished frame with
+/// `RenderBase::push_post_effect()`.
+///
+/// Frames are recorded into one of `FRAMES_IN_FLIGHT` frame slots in turn. Each slot has its own
+/// command buffer allocator, uniform subbuffer allocator, and fence, and a slot is only reused
+/// once the GPU has finished the last frame recorded into it.
 pub struct RenderBase {
     instance: Arc<Instance>,
-    surface: Arc<Surface>,
-    window: Arc<Window>,
+    debug_messenger: Option<DebugUtilsMessenger>,
+    surface: Option<Arc<Surface>>,
+    window: Option<Arc<Window>>,
     device: Arc<Device>,
-    swapchain: Arc<Swapchain>,
-    pub(crate) images: Vec<Arc<SwapchainImage>>,
+    swapchain: Option<Arc<Swapchain>>,
+    pub(crate) images: Vec<Arc<dyn ImageAccess>>,
+    image_format: Format,
+
+    memory_allocator: Arc<StandardMemoryAllocator>,
 
     graphics_queue: Arc<Queue>,
     transfer_queue: Arc<Queue>,
 
-    command_buffer_allocator: StandardCommandBufferAllocator,
+    pipeline_cache: Arc<PipelineCache>,
+    pipeline_cache_path: Option<PathBuf>,
 
     viewport: Viewport,
-    previous_frame_end: Option<Box<dyn GpuFuture>>,
+    frames: Vec<FrameSlot>,
+    frame_idx: usize,
 
     commands: Option<
         AutoCommandBufferBuilder<
@@ -78,69 +221,159 @@ pub struct RenderBase {
     image_idx: u32,
     acquire_future: Option<SwapchainAcquireFuture>,
 
+    capture_requested: bool,
+    captured_frame: Option<FrameCapture>,
+
+    clear_color: [f32; 4],
+    tonemapping: Tonemapping,
+    /// The sample count of `RenderConfig::samples`, capped to what the device supports
+    samples: SampleCount,
+    /// The chain of effects applied after the render pass of each frame, which is only created
+    /// once the first effect is added
+    post_process: Option<PostProcess>,
+    /// Whether the commands of the current stage are inside a debug label that hasn't ended yet
+    stage_label_open: bool,
+
     should_recreate_swapchain: bool,
     render_error: bool,
 }
 
 impl RenderBase {
-    pub fn new(event_loop: &EventLoop<()>) -> Self {
+    /// Creates a `RenderBase` that presents to a new window created with the specified event
+    /// loop, configuring the window and swapchain with `config`.
+    pub fn new(event_loop: &EventLoop<()>, config: &RenderConfig) -> Result<Self, RhyoliteError> {
         // Create the instance, the "root" object of all Vulkan operations
-        let instance = get_instance();
+        let instance = get_instance(false, config.validation)?;
+        let debug_messenger = create_debug_messenger(&instance, config.validation)?;
 
+        let [width, height] = config.window_size;
         let window = Arc::from(
             WindowBuilder::new()
-                .with_title(env!("CARGO_PKG_NAME"))
-                .with_inner_size(LogicalSize::new(250, 250))
-                // .with_inner_size(LogicalSize::new(1280, 720))
-                .build(event_loop)
-                .unwrap(),
+                .with_title(config.title.as_str())
+                .with_inner_size(LogicalSize::new(width, height))
+                .with_resizable(config.resizable)
+                .with_fullscreen(config.fullscreen.then(|| Fullscreen::Borderless(None)))
+                .build(event_loop)?,
         );
 
-        let surface =
-            vulkano_win::create_surface_from_winit(window.clone(), instance.clone()).unwrap();
+        let surface = vulkano_win::create_surface_from_winit(window.clone(), instance.clone())?;
 
         // Get the device and physical device
-        let (physical_device, device, queues) = get_device(&instance, &surface);
-
-        let queues: Vec<Arc<Queue>> = queues.collect();
-
-        let find_queue = |queue_flags: QueueFlags| -> Arc<Queue> {
-            queues
-                .iter()
-                .find(|q| {
-                    physical_device.queue_family_properties()[q.queue_family_index() as usize]
-                        .queue_flags
-                        .contains(queue_flags)
-                })
-                .unwrap()
-                .clone()
-        };
+        let (physical_device, device, queues) =
+            get_device(&instance, Some(&surface), &config.device)?;
+        let (graphics_queue, transfer_queue) =
+            find_queues(&physical_device, queues, &config.device)?;
+        let pipeline_cache = pipeline_cache::load(
+            &physical_device,
+            &device,
+            config.pipeline_cache.as_deref(),
+        )?;
 
-        let graphics_queue = find_queue(QueueFlags::GRAPHICS);
-        let transfer_queue = find_queue(QueueFlags::TRANSFER);
+        // Create the swapchain, an object which contains a vector of Images used for rendering and information on
+        // how to show them to the user
+        let (swapchain, images) =
+            get_swapchain(&physical_device, &device, &surface, &window, config)?;
+        let image_format = swapchain.image_format();
+        let images = images
+            .into_iter()
+            .map(|image| image as Arc<dyn ImageAccess>)
+            .collect();
 
-        println!(
-            "Queue families:\n\tQueueFlags::GRAPHICS: {}\n\tQueueFlags::TRANSFER: {}",
-            graphics_queue.queue_family_index(),
-            transfer_queue.queue_family_index()
+        let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
+
+        let mut base = Self::from_parts(
+            instance,
+            debug_messenger,
+            Some(surface),
+            Some(window),
+            device,
+            Some(swapchain),
+            images,
+            image_format,
+            memory_allocator,
+            graphics_queue,
+            transfer_queue,
+            pipeline_cache,
         );
+        base.clear_color = config.clear_color;
+        base.tonemapping = config.tonemapping;
+        base.samples = supported_sample_count(&physical_device, config.samples);
+        base.pipeline_cache_path = config.pipeline_cache.clone();
+        Ok(base)
+    }
 
-        // Create the swapchain, an object which contains a vector of Images used for rendering and information on
-        // how to show them to the user
-        let (swapchain, images) = get_swapchain(&physical_device, &device, &surface, &window);
+    /// Creates a `RenderBase` without a window or swapchain, which renders into an offscreen
+    /// image instead. Frames are started and finished in the same way as with a windowed
+    /// `RenderBase`, but nothing is presented to the screen.
+    ///
+    /// The offscreen image is `config.window_size` pixels large, and the settings of `config`
+    /// that only apply to windows and swapchains are ignored.
+    pub fn headless(config: &RenderConfig) -> Result<Self, RhyoliteError> {
+        let instance = get_instance(true, config.validation)?;
+        let debug_messenger = create_debug_messenger(&instance, config.validation)?;
+
+        let (physical_device, device, queues) = get_device(&instance, None, &config.device)?;
+        let (graphics_queue, transfer_queue) =
+            find_queues(&physical_device, queues, &config.device)?;
+        let pipeline_cache = pipeline_cache::load(
+            &physical_device,
+            &device,
+            config.pipeline_cache.as_deref(),
+        )?;
 
+        let image_format = Format::R8G8B8A8_UNORM;
+        let memory_allocator = Arc::new(StandardMemoryAllocator::new_default(device.clone()));
+        let image = AttachmentImage::with_usage(
+            &memory_allocator,
+            config.window_size,
+            image_format,
+            ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSFER_SRC,
+        )?;
+
+        let mut base = Self::from_parts(
+            instance,
+            debug_messenger,
+            None,
+            None,
+            device,
+            None,
+            vec![image as Arc<dyn ImageAccess>],
+            image_format,
+            memory_allocator,
+            graphics_queue,
+            transfer_queue,
+            pipeline_cache,
+        );
+        base.clear_color = config.clear_color;
+        base.tonemapping = config.tonemapping;
+        base.samples = supported_sample_count(&physical_device, config.samples);
+        base.pipeline_cache_path = config.pipeline_cache.clone();
+        Ok(base)
+    }
+
+    fn from_parts(
+        instance: Arc<Instance>,
+        debug_messenger: Option<DebugUtilsMessenger>,
+        surface: Option<Arc<Surface>>,
+        window: Option<Arc<Window>>,
+        device: Arc<Device>,
+        swapchain: Option<Arc<Swapchain>>,
+        images: Vec<Arc<dyn ImageAccess>>,
+        image_format: Format,
+        memory_allocator: Arc<StandardMemoryAllocator>,
+        graphics_queue: Arc<Queue>,
+        transfer_queue: Arc<Queue>,
+        pipeline_cache: Arc<PipelineCache>,
+    ) -> Self {
         let viewport = Viewport {
             origin: [0.0, 0.0],
             dimensions: [0.0, 0.0],
             depth_range: 0.0..1.0,
         };
 
-        let command_buffer_allocator = StandardCommandBufferAllocator::new(
-            device.clone(),
-            StandardCommandBufferAllocatorCreateInfo::default(),
-        );
-
-        let previous_frame_end = Some(Box::new(sync::now(device.clone())) as Box<dyn GpuFuture>);
+        let frames = (0..FRAMES_IN_FLIGHT)
+            .map(|_| FrameSlot::new(&device, &memory_allocator))
+            .collect();
 
         let commands = None;
         let image_idx = 0;
@@ -148,212 +381,658 @@ impl RenderBase {
 
         Self {
             instance,
+            debug_messenger,
             surface,
             window,
             device,
             swapchain,
             images,
+            image_format,
+
+            memory_allocator,
 
             graphics_queue,
             transfer_queue,
 
+            pipeline_cache,
+            pipeline_cache_path: None,
+
             viewport,
-            previous_frame_end,
+            frames,
+            frame_idx: 0,
 
             commands,
             image_idx,
             acquire_future,
 
-            command_buffer_allocator,
+            capture_requested: false,
+            captured_frame: None,
+
+            clear_color: RenderConfig::default().clear_color,
+            tonemapping: Tonemapping::default(),
+            samples: SampleCount::Sample1,
+            post_process: None,
+            stage_label_open: false,
 
             should_recreate_swapchain: false,
             render_error: false,
         }
     }
 
-    /// Starts the rendering process for the current frame
-    fn start(&mut self, framebuffers: &Vec<Arc<Framebuffer>>) {
-        self.previous_frame_end
-            .as_mut()
-            .expect(
-                "previous_frame_end future is null. Did you remember to finish the previous frame?",
-            )
-            .cleanup_finished();
+    /// Starts the rendering process for the current frame, clearing each attachment of the
+    /// framebuffer with the corresponding value in `clear_values`. If the swapchain is out of
+    /// date, the frame is skipped and the swapchain is recreated before the next one.
+    fn start(
+        &mut self,
+        framebuffers: &Vec<Arc<Framebuffer>>,
+        clear_values: Vec<Option<ClearValue>>,
+    ) -> Result<(), RhyoliteError> {
+        self.begin_frame()?;
+        self.begin_render_pass(framebuffers, clear_values)
+    }
 
-        // Get an image from the swapchain, recreating the swapchain if its settings are suboptimal
-        let (image_idx, suboptimal, acquire_future) =
-            match swapchain::acquire_next_image(self.swapchain.clone(), None) {
-                Ok(r) => r,
+    /// Waits for the current frame slot and acquires the image that the frame will be rendered
+    /// to. Work that has to happen outside of the render pass, such as compute passes, can be
+    /// recorded between this and `begin_render_pass()`.
+    fn begin_frame(&mut self) -> Result<(), RhyoliteError> {
+        self.render_error = false;
+        self.wait_for_frame_slot()?;
+
+        // Get an image from the swapchain, recreating the swapchain if its settings are suboptimal.
+        // Headless bases always render into their only offscreen image.
+        let (image_idx, suboptimal, acquire_future) = match self.swapchain.clone() {
+            None => (0, false, None),
+            Some(swapchain) => match swapchain::acquire_next_image(swapchain, None) {
+                Ok((image_idx, suboptimal, acquire_future)) => {
+                    (image_idx, suboptimal, Some(acquire_future))
+                }
                 Err(AcquireError::OutOfDate) => {
                     self.should_recreate_swapchain = true;
                     self.render_error = true;
-                    return;
+                    return Ok(());
+                }
+                Err(e) => {
+                    self.render_error = true;
+                    return Err(e.into());
                 }
-                Err(e) => panic!("Failed to acquire next image: {:?}", e),
-            };
+            },
+        };
 
         if suboptimal {
             // self.should_recreate_swapchain = true;
             // TODO: for some reason, swapchain is permanently suboptimal after moving to a retina display and then scaling
-            println!("Swapchain is suboptimal");
+            log::debug!("Swapchain is suboptimal");
         }
 
-        // Set the clear values for each of the buffers
-        let clear_values: Vec<Option<ClearValue>> = vec![
-            Some(ClearValue::Float([0.0, 0.0, 0.0, 1.0])),
-            Some(ClearValue::Float([0.0, 0.0, 0.0, 1.0])),
-            Some(ClearValue::Float([0.0, 0.0, 0.0, 1.0])),
-            Some(ClearValue::Float([0.0, 0.0, 0.0, 1.0])),
-            Some(ClearValue::Float([0.0, 0.0, 0.0, 1.0])),
-            Some(ClearValue::Depth(1f32)),
-        ];
-
         self.image_idx = image_idx;
-        self.acquire_future = Some(acquire_future);
+        self.acquire_future = acquire_future;
+        Ok(())
+    }
+
+    /// Begins the render pass of the frame started with `begin_frame()`, unless the frame is
+    /// being skipped.
+    fn begin_render_pass(
+        &mut self,
+        framebuffers: &Vec<Arc<Framebuffer>>,
+        clear_values: Vec<Option<ClearValue>>,
+    ) -> Result<(), RhyoliteError> {
+        if self.render_error {
+            return Ok(());
+        }
 
+        // Pipelines use a dynamic viewport and scissor, so that they don't have to be recreated
+        // when the window is resized
         let viewport = self.viewport.clone();
+        let scissor = self.get_scissor();
+        let framebuffer = framebuffers[self.render_target_idx()].clone();
 
-        self.commands_mut()
+        self.commands_mut()?
             .begin_render_pass(
                 RenderPassBeginInfo {
                     clear_values,
-                    ..RenderPassBeginInfo::framebuffer(framebuffers[image_idx as usize].clone())
+                    ..RenderPassBeginInfo::framebuffer(framebuffer)
                 },
                 SubpassContents::Inline,
-            )
-            .unwrap()
-            .set_viewport(0, [viewport]);
+            )?
+            .set_viewport(0, [viewport])
+            .set_scissor(0, [scissor]);
+        Ok(())
     }
 
     /// Finishes the rendering process and draws to the screen
-    /// # Panics
-    /// Panics if not called after a `draw_object_unlit()` call or a `draw_point()` call
-    fn finish(&mut self) {
+    /// # Errors
+    /// Returns `RhyoliteError::Unconfigured` if no frame has been started, and an error if the
+    /// frame can't be submitted. A swapchain that's out of date isn't an error, and is recreated
+    /// before the next frame instead.
+    fn finish(&mut self) -> Result<(), RhyoliteError> {
+        self.end_stage_label()?;
+
         // End and build the render pass
-        let mut command_buffer_builder = self.commands.take().unwrap();
-        command_buffer_builder.end_render_pass().unwrap();
-        let command_buffer = command_buffer_builder.build().unwrap();
-
-        let af = self.acquire_future.take().unwrap();
-        let fe = self.previous_frame_end.take().unwrap();
-
-        let future = fe
-            .join(af)
-            .then_execute(self.graphics_queue.clone(), command_buffer)
-            .unwrap()
-            .then_swapchain_present(
-                self.graphics_queue.clone(),
-                SwapchainPresentInfo::swapchain_image_index(self.swapchain.clone(), self.image_idx),
-            )
-            .then_signal_fence_and_flush();
+        let mut command_buffer_builder = self.commands.take().ok_or(RhyoliteError::Unconfigured(
+            "No frame has been started. Was the frame's render pass started?",
+        ))?;
+        command_buffer_builder.end_render_pass()?;
+
+        // The frame was drawn into the input of the post-processing chain, whose last pass writes
+        // into the final image
+        if let Some(post_process) = self.post_process.as_ref().filter(|post| !post.is_empty()) {
+            post_process.record(
+                &mut command_buffer_builder,
+                self.image_idx as usize,
+                self.viewport.clone(),
+                self.get_scissor(),
+                self.debug_messenger.is_some(),
+            )?;
+        }
 
-        match future {
-            Ok(future) => self.previous_frame_end = Some(Box::new(future)),
+        // If a capture was requested, copy the rendered image into a host-visible buffer. The
+        // request is only cleared once the frame has been submitted.
+        let capture_buffer = if self.capture_requested {
+            let image = self.images[self.image_idx as usize].clone();
+            let buffer = self.capture_buffer(image.clone())?;
+            command_buffer_builder
+                .copy_image_to_buffer(CopyImageToBufferInfo::image_buffer(image, buffer.clone()))?;
+            Some(buffer)
+        } else {
+            None
+        };
+
+        let command_buffer = command_buffer_builder.build()?;
+
+        // Frames are executed in order, so each frame waits for the one recorded before it
+        let previous_slot = (self.frame_idx + self.frames.len() - 1) % self.frames.len();
+        let fe = match self.frames[previous_slot].fence.clone() {
+            Some(fence) => fence.boxed(),
+            None => sync::now(self.device.clone()).boxed(),
+        };
+
+        // Windowed bases wait for the acquired image and present it, while headless bases simply
+        // execute the command buffer
+        let future = match self.swapchain.clone() {
+            Some(swapchain) => {
+                let af = self.acquire_future.take().ok_or(RhyoliteError::Unconfigured(
+                    "No swapchain image has been acquired for this frame.",
+                ))?;
+                fe.join(af)
+                    .then_execute(self.graphics_queue.clone(), command_buffer)?
+                    .then_swapchain_present(
+                        self.graphics_queue.clone(),
+                        SwapchainPresentInfo::swapchain_image_index(swapchain, self.image_idx),
+                    )
+                    .boxed()
+            }
+            None => fe
+                .then_execute(self.graphics_queue.clone(), command_buffer)?
+                .boxed(),
+        }
+        .then_signal_fence_and_flush();
+
+        let (fence, result) = match future {
+            Ok(future) => (Some(Arc::new(future)), Ok(())),
             Err(FlushError::OutOfDate) => {
+                self.should_recreate_swapchain = true;
                 self.render_error = true;
-                self.previous_frame_end = Some(Box::new(sync::now(self.device.clone())));
-                return;
+                (None, Ok(()))
             }
             Err(e) => {
-                println!("Failed to flush future: {:?}", e);
                 self.render_error = true;
-                self.previous_frame_end = Some(Box::new(sync::now(self.device.clone())));
-                return;
+                (None, Err(e.into()))
             }
-        }
+        };
 
+        // The next frame is recorded into the next slot, while this one is in flight
+        self.frames[self.frame_idx].fence = fence.clone();
+        self.frame_idx = (self.frame_idx + 1) % self.frames.len();
         self.commands = None;
 
+        // Captures need the frame to be complete before the buffer can be read
+        if let (Some(fence), Some(buffer)) = (fence, capture_buffer) {
+            fence.wait(None)?;
+            self.captured_frame = Some(FrameCapture::from_raw(
+                self.image_format,
+                self.images[self.image_idx as usize].dimensions().width_height(),
+                &buffer.read()?,
+            )?);
+            self.capture_requested = false;
+        }
+
         // TODO: In complicated programs it’s likely that one or more of the operations we’ve just scheduled
         // will block. This happens when the graphics hardware can not accept further commands and the program
         // has to wait until it can. Vulkan provides no easy way to check for this. Because of this, any serious
         // application will probably want to have command submissions done on a dedicated thread so the rest of
         // the application can keep running in the background. We will be completely ignoring this for the sake
         // of these tutorials but just keep this in mind for your own future work.
+        result
+    }
+
+    /// Requests that the final color of the next frame be copied back into a host-visible buffer
+    /// when it's finished. The capture is converted to RGBA8 regardless of the format that the
+    /// frame was rendered in, and can be retrieved with `take_captured_frame()` afterward.
+    ///
+    /// If the next frame is skipped or can't be submitted, such as when the swapchain is out of
+    /// date, the request carries over to the frame after it.
+    ///
+    /// Capturing a frame waits for the GPU to finish rendering it, so it shouldn't be done every
+    /// frame in interactive programs. Swapchain images can only be captured if the surface
+    /// supports `ImageUsage::TRANSFER_SRC`, which is the case on most platforms.
+    pub fn capture_next_frame(&mut self) -> Result<(), RhyoliteError> {
+        if !FrameCapture::supports_format(self.image_format) {
+            return Err(UnsupportedFormatError(self.image_format).into());
+        }
+        self.capture_requested = true;
+        Ok(())
+    }
+
+    /// Takes the frame captured after a call to `capture_next_frame()`, if it has been rendered.
+    pub fn take_captured_frame(&mut self) -> Option<FrameCapture> {
+        self.captured_frame.take()
+    }
+
+    /// Creates a buffer large enough to hold the contents of the specified image, which can be
+    /// read from the CPU
+    fn capture_buffer(&self, image: Arc<dyn ImageAccess>) -> Result<Subbuffer<[u8]>, RhyoliteError> {
+        let [width, height] = image.dimensions().width_height();
+        let block_size = self.image_format.block_size().unwrap();
+        Ok(Buffer::new_slice::<u8>(
+            &self.memory_allocator,
+            BufferCreateInfo {
+                usage: BufferUsage::TRANSFER_DST,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                usage: MemoryUsage::Download,
+                ..Default::default()
+            },
+            width as u64 * height as u64 * block_size,
+        )?)
     }
 
     /// Recreates the swapchain. Should be called if the swapchain is invalidated, such as by a window resize
-    fn recreate_swapchain(&mut self) {
-        let (new_swapchain, new_images) = match self.swapchain.recreate(SwapchainCreateInfo {
-            image_extent: self.window.inner_size().into(),
-            ..self.swapchain.create_info()
+    /// Headless bases keep their offscreen image, since it has a fixed size.
+    fn recreate_swapchain(&mut self) -> Result<(), RhyoliteError> {
+        let (swapchain, window) = match (&self.swapchain, &self.window) {
+            (Some(swapchain), Some(window)) => (swapchain, window),
+            _ => {
+                self.should_recreate_swapchain = false;
+                return Ok(());
+            }
+        };
+
+        let (new_swapchain, new_images) = match swapchain.recreate(SwapchainCreateInfo {
+            image_extent: window.inner_size().into(),
+            ..swapchain.create_info()
         }) {
             Ok(r) => r,
-            Err(SwapchainCreationError::ImageExtentNotSupported { .. }) => return,
-            Err(e) => panic!("Failed to recreate swapchain: {:?}", e),
+            Err(SwapchainCreationError::ImageExtentNotSupported { .. }) => return Ok(()),
+            Err(e) => return Err(e.into()),
         };
 
-        self.swapchain = new_swapchain;
-        self.images = new_images;
+        self.swapchain = Some(new_swapchain);
+        self.should_recreate_swapchain = false;
+        self.images = new_images
+            .into_iter()
+            .map(|image| image as Arc<dyn ImageAccess>)
+            .collect();
+        if let Some(post_process) = &mut self.post_process {
+            post_process.resize(&self.images)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the images that renderers should create their framebuffers for. These are the final
+    /// images, unless there are post-processing effects, in which case it's the only image that
+    /// the chain reads from.
+    pub(crate) fn render_targets(&self) -> Vec<Arc<dyn ImageAccess>> {
+        match self.post_process.as_ref().filter(|post| !post.is_empty()) {
+            Some(post_process) => vec![post_process.input_image()],
+            None => self.images.clone(),
+        }
+    }
+
+    /// Gets the index of the framebuffer, among those created for `render_targets()`, that the
+    /// current frame is drawn into.
+    fn render_target_idx(&self) -> usize {
+        match self.post_process.as_ref().filter(|post| !post.is_empty()) {
+            Some(_) => 0,
+            None => self.image_idx as usize,
+        }
+    }
+
+    /// Adds a full-screen effect to the end of the post-processing chain, which is applied to
+    /// each frame after tonemapping. Should be called between frames.
+    ///
+    /// Adding the first effect makes the renderer recreate its framebuffers before the next
+    /// frame, since it has to draw into an intermediate image from then on.
+    /// # Errors
+    /// Returns an error if the shaders or pipelines of the effect can't be created, and
+    /// `RhyoliteError::Unconfigured` if it's a `PostEffect::Custom` without any passes.
+    pub fn push_post_effect(&mut self, effect: PostEffect) -> Result<(), RhyoliteError> {
+        if self.post_process.is_none() {
+            self.post_process = Some(PostProcess::new(self)?);
+        }
+        let post_process = self.post_process.as_mut().unwrap();
+        if post_process.is_empty() {
+            self.should_recreate_swapchain = true;
+        }
+        post_process.push(effect)
+    }
+
+    /// Removes every effect from the post-processing chain, so frames are drawn straight into
+    /// the final image again.
+    pub fn clear_post_effects(&mut self) {
+        if let Some(post_process) = self.post_process.as_mut().filter(|post| !post.is_empty()) {
+            post_process.clear();
+            self.should_recreate_swapchain = true;
+        }
+    }
+
+    /// Gets the effects in the post-processing chain, in the order they're applied.
+    pub fn post_effects(&self) -> Vec<&PostEffect> {
+        match &self.post_process {
+            Some(post_process) => post_process.effects().collect(),
+            None => vec![],
+        }
+    }
+
+    /// Gets the effect at `index` in the post-processing chain, so that its settings can be
+    /// changed. The passes of an effect are created when it's added, so replacing it with a
+    /// different kind of effect doesn't change how it's drawn.
+    pub fn post_effect_mut(&mut self, index: usize) -> Option<&mut PostEffect> {
+        self.post_process.as_mut()?.effect_mut(index)
+    }
+
+    /// Waits until the GPU has finished the last frame recorded into the current frame slot, so
+    /// that the slot's resources can be reused. Does nothing if the slot has already been waited
+    /// for.
+    pub(crate) fn wait_for_frame_slot(&mut self) -> Result<(), RhyoliteError> {
+        if let Some(fence) = self.frames[self.frame_idx].fence.take() {
+            fence.wait(None)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the allocator for uniform data that only lives for the current frame, such as the
+    /// view and projection matrices of a camera. Each frame slot has its own allocator, so
+    /// allocating from it never has to wait for frames that are still in flight.
+    pub fn subbuffer_allocator(&self) -> &SubbufferAllocator {
+        &self.frames[self.frame_idx].subbuffer_allocator
+    }
+
+    /// Gets the command buffer allocator of the current frame slot, which is also used for
+    /// one-time uploads.
+    pub(crate) fn command_buffer_allocator(&self) -> &StandardCommandBufferAllocator {
+        &self.frames[self.frame_idx].command_buffer_allocator
     }
 
+    /// Gets the index of the frame slot that the current frame is recorded into, from `0` up to
+    /// `FRAMES_IN_FLIGHT`. Resources that are rewritten every frame can be indexed by slot, so
+    /// that a frame never overwrites data still being read by a frame in flight.
+    pub fn frame_index(&self) -> usize { self.frame_idx }
+
     /// Gets a mutable reference to the current command buffer, which holds a list of commands that
     /// tell the graphics hardware what to do. If no such buffer yet exists, the function will
-    /// create a new one.
+    /// create a new one from the current frame slot's allocator.
     pub fn commands_mut(
         &mut self,
-    ) -> &mut AutoCommandBufferBuilder<
-        PrimaryAutoCommandBuffer<StandardCommandBufferAlloc>,
-        StandardCommandBufferAllocator,
+    ) -> Result<
+        &mut AutoCommandBufferBuilder<
+            PrimaryAutoCommandBuffer<StandardCommandBufferAlloc>,
+            StandardCommandBufferAllocator,
+        >,
+        RhyoliteError,
     > {
         let cbb = match self.commands.take() {
             None => {
                  AutoCommandBufferBuilder::primary(
-                    &self.command_buffer_allocator,
+                    self.command_buffer_allocator(),
                     self.graphics_queue.queue_family_index(),
                     CommandBufferUsage::OneTimeSubmit,
-                ).unwrap()
+                )?
             }
             Some(current_cbb) => { current_cbb }
         };
-        self.commands = Some(cbb);
-        self.commands.as_mut().unwrap()
+        Ok(self.commands.insert(cbb))
+    }
+
+    /// Labels the commands recorded after this call as part of the rendering stage `name`, ending
+    /// the label of the previous stage. The label ends when the next stage begins or the frame
+    /// is finished. Does nothing unless validation is enabled in the `RenderConfig`.
+    pub fn begin_stage_label(&mut self, name: &str) -> Result<(), RhyoliteError> {
+        if self.debug_messenger.is_none() {
+            return Ok(());
+        }
+        self.end_stage_label()?;
+        self.commands_mut()?.begin_debug_utils_label(DebugUtilsLabel {
+            label_name: name.to_owned(),
+            ..Default::default()
+        })?;
+        self.stage_label_open = true;
+        Ok(())
+    }
+
+    /// Ends the label begun by the last `begin_stage_label()` call, if it hasn't ended yet.
+    fn end_stage_label(&mut self) -> Result<(), RhyoliteError> {
+        if !self.stage_label_open {
+            return Ok(());
+        }
+        self.stage_label_open = false;
+        // Safety: the label was begun in the same command buffer, and hasn't been ended
+        unsafe {
+            self.commands_mut()?.end_debug_utils_label()?;
+        }
+        Ok(())
     }
 
+    /// Gets the linear color that's visible wherever nothing is drawn. See
+    /// `RenderConfig::clear_color`.
+    pub fn clear_color(&self) -> [f32; 4] { self.clear_color }
+    pub fn set_clear_color(&mut self, clear_color: [f32; 4]) { self.clear_color = clear_color; }
+
+    /// Gets the settings that the HDR lighting of each frame is tonemapped with.
+    pub fn tonemapping(&self) -> Tonemapping { self.tonemapping }
+    pub fn set_tonemapping(&mut self, tonemapping: Tonemapping) { self.tonemapping = tonemapping; }
+
+    /// Gets the number of samples per pixel that renderers with MSAA should use, which is
+    /// `RenderConfig::samples` capped to what the device supports.
+    pub fn samples(&self) -> SampleCount { self.samples }
+
     pub fn get_device(&self) -> Arc<Device> { self.device.clone() }
     pub fn get_viewport(&self) -> &Viewport { &self.viewport }
+
+    /// Gets the scissor rectangle that covers the whole viewport, which is set when each frame
+    /// starts.
+    pub fn get_scissor(&self) -> Scissor {
+        Scissor {
+            origin: [0, 0],
+            dimensions: [self.viewport.dimensions[0] as u32, self.viewport.dimensions[1] as u32],
+        }
+    }
+    pub fn get_memory_allocator(&self) -> Arc<StandardMemoryAllocator> { self.memory_allocator.clone() }
+
+    /// Gets the pipeline cache that all built-in pipelines are created with. Custom pipelines can
+    /// use it with `GraphicsPipelineBuilder::build_with_cache()`, so that they're saved along
+    /// with the built-in ones.
+    pub fn pipeline_cache(&self) -> &Arc<PipelineCache> { &self.pipeline_cache }
+
+    /// Saves the pipeline cache to the file set in `RenderConfig::pipeline_cache`, which also
+    /// happens automatically when the base is dropped. Does nothing if no file was set.
+    pub fn save_pipeline_cache(&self) -> Result<(), RhyoliteError> {
+        match &self.pipeline_cache_path {
+            Some(path) => pipeline_cache::save(&self.pipeline_cache, path),
+            None => Ok(()),
+        }
+    }
+
+    /// Gets the file that the pipeline cache is saved to, if there is one.
+    pub fn pipeline_cache_path(&self) -> Option<&Path> { self.pipeline_cache_path.as_deref() }
+
+    /// Gets the format of the images that are rendered to, whether they belong to the swapchain
+    /// or are offscreen images.
+    pub fn image_format(&self) -> Format { self.image_format }
+
+    /// Gets the dimensions of the images that are rendered to. For windowed bases, this is the
+    /// inner size of the window.
+    pub fn get_dimensions(&self) -> [u32; 2] {
+        match &self.window {
+            Some(window) => window.inner_size().into(),
+            None => self.images[0].dimensions().width_height(),
+        }
+    }
+
+    /// Returns `true` if this base renders offscreen rather than to a window.
+    pub fn is_headless(&self) -> bool { self.swapchain.is_none() }
+}
+
+impl Drop for RenderBase {
+    fn drop(&mut self) {
+        if let Err(e) = self.save_pipeline_cache() {
+            log::error!("Failed to save pipeline cache: {}", e);
+        }
+    }
+}
+
+/// The resources used to record and execute a single frame in flight.
+struct FrameSlot {
+    command_buffer_allocator: StandardCommandBufferAllocator,
+    subbuffer_allocator: SubbufferAllocator,
+    /// Signaled when the GPU finishes the last frame recorded into this slot
+    fence: Option<Arc<FenceSignalFuture<Box<dyn GpuFuture>>>>,
+}
+
+impl FrameSlot {
+    fn new(device: &Arc<Device>, memory_allocator: &Arc<StandardMemoryAllocator>) -> Self {
+        let command_buffer_allocator = StandardCommandBufferAllocator::new(
+            device.clone(),
+            StandardCommandBufferAllocatorCreateInfo::default(),
+        );
+
+        // A buffer pool for all uniform and storage data that's only used for a single frame,
+        // including view/projection matrices, model matrices, and light lists
+        let subbuffer_allocator = SubbufferAllocator::new(
+            memory_allocator.clone(),
+            SubbufferAllocatorCreateInfo {
+                arena_size: 512, // TODO: FIND THE ACTUAL VALUE
+                buffer_usage: BufferUsage::UNIFORM_BUFFER | BufferUsage::STORAGE_BUFFER,
+                memory_usage: MemoryUsage::Upload,
+                ..Default::default()
+            },
+        );
+
+        Self {
+            command_buffer_allocator,
+            subbuffer_allocator,
+            fence: None,
+        }
+    }
 }
 
 // ========================================
 // HELPER FUNCTIONS FOR RENDERBASE CREATION
 // ========================================
 
-/// Selects the best physical device based on the available hardware, returning the device and the
-/// indices of the necessary queues
+/// Selects a physical device with `selector` from the available hardware, returning the device
+/// and the indices of the necessary queues
+/// # Errors
+/// Returns `RhyoliteError::NoSuitableDevice` if no device matching `selector` supports the
+/// required extensions and queues.
 pub(crate) fn select_physical_device(
     instance: &Arc<Instance>,
-    surface: &Arc<Surface>,
+    surface: Option<&Arc<Surface>>,
     device_extensions: &DeviceExtensions,
-) -> (Arc<PhysicalDevice>, Vec<u32>) {
-    let (physical_device, queue_families) = instance
-        .enumerate_physical_devices()
-        .unwrap()
-        .filter(|p| p.supported_extensions().contains(device_extensions))
-        .filter_map(|p| {
+    selector: &DeviceSelector,
+) -> Result<(Arc<PhysicalDevice>, Vec<u32>), RhyoliteError> {
+    let mut candidates = instance
+        .enumerate_physical_devices()?
+        .enumerate()
+        .inspect(|(i, p)| {
+            log::debug!(
+                "Found device {}: {} (type: {:?})",
+                i,
+                p.properties().device_name,
+                p.properties().device_type,
+            )
+        })
+        .filter(|(_, p)| p.supported_extensions().contains(device_extensions))
+        .filter_map(|(i, p)| {
             find_queue_families(
                 &[QueueFlags::GRAPHICS, QueueFlags::TRANSFER],
                 p.clone(),
                 surface,
             )
-                .map(|q| (p, q))
-        })
-        .min_by_key(|(p, _)| match p.properties().device_type {
+                .map(|q| (i, p, q))
+        });
+
+    let selected = match selector {
+        DeviceSelector::Auto => candidates.min_by_key(|(_, p, _)| match p.properties().device_type {
             PhysicalDeviceType::DiscreteGpu => 0,
             PhysicalDeviceType::IntegratedGpu => 1,
             PhysicalDeviceType::VirtualGpu => 2,
             PhysicalDeviceType::Cpu => 3,
             PhysicalDeviceType::Other => 4,
             _ => 5,
-        })
-        .unwrap();
+        }),
+        DeviceSelector::Name(name) => {
+            let name = name.to_lowercase();
+            candidates.find(|(_, p, _)| p.properties().device_name.to_lowercase().contains(&name))
+        }
+        DeviceSelector::Index(index) => candidates.find(|(i, _, _)| i == index),
+        DeviceSelector::Type(device_type) => {
+            candidates.find(|(_, p, _)| p.properties().device_type == *device_type)
+        }
+    };
+    let (_, physical_device, queue_families) =
+        selected.ok_or_else(|| RhyoliteError::NoSuitableDevice(selector.clone()))?;
 
-    println!(
+    log::info!(
         "Using device: {} (type: {:?})",
         physical_device.properties().device_name,
         physical_device.properties().device_type,
     );
 
-    (physical_device, queue_families)
+    Ok((physical_device, queue_families))
+}
+
+/// Caps `requested` to the highest sample count that both color and depth attachments support
+/// on `physical_device`. Multisampling is disabled entirely if the device doesn't support sample
+/// rate shading.
+pub(crate) fn supported_sample_count(
+    physical_device: &PhysicalDevice,
+    requested: SampleCount,
+) -> SampleCount {
+    if requested == SampleCount::Sample1 {
+        return requested;
+    }
+    if !physical_device.supported_features().sample_rate_shading {
+        log::warn!("The device doesn't support sample rate shading, so MSAA is disabled");
+        return SampleCount::Sample1;
+    }
+
+    let properties = physical_device.properties();
+    let samples = highest_sample_count(
+        properties.framebuffer_color_sample_counts & properties.framebuffer_depth_sample_counts,
+        requested,
+    );
+
+    if samples != requested {
+        log::info!("{:?} isn't supported by the device, so {:?} is used instead", requested, samples);
+    }
+    samples
+}
+
+/// Gets the highest sample count in `supported` that isn't higher than `requested`, falling back
+/// to a single sample, which every device supports.
+fn highest_sample_count(supported: SampleCounts, requested: SampleCount) -> SampleCount {
+    [
+        SampleCount::Sample64,
+        SampleCount::Sample32,
+        SampleCount::Sample16,
+        SampleCount::Sample8,
+        SampleCount::Sample4,
+        SampleCount::Sample2,
+    ]
+    .into_iter()
+    .find(|&samples| samples as u32 <= requested as u32 && supported.contains_enum(samples))
+    .unwrap_or(SampleCount::Sample1)
 }
 
 // QUEUE FAMILIES
@@ -361,18 +1040,23 @@ pub(crate) fn select_physical_device(
 fn find_queue_family(
     required_flags: QueueFlags,
     physical_device: Arc<PhysicalDevice>,
-    surface: &Surface,
+    surface: Option<&Arc<Surface>>,
 ) -> Option<usize> {
     physical_device
         .queue_family_properties()
         .iter()
         .enumerate()
         .find(|&q| {
-            if required_flags.contains(QueueFlags::GRAPHICS)
-                && !physical_device
-                .surface_support(q.0 as u32, surface)
-                .unwrap_or(false)
-            {}
+            // Graphics queues of windowed bases also present, so they need to support the surface
+            if let Some(surface) = surface {
+                if required_flags.contains(QueueFlags::GRAPHICS)
+                    && !physical_device
+                    .surface_support(q.0 as u32, surface)
+                    .unwrap_or(false)
+                {
+                    return false;
+                }
+            }
             q.1.queue_flags.contains(required_flags)
         })
         .map(|q| q.0)
@@ -381,7 +1065,7 @@ fn find_queue_family(
 fn find_queue_families(
     required_flags: &[QueueFlags],
     physical_device: Arc<PhysicalDevice>,
-    surface: &Surface,
+    surface: Option<&Arc<Surface>>,
 ) -> Option<Vec<u32>> {
     let mut queue_families = Vec::new();
     for flags in required_flags.into_iter() {
@@ -401,41 +1085,151 @@ pub struct QueueFamilies {
     transfer: u32,
 }
 
+/// Picks the graphics and transfer queues out of the queues created alongside the logical device
+/// # Errors
+/// Returns `RhyoliteError::NoSuitableDevice` if none of the queues support graphics or transfers.
+fn find_queues(
+    physical_device: &Arc<PhysicalDevice>,
+    queues: impl ExactSizeIterator<Item = Arc<Queue>>,
+    selector: &DeviceSelector,
+) -> Result<(Arc<Queue>, Arc<Queue>), RhyoliteError> {
+    let queues: Vec<Arc<Queue>> = queues.collect();
+
+    let find_queue = |queue_flags: QueueFlags| -> Result<Arc<Queue>, RhyoliteError> {
+        queues
+            .iter()
+            .find(|q| {
+                physical_device.queue_family_properties()[q.queue_family_index() as usize]
+                    .queue_flags
+                    .contains(queue_flags)
+            })
+            .cloned()
+            .ok_or_else(|| RhyoliteError::NoSuitableDevice(selector.clone()))
+    };
+
+    let graphics_queue = find_queue(QueueFlags::GRAPHICS)?;
+    let transfer_queue = find_queue(QueueFlags::TRANSFER)?;
+
+    log::debug!(
+        "Queue families:\n\tQueueFlags::GRAPHICS: {}\n\tQueueFlags::TRANSFER: {}",
+        graphics_queue.queue_family_index(),
+        transfer_queue.queue_family_index()
+    );
+
+    Ok((graphics_queue, transfer_queue))
+}
+
 /// Gets the Vulkan instance to use for rendering. May need to be modified based on what extensions
-/// are required or what version is used
-pub(crate) fn get_instance() -> Arc<Instance> {
-    let library = VulkanLibrary::new().unwrap();
-    let required_extensions = vulkano_win::required_extensions(&*library);
-    Instance::new(
+/// are required or what version is used. Headless instances don't enable any surface extensions.
+///
+/// With `validation`, the validation layer is enabled if it's installed, along with
+/// `VK_EXT_debug_utils` if it's supported.
+pub(crate) fn get_instance(headless: bool, validation: bool) -> Result<Arc<Instance>, RhyoliteError> {
+    let library = VulkanLibrary::new()?;
+    let mut required_extensions = if headless {
+        InstanceExtensions::empty()
+    } else {
+        vulkano_win::required_extensions(&*library)
+    };
+
+    let mut enabled_layers = Vec::new();
+    if validation {
+        if library
+            .layer_properties()?
+            .any(|layer| layer.name() == VALIDATION_LAYER)
+        {
+            enabled_layers.push(VALIDATION_LAYER.to_owned());
+        } else {
+            log::warn!("{} isn't installed, so validation is disabled", VALIDATION_LAYER);
+        }
+        required_extensions.ext_debug_utils = library.supported_extensions().ext_debug_utils;
+    }
+
+    Ok(Instance::new(
         library,
         InstanceCreateInfo {
             enabled_extensions: required_extensions,
+            enabled_layers,
             enumerate_portability: true,
             max_api_version: Some(Version::V1_1),
             ..Default::default()
         },
-    )
-        .unwrap()
+    )?)
+}
+
+/// Creates a messenger that reports the messages of the validation layer and the Vulkan
+/// implementation through the `log` crate, if `validation` is enabled and the instance supports
+/// `VK_EXT_debug_utils`.
+pub(crate) fn create_debug_messenger(
+    instance: &Arc<Instance>,
+    validation: bool,
+) -> Result<Option<DebugUtilsMessenger>, RhyoliteError> {
+    if !validation || !instance.enabled_extensions().ext_debug_utils {
+        return Ok(None);
+    }
+
+    // Safety: the callback doesn't make any Vulkan calls
+    let messenger = unsafe {
+        DebugUtilsMessenger::new(
+            instance.clone(),
+            DebugUtilsMessengerCreateInfo {
+                message_severity: DebugUtilsMessageSeverity::ERROR
+                    | DebugUtilsMessageSeverity::WARNING
+                    | DebugUtilsMessageSeverity::INFO
+                    | DebugUtilsMessageSeverity::VERBOSE,
+                message_type: DebugUtilsMessageType::GENERAL
+                    | DebugUtilsMessageType::VALIDATION
+                    | DebugUtilsMessageType::PERFORMANCE,
+                ..DebugUtilsMessengerCreateInfo::user_callback(Arc::new(log_message))
+            },
+        )?
+    };
+    Ok(Some(messenger))
+}
+
+/// Logs a message from `VK_EXT_debug_utils` at the level matching its severity.
+fn log_message(message: &Message) {
+    let level = if message.severity.intersects(DebugUtilsMessageSeverity::ERROR) {
+        log::Level::Error
+    } else if message.severity.intersects(DebugUtilsMessageSeverity::WARNING) {
+        log::Level::Warn
+    } else if message.severity.intersects(DebugUtilsMessageSeverity::INFO) {
+        log::Level::Info
+    } else {
+        log::Level::Debug
+    };
+    log::log!(
+        target: "rhyolite::vulkan",
+        level,
+        "[{}] {}",
+        message.layer_prefix.unwrap_or("vulkan"),
+        message.description,
+    );
 }
 
 /// Creates the physical device, logical device, and queues that will be needed for rendering
 pub(crate) fn get_device(
     instance: &Arc<Instance>,
-    surface: &Arc<Surface>,
-) -> (
-    Arc<PhysicalDevice>,
-    Arc<Device>,
-    impl ExactSizeIterator<Item = Arc<Queue>>,
-) {
-    // Specify features for the physical device with the relevant extensions
+    surface: Option<&Arc<Surface>>,
+    selector: &DeviceSelector,
+) -> Result<
+    (
+        Arc<PhysicalDevice>,
+        Arc<Device>,
+        impl ExactSizeIterator<Item = Arc<Queue>>,
+    ),
+    RhyoliteError,
+> {
+    // Specify features for the physical device with the relevant extensions. The swapchain
+    // extension is only needed if there's a surface to present to.
     let enabled_extensions = DeviceExtensions {
-        khr_swapchain: true,
+        khr_swapchain: surface.is_some(),
         khr_storage_buffer_storage_class: true,
         ..DeviceExtensions::empty()
     };
 
     let (physical_device, queue_families) =
-        select_physical_device(instance, surface, &enabled_extensions);
+        select_physical_device(instance, surface, &enabled_extensions, selector)?;
 
     let queue_create_infos = queue_families
         .iter()
@@ -445,18 +1239,25 @@ pub(crate) fn get_device(
         })
         .collect();
 
+    // Sample rate shading lets the lighting passes shade each sample of a multisampled G-buffer,
+    // and is only enabled where it's supported
+    let enabled_features = Features {
+        sample_rate_shading: physical_device.supported_features().sample_rate_shading,
+        ..Features::empty()
+    };
+
     // Create a device, which is the software representation of the hardware stored in the physical device
     let (device, queues) = Device::new(
         physical_device.clone(),
         DeviceCreateInfo {
             queue_create_infos,
             enabled_extensions,
+            enabled_features,
             ..Default::default()
         },
-    )
-        .expect("Unable to create logical device!");
+    )?;
 
-    (physical_device, device, queues)
+    Ok((physical_device, device, queues))
 }
 
 /// Creates a swapchain for the provided surface based on the capabilities of the physical device
@@ -465,27 +1266,83 @@ pub(crate) fn get_swapchain(
     device: &Arc<Device>,
     surface: &Arc<Surface>,
     window: &Arc<Window>,
-) -> (Arc<Swapchain>, Vec<Arc<SwapchainImage>>) {
-    let caps = physical_device
-        .surface_capabilities(&surface, Default::default())
-        .unwrap();
+    config: &RenderConfig,
+) -> Result<(Arc<Swapchain>, Vec<Arc<SwapchainImage>>), RhyoliteError> {
+    let caps = physical_device.surface_capabilities(&surface, Default::default())?;
     let usage = caps.supported_usage_flags;
-    let image_format = Some(
-        physical_device
-            .surface_formats(&surface, Default::default())
-            .unwrap()[0]
-            .0,
-    );
-    Swapchain::new(
+
+    // Use the preferred format if it's supported, and otherwise fall back to the first one
+    let formats: Vec<Format> = physical_device
+        .surface_formats(&surface, Default::default())?
+        .into_iter()
+        .map(|(format, _)| format)
+        .collect();
+    let image_format = match config.surface_format {
+        Some(format) if formats.contains(&format) => format,
+        _ => formats[0],
+    };
+
+    // FIFO is the only present mode that every surface is required to support
+    let present_mode = if physical_device
+        .surface_present_modes(&surface)?
+        .any(|mode| mode == config.present_mode)
+    {
+        config.present_mode
+    } else {
+        PresentMode::Fifo
+    };
+
+    let min_image_count = match config.image_count {
+        Some(count) => count.clamp(
+            caps.min_image_count,
+            caps.max_image_count.unwrap_or(u32::MAX),
+        ),
+        None => caps.min_image_count,
+    };
+
+    Ok(Swapchain::new(
         device.clone(),
         surface.clone(),
         SwapchainCreateInfo {
-            min_image_count: caps.min_image_count, // TODO: +1?
-            image_format,
+            min_image_count,
+            image_format: Some(image_format),
             image_extent: window.inner_size().into(),
             image_usage: usage,
+            present_mode,
             ..Default::default()
         },
-    )
-        .unwrap()
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_counts_are_capped_to_the_highest_supported() {
+        let supported = SampleCounts::SAMPLE_1 | SampleCounts::SAMPLE_2 | SampleCounts::SAMPLE_4;
+        assert_eq!(highest_sample_count(supported, SampleCount::Sample8), SampleCount::Sample4);
+        assert_eq!(highest_sample_count(supported, SampleCount::Sample4), SampleCount::Sample4);
+        // Counts between supported ones round down
+        let sparse = SampleCounts::SAMPLE_1 | SampleCounts::SAMPLE_4;
+        assert_eq!(highest_sample_count(sparse, SampleCount::Sample2), SampleCount::Sample1);
+    }
+
+    #[test]
+    fn unsupported_sample_counts_fall_back_to_one() {
+        assert_eq!(
+            highest_sample_count(SampleCounts::SAMPLE_1, SampleCount::Sample4),
+            SampleCount::Sample1
+        );
+        assert_eq!(
+            highest_sample_count(SampleCounts::empty(), SampleCount::Sample8),
+            SampleCount::Sample1
+        );
+    }
+
+    #[test]
+    fn single_samples_are_unchanged() {
+        let supported = SampleCounts::SAMPLE_1 | SampleCounts::SAMPLE_8;
+        assert_eq!(highest_sample_count(supported, SampleCount::Sample1), SampleCount::Sample1);
+    }
 }
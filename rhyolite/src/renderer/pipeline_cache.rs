@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use vulkano::device::physical::PhysicalDevice;
+use vulkano::device::Device;
+use vulkano::pipeline::cache::PipelineCache;
+
+use crate::RhyoliteError;
+
+/// The length of the header at the start of pipeline cache data, as written by
+/// `VK_PIPELINE_CACHE_HEADER_VERSION_ONE`
+const HEADER_LEN: usize = 32;
+/// The only header version defined by Vulkan
+const HEADER_VERSION_ONE: u32 = 1;
+
+/// Creates the pipeline cache of a device, filling it with the data saved at `path` if there is
+/// any. Data that can't be read, or that was saved by a different device or driver, is discarded,
+/// and the cache starts out empty.
+pub(crate) fn load(
+    physical_device: &PhysicalDevice,
+    device: &Arc<Device>,
+    path: Option<&Path>,
+) -> Result<Arc<PipelineCache>, RhyoliteError> {
+    let data = match path.map(fs::read) {
+        None => return Ok(PipelineCache::empty(device.clone())?),
+        Some(Ok(data)) => data,
+        Some(Err(e)) => {
+            log::info!("No pipeline cache loaded from {}: {}", path.unwrap().display(), e);
+            return Ok(PipelineCache::empty(device.clone())?);
+        }
+    };
+
+    if !is_compatible(physical_device, &data) {
+        log::info!(
+            "Discarding pipeline cache at {}, which was saved by a different device or driver",
+            path.unwrap().display()
+        );
+        return Ok(PipelineCache::empty(device.clone())?);
+    }
+
+    // Safety: the header matches this device, so the data was returned by `get_data()` on a
+    // compatible device, and the driver validates the rest of it
+    Ok(unsafe { PipelineCache::with_data(device.clone(), &data)? })
+}
+
+/// Saves the data of `cache` to `path`. The data is written to a temporary file first, so a
+/// process that's interrupted while saving never leaves behind a partially written cache.
+pub(crate) fn save(cache: &PipelineCache, path: &Path) -> Result<(), RhyoliteError> {
+    let data = cache.get_data()?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, data)
+        .and_then(|_| fs::rename(&temp_path, path))
+        .map_err(|e| RhyoliteError::Pipeline(Box::new(e)))
+}
+
+/// Checks whether the header of pipeline cache data was written by the same device and driver as
+/// `physical_device`.
+fn is_compatible(physical_device: &PhysicalDevice, data: &[u8]) -> bool {
+    let properties = physical_device.properties();
+    header_matches(
+        data,
+        properties.vendor_id,
+        properties.device_id,
+        &properties.pipeline_cache_uuid,
+    )
+}
+
+/// Checks whether the header of pipeline cache data has the specified vendor, device, and
+/// pipeline cache UUID. Header fields are always little-endian.
+fn header_matches(data: &[u8], vendor_id: u32, device_id: u32, uuid: &[u8; 16]) -> bool {
+    if data.len() < HEADER_LEN {
+        return false;
+    }
+    let read_u32 = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+
+    read_u32(0) as usize >= HEADER_LEN
+        && read_u32(4) == HEADER_VERSION_ONE
+        && read_u32(8) == vendor_id
+        && read_u32(12) == device_id
+        && data[16..32] == uuid[..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VENDOR_ID: u32 = 0x10de;
+    const DEVICE_ID: u32 = 0x2684;
+    const UUID: [u8; 16] = [7; 16];
+
+    /// Builds cache data with a version one header followed by some driver-specific data
+    fn cache_data(vendor_id: u32, device_id: u32, uuid: [u8; 16]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend((HEADER_LEN as u32).to_le_bytes());
+        data.extend(HEADER_VERSION_ONE.to_le_bytes());
+        data.extend(vendor_id.to_le_bytes());
+        data.extend(device_id.to_le_bytes());
+        data.extend(uuid);
+        data.extend([0xab; 8]);
+        data
+    }
+
+    #[test]
+    fn matching_headers_are_compatible() {
+        let data = cache_data(VENDOR_ID, DEVICE_ID, UUID);
+        assert!(header_matches(&data, VENDOR_ID, DEVICE_ID, &UUID));
+        assert!(header_matches(&data[..HEADER_LEN], VENDOR_ID, DEVICE_ID, &UUID));
+    }
+
+    #[test]
+    fn other_devices_are_incompatible() {
+        let data = cache_data(VENDOR_ID, DEVICE_ID, UUID);
+        assert!(!header_matches(&data, 0x1002, DEVICE_ID, &UUID));
+        assert!(!header_matches(&data, VENDOR_ID, DEVICE_ID + 1, &UUID));
+
+        // A byte-swapped vendor ID is a different vendor
+        let swapped = cache_data(VENDOR_ID.swap_bytes(), DEVICE_ID, UUID);
+        assert!(!header_matches(&swapped, VENDOR_ID, DEVICE_ID, &UUID));
+    }
+
+    #[test]
+    fn other_drivers_are_incompatible() {
+        let mut uuid = UUID;
+        uuid[15] = 8;
+        let data = cache_data(VENDOR_ID, DEVICE_ID, uuid);
+        assert!(!header_matches(&data, VENDOR_ID, DEVICE_ID, &UUID));
+    }
+
+    #[test]
+    fn truncated_headers_are_incompatible() {
+        let data = cache_data(VENDOR_ID, DEVICE_ID, UUID);
+        assert!(!header_matches(&data[..HEADER_LEN - 1], VENDOR_ID, DEVICE_ID, &UUID));
+        assert!(!header_matches(&[], VENDOR_ID, DEVICE_ID, &UUID));
+    }
+}
@@ -0,0 +1,416 @@
+use std::sync::Arc;
+
+use vulkano::buffer::{BufferContents, Subbuffer};
+use vulkano::command_buffer::allocator::{StandardCommandBufferAlloc, StandardCommandBufferAllocator};
+use vulkano::command_buffer::{
+    AutoCommandBufferBuilder, PrimaryAutoCommandBuffer, RenderPassBeginInfo, SubpassContents,
+};
+use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
+use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
+use vulkano::device::Device;
+use vulkano::format::Format;
+use vulkano::image::view::ImageView;
+use vulkano::image::{AttachmentImage, ImageAccess, ImageUsage};
+use vulkano::instance::debug::DebugUtilsLabel;
+use vulkano::memory::allocator::StandardMemoryAllocator;
+use vulkano::pipeline::cache::PipelineCache;
+use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
+use vulkano::pipeline::graphics::vertex_input::Vertex;
+use vulkano::pipeline::graphics::viewport::{Scissor, Viewport, ViewportState};
+use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint};
+use vulkano::render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass};
+use vulkano::sampler::{Filter, Sampler, SamplerAddressMode, SamplerCreateInfo};
+
+use crate::geometry::dummy::DummyVertex;
+use crate::shaders::{bloom_bright_frag, bloom_composite_frag, fxaa_frag, ShaderModulePair};
+use crate::RhyoliteError;
+
+use super::RenderBase;
+
+/// The number of intermediate images that passes render into. A pass can read both the result of
+/// the pass before it and the input of its effect, so it needs a third image to write to.
+const TARGET_COUNT: usize = 3;
+
+/// Bloom, which makes bright parts of the image glow by blurring them over their surroundings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bloom {
+    /// The luminance above which colors start to glow. Since bloom runs after tonemapping, colors
+    /// are in the range of the final image.
+    pub threshold: f32,
+    /// How strongly the blurred bright parts are added back onto the image
+    pub intensity: f32,
+    /// The distance between the samples of the blur, in pixels. Larger values spread the glow
+    /// further, at the cost of visible banding.
+    pub radius: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            threshold: 0.8,
+            intensity: 0.6,
+            radius: 1.5,
+        }
+    }
+}
+
+/// An effect made of user-defined passes.
+///
+/// Each pass is a full-screen draw of the `DummyVertex` quad. The vertex shader of
+/// `ShaderModulePair::post_process()` can be used for it, which passes the UV of each pixel to
+/// the fragment shader at location 0. The fragment shader reads its input from set 0:
+/// - Binding 0: a `sampler2D` with the result of the previous pass, or the input of the effect
+/// for its first pass
+/// - Binding 1 (optional): a `sampler2D` with the input of the effect
+///
+/// It can also declare a push constant block with the layout
+/// `{ vec2 texel_size; vec4 params; }`, where `texel_size` is the size of a pixel in UV
+/// coordinates, and `params` are the `params` of the effect.
+pub struct CustomEffect {
+    name: String,
+    passes: Vec<ShaderModulePair>,
+    /// Parameters that are passed to every pass of the effect
+    pub params: [f32; 4],
+}
+
+impl CustomEffect {
+    /// Creates an effect that runs `passes` in order, with all of its parameters set to zero.
+    /// The `name` labels the effect in the command buffer when validation is enabled.
+    pub fn new(name: impl Into<String>, passes: Vec<ShaderModulePair>) -> Self {
+        Self {
+            name: name.into(),
+            passes,
+            params: [0.0; 4],
+        }
+    }
+
+    pub fn name(&self) -> &str { &self.name }
+}
+
+/// A full-screen effect that's applied to the final image of each frame, after tonemapping.
+pub enum PostEffect {
+    Bloom(Bloom),
+    /// Fast approximate anti-aliasing, which smooths edges by blurring along them
+    Fxaa,
+    Custom(CustomEffect),
+}
+
+impl PostEffect {
+    /// Gets the name that labels the effect in the command buffer
+    pub fn name(&self) -> &str {
+        match self {
+            PostEffect::Bloom(_) => "Bloom",
+            PostEffect::Fxaa => "FXAA",
+            PostEffect::Custom(custom) => custom.name(),
+        }
+    }
+
+    /// Gets the parameters that are pushed to each pass of the effect
+    fn params(&self) -> [f32; 4] {
+        match self {
+            PostEffect::Bloom(bloom) => [bloom.threshold, bloom.intensity, bloom.radius, 0.0],
+            PostEffect::Fxaa => [0.0; 4],
+            PostEffect::Custom(custom) => custom.params,
+        }
+    }
+
+    /// Loads the shaders of each pass of the effect
+    fn passes(&self, device: &Arc<Device>) -> Result<Vec<ShaderModulePair>, RhyoliteError> {
+        Ok(match self {
+            PostEffect::Bloom(_) => vec![
+                ShaderModulePair::post_process(device, bloom_bright_frag::load(device.clone())?)?,
+                ShaderModulePair::post_process(device, bloom_composite_frag::load(device.clone())?)?,
+            ],
+            PostEffect::Fxaa => vec![ShaderModulePair::post_process(
+                device,
+                fxaa_frag::load(device.clone())?,
+            )?],
+            PostEffect::Custom(custom) => custom.passes.clone(),
+        })
+    }
+}
+
+/// The push constants of every pass, matching the `UPostData` block of the post shaders
+#[repr(C)]
+#[derive(BufferContents, Clone, Copy)]
+struct PostData {
+    texel_size: [f32; 2],
+    /// A `vec4` is aligned to 16 bytes in the push constant block
+    _padding: [f32; 2],
+    params: [f32; 4],
+}
+
+/// An effect in the chain, along with the pipelines of its passes
+struct ChainedEffect {
+    effect: PostEffect,
+    pipelines: Vec<Arc<GraphicsPipeline>>,
+}
+
+/// A chain of full-screen effects that's run after the render pass of each frame. While the chain
+/// isn't empty, renderers draw into the first of its intermediate images instead of the final
+/// image, and each pass samples the result of the pass before it. The last pass writes into the
+/// final image.
+pub(crate) struct PostProcess {
+    device: Arc<Device>,
+    pipeline_cache: Arc<PipelineCache>,
+    memory_allocator: Arc<StandardMemoryAllocator>,
+    render_pass: Arc<RenderPass>,
+    descriptor_set_allocator: StandardDescriptorSetAllocator,
+    sampler: Arc<Sampler>,
+    dummy_vertex_buf: Subbuffer<[DummyVertex]>,
+    format: Format,
+
+    effects: Vec<ChainedEffect>,
+
+    targets: Vec<Arc<ImageView<AttachmentImage>>>,
+    target_framebuffers: Vec<Arc<Framebuffer>>,
+    /// The framebuffers of the final images, which the last pass writes into
+    output_framebuffers: Vec<Arc<Framebuffer>>,
+}
+
+impl PostProcess {
+    /// Creates an empty chain that writes into the final images of `base`.
+    pub fn new(base: &RenderBase) -> Result<Self, RhyoliteError> {
+        let device = base.get_device();
+        let format = base.image_format();
+
+        // Every pixel is written by the full-screen quad, so there's no need to clear it
+        let render_pass = vulkano::single_pass_renderpass!(
+            device.clone(),
+            attachments: {
+                color: {
+                    load: DontCare,
+                    store: Store,
+                    format: format,
+                    samples: 1,
+                }
+            },
+            pass: {
+                color: [color],
+                depth_stencil: {}
+            }
+        )?;
+
+        let sampler = Sampler::new(
+            device.clone(),
+            SamplerCreateInfo {
+                mag_filter: Filter::Linear,
+                min_filter: Filter::Linear,
+                address_mode: [SamplerAddressMode::ClampToEdge; 3],
+                ..Default::default()
+            },
+        )?;
+
+        let memory_allocator = base.get_memory_allocator();
+        let dummy_vertex_buf = DummyVertex::buf(memory_allocator.as_ref(), base)?;
+
+        let mut post_process = Self {
+            descriptor_set_allocator: StandardDescriptorSetAllocator::new(device.clone()),
+            device,
+            pipeline_cache: base.pipeline_cache().clone(),
+            memory_allocator,
+            render_pass,
+            sampler,
+            dummy_vertex_buf,
+            format,
+
+            effects: vec![],
+
+            targets: vec![],
+            target_framebuffers: vec![],
+            output_framebuffers: vec![],
+        };
+        post_process.resize(&base.images)?;
+        Ok(post_process)
+    }
+
+    pub fn is_empty(&self) -> bool { self.effects.is_empty() }
+
+    /// Adds an effect to the end of the chain, creating the pipelines of its passes.
+    pub fn push(&mut self, effect: PostEffect) -> Result<(), RhyoliteError> {
+        let passes = effect.passes(&self.device)?;
+        if passes.is_empty() {
+            return Err(RhyoliteError::Unconfigured(
+                "A post-processing effect must have at least one pass.",
+            ));
+        }
+
+        let subpass = Subpass::from(self.render_pass.clone(), 0).unwrap();
+        let pipelines = passes
+            .iter()
+            .map(|pass| {
+                Ok(GraphicsPipeline::start()
+                    .vertex_input_state(DummyVertex::per_vertex())
+                    .vertex_shader(pass.vert.entry_point("main").unwrap(), ())
+                    .input_assembly_state(InputAssemblyState::new())
+                    .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+                    .fragment_shader(pass.frag.entry_point("main").unwrap(), ())
+                    .render_pass(subpass.clone())
+                    .build_with_cache(self.pipeline_cache.clone())
+                    .build(self.device.clone())?)
+            })
+            .collect::<Result<Vec<_>, RhyoliteError>>()?;
+
+        self.effects.push(ChainedEffect { effect, pipelines });
+        Ok(())
+    }
+
+    pub fn clear(&mut self) { self.effects.clear(); }
+
+    pub fn effects(&self) -> impl Iterator<Item = &PostEffect> {
+        self.effects.iter().map(|chained| &chained.effect)
+    }
+
+    pub fn effect_mut(&mut self, index: usize) -> Option<&mut PostEffect> {
+        self.effects.get_mut(index).map(|chained| &mut chained.effect)
+    }
+
+    /// Gets the image that renderers draw into while the chain isn't empty
+    pub fn input_image(&self) -> Arc<dyn ImageAccess> {
+        self.targets[0].image().clone()
+    }
+
+    /// Recreates the intermediate images to match the size of `images`, and the framebuffers of
+    /// `images` themselves. Must be called whenever the final images are recreated.
+    pub fn resize(&mut self, images: &[Arc<dyn ImageAccess>]) -> Result<(), RhyoliteError> {
+        let dimensions = images[0].dimensions().width_height();
+
+        self.targets = (0..TARGET_COUNT)
+            .map(|_| {
+                Ok(ImageView::new_default(AttachmentImage::with_usage(
+                    &self.memory_allocator,
+                    dimensions,
+                    self.format,
+                    ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED,
+                )?)?)
+            })
+            .collect::<Result<Vec<_>, RhyoliteError>>()?;
+
+        let framebuffer = |view| -> Result<Arc<Framebuffer>, RhyoliteError> {
+            Ok(Framebuffer::new(
+                self.render_pass.clone(),
+                FramebufferCreateInfo {
+                    attachments: vec![view],
+                    ..Default::default()
+                },
+            )?)
+        };
+        self.target_framebuffers = self
+            .targets
+            .iter()
+            .map(|target| framebuffer(target.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.output_framebuffers = images
+            .iter()
+            .map(|image| framebuffer(ImageView::new_default(image.clone())?))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(())
+    }
+
+    /// Records every pass of the chain after the render pass of a frame has ended, with the last
+    /// pass writing into the final image at `image_idx`. With `label`, the passes of each effect
+    /// are labeled with the name of the effect.
+    pub fn record(
+        &self,
+        commands: &mut AutoCommandBufferBuilder<
+            PrimaryAutoCommandBuffer<StandardCommandBufferAlloc>,
+            StandardCommandBufferAllocator,
+        >,
+        image_idx: usize,
+        viewport: Viewport,
+        scissor: Scissor,
+        label: bool,
+    ) -> Result<(), RhyoliteError> {
+        let pass_count: usize = self.effects.iter().map(|chained| chained.pipelines.len()).sum();
+        let texel_size = [1.0 / viewport.dimensions[0], 1.0 / viewport.dimensions[1]];
+
+        // The renderer drew into the first target, which is the input of the first effect
+        let mut input = 0;
+        let mut previous = 0;
+        let mut pass_idx = 0;
+
+        for chained in &self.effects {
+            if label {
+                commands.begin_debug_utils_label(DebugUtilsLabel {
+                    label_name: chained.effect.name().to_owned(),
+                    ..Default::default()
+                })?;
+            }
+
+            let push_constants = PostData {
+                texel_size,
+                _padding: [0.0; 2],
+                params: chained.effect.params(),
+            };
+
+            for pipeline in &chained.pipelines {
+                pass_idx += 1;
+                // The last pass writes into the final image, and every other pass writes into a
+                // target that isn't read by it
+                let (framebuffer, output) = if pass_idx == pass_count {
+                    (self.output_framebuffers[image_idx].clone(), None)
+                } else {
+                    let target = (0..TARGET_COUNT)
+                        .find(|target| *target != input && *target != previous)
+                        .unwrap();
+                    (self.target_framebuffers[target].clone(), Some(target))
+                };
+
+                let layout = pipeline.layout().set_layouts().get(0).unwrap().clone();
+                let reads_input = layout.bindings().contains_key(&1);
+                let sampled = |target: usize, binding: u32| {
+                    WriteDescriptorSet::image_view_sampler(
+                        binding,
+                        self.targets[target].clone(),
+                        self.sampler.clone(),
+                    )
+                };
+                let set = PersistentDescriptorSet::new(
+                    &self.descriptor_set_allocator,
+                    layout,
+                    [sampled(previous, 0)]
+                        .into_iter()
+                        .chain(reads_input.then(|| sampled(input, 1))),
+                )?;
+
+                commands
+                    .begin_render_pass(
+                        RenderPassBeginInfo {
+                            clear_values: vec![None],
+                            ..RenderPassBeginInfo::framebuffer(framebuffer)
+                        },
+                        SubpassContents::Inline,
+                    )?
+                    .set_viewport(0, [viewport.clone()])
+                    .set_scissor(0, [scissor.clone()])
+                    .bind_pipeline_graphics(pipeline.clone())
+                    .bind_descriptor_sets(
+                        PipelineBindPoint::Graphics,
+                        pipeline.layout().clone(),
+                        0,
+                        set,
+                    );
+                if !pipeline.layout().push_constant_ranges().is_empty() {
+                    commands.push_constants(pipeline.layout().clone(), 0, push_constants);
+                }
+                commands
+                    .bind_vertex_buffers(0, self.dummy_vertex_buf.clone())
+                    .draw(self.dummy_vertex_buf.len() as u32, 1, 0, 0)?
+                    .end_render_pass()?;
+
+                if let Some(output) = output {
+                    previous = output;
+                }
+            }
+            input = previous;
+
+            if label {
+                // Safety: the label was begun in the same command buffer, and hasn't been ended
+                unsafe {
+                    commands.end_debug_utils_label()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
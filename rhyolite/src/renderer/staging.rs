@@ -1,8 +1,13 @@
+use std::sync::Arc;
+
 use crate::renderer::RenderBase;
+use crate::RhyoliteError;
 use vulkano::buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer};
 use vulkano::command_buffer::{
     AutoCommandBufferBuilder, CommandBufferUsage, CopyBufferInfo, PrimaryCommandBufferAbstract,
 };
+use vulkano::format::Format;
+use vulkano::image::{ImageDimensions, ImmutableImage, MipmapsCount};
 use vulkano::memory::allocator::{AllocationCreateInfo, MemoryAllocator, MemoryUsage};
 use vulkano::sync::GpuFuture;
 
@@ -12,7 +17,9 @@ pub trait StagingBuffer {
         buffer_len: u64,
         buffer_allocator: &(impl MemoryAllocator + ?Sized),
         render_base: &RenderBase,
-    ) -> Self;
+    ) -> Result<Self, RhyoliteError>
+    where
+        Self: Sized;
 }
 
 // TODO: remove the buffer_len parameter by improving generics and utilizing len() function of Subbuffer<[T]>
@@ -34,12 +41,16 @@ impl<T: BufferContents + ?Sized> StagingBuffer for Subbuffer<T> {
     /// # Panics
     /// - The function will panic if the length passed in through the `buffer_len` parameter is not
     /// equal to the length of the length of the buffer this function is called on.
+    ///
+    /// # Errors
+    /// - Returns an error if the device-local buffer can't be allocated, or if the copy can't be
+    /// recorded or executed.
     fn into_device_local(
         self,
         buffer_len: u64,
         buffer_allocator: &(impl MemoryAllocator + ?Sized),
         render_base: &RenderBase,
-    ) -> Subbuffer<T> {
+    ) -> Result<Subbuffer<T>, RhyoliteError> {
         let usage = self.buffer().usage().difference(BufferUsage::TRANSFER_SRC);
         let device_local_buf = Buffer::new_unsized::<T>(
             buffer_allocator,
@@ -53,42 +64,76 @@ impl<T: BufferContents + ?Sized> StagingBuffer for Subbuffer<T> {
                 ..Default::default()
             },
             buffer_len,
-        )
-        .unwrap();
+        )?;
 
         assert_eq!(&self.size(), &device_local_buf.size());
 
         // Create a one-time command to copy between the buffers.
         let mut cbb = AutoCommandBufferBuilder::primary(
-            &render_base.command_buffer_allocator,
+            render_base.command_buffer_allocator(),
             render_base.transfer_queue.queue_family_index(),
             CommandBufferUsage::OneTimeSubmit,
-        )
-        .unwrap();
+        )?;
 
         // Add the copy command to the command buffer
         cbb.copy_buffer(CopyBufferInfo::buffers(
             self,
             device_local_buf.clone(), // This is chill because it's basically just cloning an arc (the parent) and a few integers
-        ))
-        .unwrap();
+        ))?;
 
         // Execute the copy command and wait for completion before proceeding.
-        cbb.build()
-            .unwrap()
-            .execute(render_base.transfer_queue.clone())
-            .unwrap()
-            .then_signal_fence_and_flush()
-            .unwrap()
-            .wait(None)
-            .unwrap();
+        cbb.build()?
+            .execute(render_base.transfer_queue.clone())?
+            .then_signal_fence_and_flush()?
+            .wait(None)?;
 
         // println!("Created device-local buffer: {:?}", buffer_usage);
 
-        return device_local_buf;
+        return Ok(device_local_buf);
     }
 }
 
+/// Creates a device-local image that can be sampled in shaders, copying `data` into it and
+/// waiting for the copy to complete. If `mip_levels` is anything other than `MipmapsCount::One`,
+/// the additional mip levels are generated from the copied data.
+///
+/// The copy is executed on the graphics queue rather than the transfer queue, since mipmaps are
+/// generated with blit commands.
+///
+/// # Errors
+/// - Returns an error if the length of `data` doesn't match the size of an image with the
+/// specified dimensions and format, or if the copy can't be executed.
+pub(crate) fn create_immutable_image(
+    data: Vec<u8>,
+    dimensions: ImageDimensions,
+    mip_levels: MipmapsCount,
+    format: Format,
+    memory_allocator: &(impl MemoryAllocator + ?Sized),
+    render_base: &RenderBase,
+) -> Result<Arc<ImmutableImage>, RhyoliteError> {
+    let mut cbb = AutoCommandBufferBuilder::primary(
+        render_base.command_buffer_allocator(),
+        render_base.graphics_queue.queue_family_index(),
+        CommandBufferUsage::OneTimeSubmit,
+    )?;
+
+    let image = ImmutableImage::from_iter(
+        memory_allocator,
+        data,
+        dimensions,
+        mip_levels,
+        format,
+        &mut cbb,
+    )?;
+
+    cbb.build()?
+        .execute(render_base.graphics_queue.clone())?
+        .then_signal_fence_and_flush()?
+        .wait(None)?;
+
+    Ok(image)
+}
+
 pub trait UniformSrc<T: BufferContents> {
     fn get_raw(&self) -> T;
 }
@@ -101,7 +146,7 @@ pub(crate) trait IntoPersistentUniform<T: BufferContents>: UniformSrc<T> {
         &mut self,
         buffer_allocator: &(impl MemoryAllocator + ?Sized),
         render_base: &RenderBase
-    ) -> Subbuffer<T> {
+    ) -> Result<Subbuffer<T>, RhyoliteError> {
         let buf: Subbuffer<T> = Buffer::from_data(
             buffer_allocator,
             BufferCreateInfo {
@@ -113,20 +158,19 @@ pub(crate) trait IntoPersistentUniform<T: BufferContents>: UniformSrc<T> {
                 ..Default::default()
             },
             self.get_raw()
-        )
-            .unwrap()
-            .into_device_local(1, buffer_allocator, render_base);
+        )?
+            .into_device_local(1, buffer_allocator, render_base)?;
         self.set_current_buffer(buf.clone());
-        buf
+        Ok(buf)
     }
 
     fn get_buffer(
         &mut self,
         buffer_allocator: &(impl MemoryAllocator + ?Sized),
         render_base: &RenderBase
-    ) -> Subbuffer<T>{
+    ) -> Result<Subbuffer<T>, RhyoliteError> {
         return if let Some(buffer) = self.get_current_buffer().as_ref() {
-            buffer.clone()
+            Ok(buffer.clone())
         } else {
             self.create_buffer(buffer_allocator, render_base)
         }
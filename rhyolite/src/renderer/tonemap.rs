@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use vulkano::buffer::Subbuffer;
+use vulkano::descriptor_set::allocator::StandardDescriptorSetAllocator;
+use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
+use vulkano::format::{Format, NumericType};
+use vulkano::image::view::ImageView;
+use vulkano::image::{AttachmentImage, SampleCount};
+use vulkano::pipeline::graphics::input_assembly::InputAssemblyState;
+use vulkano::pipeline::graphics::vertex_input::Vertex;
+use vulkano::pipeline::graphics::viewport::ViewportState;
+use vulkano::pipeline::{GraphicsPipeline, Pipeline, PipelineBindPoint};
+use vulkano::render_pass::Subpass;
+
+use crate::geometry::dummy::DummyVertex;
+use crate::shaders::{ambient_vert, tonemap_frag, tonemap_ms_frag};
+use crate::RhyoliteError;
+
+use super::RenderBase;
+
+/// The format of the attachment that lighting is accumulated in, before it's tonemapped into the
+/// final image
+pub(crate) const HDR_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+/// The curve that maps the HDR colors of a frame into the range of the final image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TonemapOperator {
+    /// Only scales colors by the exposure, so anything brighter than white is clipped
+    #[default]
+    ExposureOnly = 0,
+    /// Maps each color channel `c` to `c / (1 + c)`, which never clips but washes out bright
+    /// colors
+    Reinhard = 1,
+    /// A fit of the ACES filmic curve, which has more contrast than Reinhard and rolls bright
+    /// colors off toward white
+    Aces = 2,
+}
+
+/// The settings of the tonemapping pass at the end of each frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tonemapping {
+    pub operator: TonemapOperator,
+    /// The factor that colors are multiplied by before they're tonemapped. Each doubling of the
+    /// exposure brightens the image by one stop.
+    pub exposure: f32,
+}
+
+impl Default for Tonemapping {
+    /// Gets settings that leave colors unchanged, clipping anything brighter than white.
+    fn default() -> Self {
+        Self {
+            operator: TonemapOperator::default(),
+            exposure: 1.0,
+        }
+    }
+}
+
+/// The full-screen pass that tonemaps the HDR attachment into the final image, and fills in the
+/// clear color wherever nothing was drawn. Shared by the Mesh and ray marching renderers.
+///
+/// A multisampled HDR attachment is resolved by the pass, which averages its samples after
+/// they've been tonemapped.
+pub(crate) struct TonemapPass {
+    pipeline: Arc<GraphicsPipeline>,
+    /// The number of samples of the HDR attachment
+    samples: SampleCount,
+    /// Whether the shader has to encode colors as sRGB, because the final image is a UNORM image
+    /// that the hardware doesn't encode
+    encode_srgb: bool,
+}
+
+impl TonemapPass {
+    /// Creates the pass for `subpass`, which must have the final image as its only color
+    /// attachment and the HDR attachment, with `samples` samples, as its only input attachment.
+    pub fn new(subpass: Subpass, base: &RenderBase, samples: SampleCount) -> Result<Self, RhyoliteError> {
+        let device = base.get_device();
+        let vert = ambient_vert::load(device.clone())?;
+        let frag = match samples {
+            SampleCount::Sample1 => tonemap_frag::load(device.clone())?,
+            _ => tonemap_ms_frag::load(device.clone())?,
+        };
+
+        let pipeline = GraphicsPipeline::start()
+            .vertex_input_state(DummyVertex::per_vertex())
+            .vertex_shader(vert.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_dynamic(1))
+            .fragment_shader(frag.entry_point("main").unwrap(), ())
+            .render_pass(subpass)
+            .build_with_cache(base.pipeline_cache().clone())
+            .build(device)?;
+
+        Ok(Self {
+            pipeline,
+            samples,
+            encode_srgb: base.image_format().type_color() != Some(NumericType::SRGB),
+        })
+    }
+
+    /// Records the pass with the tonemapping settings of `base`. Must be called in the subpass
+    /// the pass was created for.
+    pub fn draw(
+        &self,
+        base: &mut RenderBase,
+        descriptor_set_allocator: &StandardDescriptorSetAllocator,
+        hdr_buffer: Arc<ImageView<AttachmentImage>>,
+        dummy_vertex_buf: &Subbuffer<[DummyVertex]>,
+    ) -> Result<(), RhyoliteError> {
+        let layout = self.pipeline.layout().set_layouts().get(0).unwrap().clone();
+        let set = PersistentDescriptorSet::new(
+            descriptor_set_allocator,
+            layout,
+            [WriteDescriptorSet::image_view(0, hdr_buffer)],
+        )?;
+
+        let tonemapping = base.tonemapping();
+        let push_constants = tonemap_frag::UTonemapData {
+            clear_color: base.clear_color(),
+            exposure: tonemapping.exposure,
+            tonemap_operator: tonemapping.operator as u32,
+            encode_srgb: self.encode_srgb as u32,
+            sample_count: self.samples as u32,
+        };
+
+        base.begin_stage_label("Tonemapping")?;
+        base.commands_mut()?
+            .bind_pipeline_graphics(self.pipeline.clone())
+            .bind_descriptor_sets(
+                PipelineBindPoint::Graphics,
+                self.pipeline.layout().clone(),
+                0,
+                set,
+            )
+            .push_constants(self.pipeline.layout().clone(), 0, push_constants)
+            .bind_vertex_buffers(0, dummy_vertex_buf.clone())
+            .draw(dummy_vertex_buf.len() as u32, 1, 0, 0)?;
+        Ok(())
+    }
+}
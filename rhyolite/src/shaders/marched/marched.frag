@@ -1,9 +1,14 @@
 #version 450
 
-#define MAX_POINT_LIGHTS 16
+#define TILE_SIZE 16
+#define MAX_LIGHTS_PER_TILE 255
+#define MAX_DIRECTIONAL_LIGHTS 4
+#define MAX_SPOT_LIGHTS 16
 #define MAX_METABALLS 1024
 #define BLEND_FACTOR 2.0
 
+#include "../mesh/lighting/phong.glsl"
+
 layout(location = 0) in vec2 uv;
 
 layout(set = 0, binding = 0) uniform UCamData {
@@ -14,18 +19,41 @@ layout(set = 0, binding = 0) uniform UCamData {
 struct UPointLight {
     vec4 position;
     vec4 color;
+    vec4 attenuation;
     float intensity;
+    float radius;
 };
 
-layout(set = 1, binding = 0) uniform UPointLightsData {
-    UPointLight data[MAX_POINT_LIGHTS];
+layout(set = 1, binding = 0) uniform UAmbientLightData {
+    vec4 color;
+    float intensity;
+} ambient_light;
+
+struct UDirectionalLight {
+    vec4 direction;
+    vec4 color;
+    float intensity;
+};
+
+layout(set = 1, binding = 1) uniform UDirectionalLightsData {
+    UDirectionalLight data[MAX_DIRECTIONAL_LIGHTS];
     uint len;
-} point_lights;
+} directional_lights;
 
-layout(set = 1, binding = 1) uniform UAmbientLightData {
+struct USpotLight {
+    vec4 position;
+    vec4 direction;
     vec4 color;
     float intensity;
-} ambient_light;
+    float inner_cos;
+    float outer_cos;
+    float range;
+};
+
+layout(set = 1, binding = 2) uniform USpotLightsData {
+    USpotLight data[MAX_SPOT_LIGHTS];
+    uint len;
+} spot_lights;
 
 struct UMetaball {
     vec4 position;
@@ -38,6 +66,19 @@ layout(set = 2, binding = 0) uniform UMetaballData {
     uint len;
 } metaballs;
 
+// Point lights are sorted into screen tiles by `light_cull.comp` each frame
+layout(set = 3, binding = 0) readonly buffer UPointLights {
+    UPointLight data[];
+} point_lights;
+
+layout(set = 3, binding = 1) readonly buffer UTileLights {
+    uint data[];
+} tiles;
+
+layout(push_constant) uniform UTileData {
+    uint tiles_per_row;
+} tile_data;
+
 layout(location = 0) out vec4 out_color;
 
 
@@ -70,39 +111,73 @@ vec3 get_normal(in vec3 p) {
     return normalize(normal);
 }
 
-vec3 phong(in vec3 frag_pos, in UPointLight light, in vec3 cam_pos) {
-    const float specular_intensity = 1.0;
-    const float specular_shininess = 64.0;
+// The specular properties of every marched surface
+const float SPECULAR_INTENSITY = 1.0;
+const float SPECULAR_SHININESS = 64.0;
 
+vec3 point_light(in vec3 frag_pos, in vec3 normal, in vec3 view_dir, in UPointLight light) {
     vec3 light_dir = vec3(light.position) - frag_pos;
-    float dist_squared = pow(length(light_dir), 2.0);
+    float dist = length(light_dir);
+    if (light.radius > 0.0 && dist >= light.radius) {
+        return vec3(0.0);
+    }
     light_dir = normalize(light_dir);
 
-    vec3 normal = get_normal(frag_pos);
+    float brightness = phong(normal, light_dir, view_dir, SPECULAR_INTENSITY, SPECULAR_SHININESS)
+        * attenuate(light.attenuation.xyz, light.radius, dist);
+    return light.color.rgb * light.intensity * brightness;
+}
+
+vec3 directional_light(in vec3 normal, in vec3 view_dir, in UDirectionalLight light) {
+    // The light travels along its direction, so the surface is lit from the opposite one
+    vec3 light_dir = normalize(-light.direction.xyz);
 
-    float lambertian = max(dot(normal, light_dir), 0.0);
-    float specular = 0.0;
+    float brightness = phong(normal, light_dir, view_dir, SPECULAR_INTENSITY, SPECULAR_SHININESS);
+    return light.color.rgb * light.intensity * brightness;
+}
 
-    if (lambertian > 0.0) {
-        vec3 view_dir = normalize(cam_pos - frag_pos);
-        vec3 reflect_dir = reflect(-light_dir, normal);
-        float spec_angle = max(dot(reflect_dir, view_dir), 0.0);
-        specular = pow(spec_angle, specular_shininess);
+vec3 spot_light(in vec3 frag_pos, in vec3 normal, in vec3 view_dir, in USpotLight light) {
+    vec3 light_dir = vec3(light.position) - frag_pos;
+    float dist = length(light_dir);
+    if (light.range > 0.0 && dist >= light.range) {
+        return vec3(0.0);
+    }
+    light_dir = normalize(light_dir);
+
+    // Fades out between the inner and outer cones
+    float theta_cos = dot(-light_dir, normalize(light.direction.xyz));
+    float cone = smoothstep(light.outer_cos, light.inner_cos, theta_cos);
+    if (cone == 0.0) {
+        return vec3(0.0);
     }
 
-    return (lambertian + specular) * light.color.rgb * light.intensity / dist_squared;
+    float falloff = window_falloff(dist, light.range) / (dist * dist);
+    float brightness = phong(normal, light_dir, view_dir, SPECULAR_INTENSITY, SPECULAR_SHININESS) * cone * falloff;
+    return light.color.rgb * light.intensity * brightness;
 }
 
 vec3 get_lighting(in vec3 frag_pos, in vec3 cam_pos) {
+    vec3 normal = get_normal(frag_pos);
+    vec3 view_dir = normalize(cam_pos - frag_pos);
     vec3 out_color = vec3(0.0);
-    for (int i = 0; i < point_lights.len; i++) {
-        out_color += phong(frag_pos, point_lights.data[i], cam_pos);
+
+    uvec2 tile = uvec2(gl_FragCoord.xy) / TILE_SIZE;
+    uint tile_start = (tile.y * tile_data.tiles_per_row + tile.x) * (MAX_LIGHTS_PER_TILE + 1);
+    for (uint i = 0; i < tiles.data[tile_start]; i++) {
+        out_color += point_light(frag_pos, normal, view_dir, point_lights.data[tiles.data[tile_start + 1 + i]]);
+    }
+    for (int i = 0; i < directional_lights.len; i++) {
+        out_color += directional_light(normal, view_dir, directional_lights.data[i]);
+    }
+    for (int i = 0; i < spot_lights.len; i++) {
+        out_color += spot_light(frag_pos, normal, view_dir, spot_lights.data[i]);
     }
     out_color += ambient_light.color.rgb * ambient_light.intensity;
     return out_color;
 }
 
-vec3 ray_march(in vec3 ro, in vec3 rd) {
+// Returns false if the ray doesn't hit anything
+bool ray_march(in vec3 ro, in vec3 rd, out vec3 color) {
     float distance_traveled = 0.0;
     const uint NUMBER_OF_STEPS = 100;
     const float MINIMUM_HIT_DISTANCE = 0.01;
@@ -113,7 +188,8 @@ vec3 ray_march(in vec3 ro, in vec3 rd) {
         float distance_to_closest = map_the_world(current_position);
         
         if (distance_to_closest < MINIMUM_HIT_DISTANCE) {
-            return get_lighting(current_position, ro);
+            color = get_lighting(current_position, ro);
+            return true;
         }
         
         if (distance_traveled > MAXIMUM_TRACE_DISTANCE) {
@@ -122,7 +198,7 @@ vec3 ray_march(in vec3 ro, in vec3 rd) {
         
         distance_traveled += distance_to_closest;
     }
-    return vec3(0.0);
+    return false;
 }
 
 void main() {    
@@ -133,7 +209,11 @@ void main() {
     vec3 rd = mat3(vp_uniforms.view) * normalize(vec3(uv, 1.0));
     rd.z *= -1;
 
-    vec3 shaded_color = ray_march(ro, rd);
+    // Rays that miss leave the clear color
+    vec3 shaded_color;
+    if (!ray_march(ro, rd, shaded_color)) {
+        discard;
+    }
     
     out_color = vec4(shaded_color, 1.0);
 }
\ No newline at end of file
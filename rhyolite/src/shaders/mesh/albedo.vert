@@ -2,7 +2,6 @@
 
 layout(location = 0) in vec3 position;
 layout(location = 1) in vec3 normal;
-layout(location = 2) in vec3 color;
 
 layout(set = 0, binding = 0) uniform UCamData {
     mat4 view;
@@ -14,14 +13,12 @@ layout(set = 1, binding = 0) uniform UModelData {
     mat4 normals;
 } model_uniforms;
 
-layout(location = 0) out vec3 out_color;
 layout(location = 1) out vec3 out_normal;
 layout(location = 2) out vec4 out_pos;
 
 void main() {
     vec4 frag_pos = vp_uniforms.projection * vp_uniforms.view * model_uniforms.model * vec4(position, 1.0);
     gl_Position = frag_pos;
-    out_color = color;
     out_normal = mat3(model_uniforms.normals) * normal;
     out_pos = model_uniforms.model * vec4(position, 1.0);
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 #version 450
 
-layout(input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput u_color;
+#include "gbuffer.glsl"
+
+layout(input_attachment_index = 0, set = 0, binding = 0) uniform GBUFFER_INPUT u_color;
 
 layout(set = 0, binding = 1) uniform UAmbientLightData {
     vec4 color;
@@ -10,7 +12,14 @@ layout(set = 0, binding = 1) uniform UAmbientLightData {
 layout(location = 0) out vec4 f_color;
 
 void main() {
+    // The albedo buffer is cleared with an alpha of 0, so the clear color is kept wherever
+    // nothing was drawn
+    vec4 albedo = LOAD_GBUFFER(u_color);
+    if (albedo.a == 0.0) {
+        discard;
+    }
+
     vec3 ambient_color = light.intensity * light.color.rgb;
-    vec3 combined_color = ambient_color * subpassLoad(u_color).rgb;
+    vec3 combined_color = ambient_color * albedo.rgb;
     f_color = vec4(combined_color, 1.0);
 }
\ No newline at end of file
@@ -0,0 +1,42 @@
+#version 450
+
+#include "gbuffer.glsl"
+#include "phong.glsl"
+
+layout(location = 0) in vec3 cam_pos;
+
+layout(input_attachment_index = 0, set = 1, binding = 0) uniform GBUFFER_INPUT u_color;
+layout(input_attachment_index = 1, set = 1, binding = 1) uniform GBUFFER_INPUT u_normals;
+layout(input_attachment_index = 2, set = 1, binding = 2) uniform GBUFFER_INPUT u_frag_pos;
+layout(input_attachment_index = 3, set = 1, binding = 3) uniform GBUFFER_INPUT u_specular;
+
+layout(set = 1, binding = 4) uniform UDirectionalLightData {
+    vec4 direction;
+    vec4 color;
+    float intensity;
+} light;
+
+layout(location = 0) out vec4 f_color;
+
+// Phong shading, without attenuation, since the light is infinitely far away
+void main() {
+    // Nothing was drawn here, so there's nothing to light
+    vec4 albedo = LOAD_GBUFFER(u_color);
+    if (albedo.a == 0.0) {
+        discard;
+    }
+
+    vec3 frag_pos = LOAD_GBUFFER(u_frag_pos).xyz;
+
+    // The light travels along its direction, so the fragment is lit from the opposite one
+    vec3 light_dir = normalize(-light.direction.xyz);
+
+    vec3 normal = normalize(LOAD_GBUFFER(u_normals).xyz);
+    vec3 view_dir = normalize(cam_pos - frag_pos);
+    vec2 specular = LOAD_GBUFFER(u_specular).xy;
+
+    float brightness = phong(normal, light_dir, view_dir, specular.x, specular.y);
+    vec3 light_color = light.color.rgb * light.intensity * brightness;
+
+    f_color = vec4(albedo.rgb * light_color, 1.0);
+}
@@ -0,0 +1,9 @@
+// Declares and reads the G-buffer attachments of the lighting subpass. When they're multisampled,
+// each sample is read separately, which runs the lighting shaders once per sample.
+#ifdef MULTISAMPLED
+#define GBUFFER_INPUT subpassInputMS
+#define LOAD_GBUFFER(input) subpassLoad(input, gl_SampleID)
+#else
+#define GBUFFER_INPUT subpassInput
+#define LOAD_GBUFFER(input) subpassLoad(input)
+#endif
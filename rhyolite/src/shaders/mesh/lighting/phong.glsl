@@ -0,0 +1,32 @@
+// Phong lighting shared by the deferred, transparent, and ray marched lighting shaders
+
+// Returns the sum of the diffuse and specular terms for light arriving from `light_dir`. The
+// normal, light direction, and view direction (from the surface to the camera) must be normalized.
+float phong(vec3 normal, vec3 light_dir, vec3 view_dir, float specular_intensity, float shininess) {
+    float lambertian = max(dot(normal, light_dir), 0.0);
+    float specular = 0.0;
+
+    if (lambertian > 0.0) {
+        vec3 reflect_dir = reflect(-light_dir, normal);
+        float spec_angle = max(dot(reflect_dir, view_dir), 0.0);
+        specular = specular_intensity * pow(spec_angle, shininess);
+    }
+
+    return lambertian + specular;
+}
+
+// Fades a light out smoothly so that it reaches 0 at `range`, leaving no visible edge where it's
+// cut off. A range of 0 is unlimited.
+float window_falloff(float dist, float range) {
+    if (range <= 0.0) {
+        return 1.0;
+    }
+    float window = clamp(1.0 - pow(dist / range, 4.0), 0.0, 1.0);
+    return window * window;
+}
+
+// The attenuation of a point light with the constant, linear, and quadratic terms in `attenuation`,
+// which fades out at `radius`, or 0 if its range is unlimited
+float attenuate(vec3 attenuation, float radius, float dist) {
+    return window_falloff(dist, radius) / dot(attenuation, vec3(1.0, dist, dist * dist));
+}
@@ -1,46 +1,53 @@
 #version 450
 
+#include "gbuffer.glsl"
+#include "phong.glsl"
+
 layout(location = 0) in vec3 cam_pos;
 
 // Unlike binding, the value of input_attachment_index depends on the order the attachments are given in the 
 // renderpass, not in the descriptor set.
-layout(input_attachment_index = 0, set = 1, binding = 0) uniform subpassInput u_color;
-layout(input_attachment_index = 1, set = 1, binding = 1) uniform subpassInput u_normals;
-layout(input_attachment_index = 2, set = 1, binding = 2) uniform subpassInput u_frag_pos;
-layout(input_attachment_index = 3, set = 1, binding = 3) uniform subpassInput u_specular;
+layout(input_attachment_index = 0, set = 1, binding = 0) uniform GBUFFER_INPUT u_color;
+layout(input_attachment_index = 1, set = 1, binding = 1) uniform GBUFFER_INPUT u_normals;
+layout(input_attachment_index = 2, set = 1, binding = 2) uniform GBUFFER_INPUT u_frag_pos;
+layout(input_attachment_index = 3, set = 1, binding = 3) uniform GBUFFER_INPUT u_specular;
 
 layout(set = 1, binding = 4) uniform UPointLightData {
     vec4 position;
     vec4 color;
+    // The constant, linear, and quadratic terms of the attenuation
+    vec4 attenuation;
     float intensity;
+    // The distance at which the light fades out completely, or 0 if its range is unlimited
+    float radius;
 } light;
 
 layout(location = 0) out vec4 f_color;
 
 // Phong shading
 void main() {
-    vec3 frag_pos = subpassLoad(u_frag_pos).xyz;
+    // Nothing was drawn here, so there's nothing to light
+    vec4 albedo = LOAD_GBUFFER(u_color);
+    if (albedo.a == 0.0) {
+        discard;
+    }
+
+    vec3 frag_pos = LOAD_GBUFFER(u_frag_pos).xyz;
 
     vec3 light_dir = light.position.xyz - frag_pos;
-    float dist_squared = pow(length(light_dir), 2);
+    float dist = length(light_dir);
+    if (light.radius > 0.0 && dist >= light.radius) {
+        discard;
+    }
     light_dir = normalize(light_dir);
 
-    vec3 normal = normalize(subpassLoad(u_normals).xyz);
-
-    float specular_intensity = subpassLoad(u_specular).x;
-    float specular_shininess = subpassLoad(u_specular).y;
-
-    float lambertian = max(dot(normal, light_dir), 0.0);
-    float specular = 0.0;
-
-    if (lambertian > 0.0) {
-        vec3 view_dir = normalize(cam_pos - frag_pos);
-        vec3 reflect_dir = reflect(-light_dir, normal);
-        float specAngle = max(dot(reflect_dir, view_dir), 0.0);
-        specular = pow(specAngle, specular_shininess);
-    }
+    vec3 normal = normalize(LOAD_GBUFFER(u_normals).xyz);
+    vec3 view_dir = normalize(cam_pos - frag_pos);
+    vec2 specular = LOAD_GBUFFER(u_specular).xy;
 
-    vec3 light_color = (lambertian * light.color.rgb + specular * light.color.rgb) * light.intensity / dist_squared;
+    float brightness = phong(normal, light_dir, view_dir, specular.x, specular.y)
+        * attenuate(light.attenuation.xyz, light.radius, dist);
+    vec3 light_color = light.color.rgb * light.intensity * brightness;
 
-    f_color = vec4(subpassLoad(u_color).rgb * light_color, 1.0);
+    f_color = vec4(albedo.rgb * light_color, 1.0);
 }
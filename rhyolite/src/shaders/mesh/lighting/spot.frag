@@ -0,0 +1,60 @@
+#version 450
+
+#include "gbuffer.glsl"
+#include "phong.glsl"
+
+layout(location = 0) in vec3 cam_pos;
+
+layout(input_attachment_index = 0, set = 1, binding = 0) uniform GBUFFER_INPUT u_color;
+layout(input_attachment_index = 1, set = 1, binding = 1) uniform GBUFFER_INPUT u_normals;
+layout(input_attachment_index = 2, set = 1, binding = 2) uniform GBUFFER_INPUT u_frag_pos;
+layout(input_attachment_index = 3, set = 1, binding = 3) uniform GBUFFER_INPUT u_specular;
+
+layout(set = 1, binding = 4) uniform USpotLightData {
+    vec4 position;
+    vec4 direction;
+    vec4 color;
+    float intensity;
+    // The cosines of the angles between the direction and the edges of the inner and outer cones
+    float inner_cos;
+    float outer_cos;
+    // The distance beyond which the light has no effect, or 0 if its range is unlimited
+    float range;
+} light;
+
+layout(location = 0) out vec4 f_color;
+
+// Phong shading, limited to a cone that fades out between its inner and outer angles
+void main() {
+    // Nothing was drawn here, so there's nothing to light
+    vec4 albedo = LOAD_GBUFFER(u_color);
+    if (albedo.a == 0.0) {
+        discard;
+    }
+
+    vec3 frag_pos = LOAD_GBUFFER(u_frag_pos).xyz;
+
+    vec3 light_dir = light.position.xyz - frag_pos;
+    float dist = length(light_dir);
+    if (light.range > 0.0 && dist >= light.range) {
+        discard;
+    }
+    light_dir = normalize(light_dir);
+
+    // Fragments outside of the outer cone aren't lit at all
+    float theta_cos = dot(-light_dir, normalize(light.direction.xyz));
+    float cone = smoothstep(light.outer_cos, light.inner_cos, theta_cos);
+    if (cone == 0.0) {
+        discard;
+    }
+
+    vec3 normal = normalize(LOAD_GBUFFER(u_normals).xyz);
+    vec3 view_dir = normalize(cam_pos - frag_pos);
+    vec2 specular = LOAD_GBUFFER(u_specular).xy;
+
+    float falloff = window_falloff(dist, light.range) / (dist * dist);
+    float brightness = phong(normal, light_dir, view_dir, specular.x, specular.y) * cone * falloff;
+    vec3 light_color = light.color.rgb * light.intensity * brightness;
+
+    f_color = vec4(albedo.rgb * light_color, 1.0);
+}
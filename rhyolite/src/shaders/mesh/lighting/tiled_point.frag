@@ -0,0 +1,77 @@
+#version 450
+
+#include "gbuffer.glsl"
+#include "phong.glsl"
+
+#define TILE_SIZE 16
+#define MAX_LIGHTS_PER_TILE 255
+
+layout(location = 0) in vec3 cam_pos;
+
+layout(input_attachment_index = 0, set = 1, binding = 0) uniform GBUFFER_INPUT u_color;
+layout(input_attachment_index = 1, set = 1, binding = 1) uniform GBUFFER_INPUT u_normals;
+layout(input_attachment_index = 2, set = 1, binding = 2) uniform GBUFFER_INPUT u_frag_pos;
+layout(input_attachment_index = 3, set = 1, binding = 3) uniform GBUFFER_INPUT u_specular;
+
+struct UPointLight {
+    vec4 position;
+    vec4 color;
+    vec4 attenuation;
+    float intensity;
+    float radius;
+};
+
+layout(set = 1, binding = 4) readonly buffer UPointLights {
+    UPointLight data[];
+} lights;
+
+// Written by `light_cull.comp`
+layout(set = 1, binding = 5) readonly buffer UTileLights {
+    uint data[];
+} tiles;
+
+layout(push_constant) uniform UTileData {
+    uint tiles_per_row;
+} tile_data;
+
+layout(location = 0) out vec4 f_color;
+
+// Phong shading with every point light in the fragment's tile
+void main() {
+    // Nothing was drawn here, so there's nothing to light
+    vec4 albedo = LOAD_GBUFFER(u_color);
+    if (albedo.a == 0.0) {
+        discard;
+    }
+
+    uvec2 tile = uvec2(gl_FragCoord.xy) / TILE_SIZE;
+    uint tile_start = (tile.y * tile_data.tiles_per_row + tile.x) * (MAX_LIGHTS_PER_TILE + 1);
+    uint light_count = tiles.data[tile_start];
+    if (light_count == 0) {
+        discard;
+    }
+
+    vec3 frag_pos = LOAD_GBUFFER(u_frag_pos).xyz;
+    vec3 normal = normalize(LOAD_GBUFFER(u_normals).xyz);
+    vec3 view_dir = normalize(cam_pos - frag_pos);
+
+    vec2 specular = LOAD_GBUFFER(u_specular).xy;
+
+    vec3 light_color = vec3(0.0);
+    for (uint i = 0; i < light_count; i++) {
+        UPointLight light = lights.data[tiles.data[tile_start + 1 + i]];
+
+        vec3 light_dir = light.position.xyz - frag_pos;
+        float dist = length(light_dir);
+        if (light.radius > 0.0 && dist >= light.radius) {
+            continue;
+        }
+        light_dir = normalize(light_dir);
+
+        float brightness = phong(normal, light_dir, view_dir, specular.x, specular.y)
+            * attenuate(light.attenuation.xyz, light.radius, dist);
+        light_color += light.color.rgb * light.intensity * brightness;
+    }
+
+    f_color = vec4(albedo.rgb * light_color, 1.0);
+}
@@ -0,0 +1,80 @@
+#version 450
+
+#define TILE_SIZE 16
+#define MAX_LIGHTS_PER_TILE 255
+
+#include "lighting/phong.glsl"
+
+layout(location = 1) in vec3 in_normal;
+layout(location = 2) in vec4 in_pos;
+
+layout(set = 0, binding = 0) uniform UCamData {
+    mat4 view;
+    mat4 projection;
+} vp_uniforms;
+
+layout(set = 2, binding = 0) uniform UMaterialData {
+    vec4 albedo;
+    float specular_intensity;
+    float shininess;
+} material;
+
+layout(set = 3, binding = 0) uniform UAmbientLightData {
+    vec4 color;
+    float intensity;
+} ambient_light;
+
+struct UPointLight {
+    vec4 position;
+    vec4 color;
+    vec4 attenuation;
+    float intensity;
+    float radius;
+};
+
+layout(set = 3, binding = 1) readonly buffer UPointLights {
+    UPointLight data[];
+} lights;
+
+// Written by `light_cull.comp`
+layout(set = 3, binding = 2) readonly buffer UTileLights {
+    uint data[];
+} tiles;
+
+layout(push_constant) uniform UTileData {
+    uint tiles_per_row;
+} tile_data;
+
+layout(location = 0) out vec4 f_color;
+
+// Forward Phong shading with the ambient light and every point light in the fragment's tile.
+// The alpha of the material's albedo is its opacity.
+void main() {
+    vec3 frag_pos = in_pos.xyz;
+    vec3 normal = normalize(in_normal);
+    mat4 view_i = inverse(vp_uniforms.view);
+    vec3 cam_pos = vec3(view_i[3][0], view_i[3][1], view_i[3][2]);
+    vec3 view_dir = normalize(cam_pos - frag_pos);
+
+    vec3 light_color = ambient_light.color.rgb * ambient_light.intensity;
+
+    uvec2 tile = uvec2(gl_FragCoord.xy) / TILE_SIZE;
+    uint tile_start = (tile.y * tile_data.tiles_per_row + tile.x) * (MAX_LIGHTS_PER_TILE + 1);
+    uint light_count = tiles.data[tile_start];
+    for (uint i = 0; i < light_count; i++) {
+        UPointLight light = lights.data[tiles.data[tile_start + 1 + i]];
+
+        vec3 light_dir = light.position.xyz - frag_pos;
+        float dist = length(light_dir);
+        if (light.radius > 0.0 && dist >= light.radius) {
+            continue;
+        }
+        light_dir = normalize(light_dir);
+
+        float brightness = phong(normal, light_dir, view_dir, material.specular_intensity, material.shininess)
+            * attenuate(light.attenuation.xyz, light.radius, dist);
+        light_color += light.color.rgb * light.intensity * brightness;
+    }
+
+    f_color = vec4(material.albedo.rgb * light_color, material.albedo.a);
+}
@@ -8,8 +8,11 @@ layout(set = 0, binding = 0) uniform UCamData {
     mat4 projection;
 } vp_uniforms;
 
+// Shares its layout with `albedo.vert`, so the same model data can be used with both pipelines.
+// The normal matrix is unused.
 layout(set = 1, binding = 0) uniform UModelData {
     mat4 model;
+    mat4 normals;
 } model_uniforms;
 
 layout(location = 0) out vec3 out_color;
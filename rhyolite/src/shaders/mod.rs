@@ -1,7 +1,10 @@
 use std::sync::Arc;
 
 use nalgebra_glm::Vec3;
-use vulkano::{device::Device, shader::ShaderModule};
+use vulkano::device::Device;
+use vulkano::shader::{ShaderCreationError, ShaderModule};
+
+use crate::RhyoliteError;
 
 pub mod albedo_vert {
     vulkano_shaders::shader! {
@@ -17,6 +20,20 @@ pub mod albedo_frag {
     }
 }
 
+pub mod albedo_textured_vert {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/mesh/albedo_textured.vert",
+    }
+}
+
+pub mod albedo_textured_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/mesh/albedo_textured.frag",
+    }
+}
+
 pub mod point_vert {
     vulkano_shaders::shader! {
         ty: "vertex",
@@ -31,6 +48,66 @@ pub mod point_frag {
     }
 }
 
+pub mod point_ms_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/mesh/lighting/point.frag",
+        define: [("MULTISAMPLED", "1")],
+    }
+}
+
+pub mod directional_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/mesh/lighting/directional.frag",
+    }
+}
+
+pub mod directional_ms_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/mesh/lighting/directional.frag",
+        define: [("MULTISAMPLED", "1")],
+    }
+}
+
+pub mod spot_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/mesh/lighting/spot.frag",
+    }
+}
+
+pub mod spot_ms_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/mesh/lighting/spot.frag",
+        define: [("MULTISAMPLED", "1")],
+    }
+}
+
+pub mod tiled_point_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/mesh/lighting/tiled_point.frag",
+    }
+}
+
+pub mod tiled_point_ms_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/mesh/lighting/tiled_point.frag",
+        define: [("MULTISAMPLED", "1")],
+    }
+}
+
+pub mod light_cull_comp {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        path: "src/shaders/lighting/light_cull.comp",
+    }
+}
+
 pub mod ambient_vert {
     vulkano_shaders::shader! {
         ty: "vertex",
@@ -45,6 +122,14 @@ pub mod ambient_frag {
     }
 }
 
+pub mod ambient_ms_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/mesh/lighting/ambient.frag",
+        define: [("MULTISAMPLED", "1")],
+    }
+}
+
 pub mod unlit_vert {
     vulkano_shaders::shader! {
         ty: "vertex",
@@ -59,6 +144,56 @@ pub mod unlit_frag {
     }
 }
 
+pub mod transparent_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/mesh/transparent.frag",
+    }
+}
+
+pub mod tonemap_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/post/tonemap.frag",
+    }
+}
+
+pub mod tonemap_ms_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/post/tonemap.frag",
+        define: [("MULTISAMPLED", "1")],
+    }
+}
+
+pub mod post_vert {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "src/shaders/post/post.vert",
+    }
+}
+
+pub mod bloom_bright_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/post/bloom_bright.frag",
+    }
+}
+
+pub mod bloom_composite_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/post/bloom_composite.frag",
+    }
+}
+
+pub mod fxaa_frag {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "src/shaders/post/fxaa.frag",
+    }
+}
+
 pub mod marched_vert {
     vulkano_shaders::shader! {
         ty: "vertex",
@@ -75,45 +210,91 @@ pub mod marched_frag {
 
 // TODO: find a better way to do this
 
+#[derive(Clone)]
 pub struct ShaderModulePair {
     pub vert: Arc<ShaderModule>,
     pub frag: Arc<ShaderModule>,
 }
 
 impl ShaderModulePair {
-    pub(crate) fn marched_default(device: &Arc<Device>) -> Self {
-        Self {
-            vert: marched_vert::load(device.clone()).unwrap(),
-            frag: marched_frag::load(device.clone()).unwrap(),
-        }
+    pub(crate) fn marched_default(device: &Arc<Device>) -> Result<Self, RhyoliteError> {
+        Ok(Self {
+            vert: marched_vert::load(device.clone())?,
+            frag: marched_frag::load(device.clone())?,
+        })
+    }
+
+    /// Pairs a post-processing fragment shader with the built-in vertex shader of post-processing
+    /// passes, which covers the screen and passes the UV of each pixel at location 0.
+    pub fn post_process(device: &Arc<Device>, frag: Arc<ShaderModule>) -> Result<Self, RhyoliteError> {
+        Ok(Self {
+            vert: post_vert::load(device.clone())?,
+            frag,
+        })
     }
 }
 pub struct Shaders {
     pub albedo: ShaderModulePair,
+    pub albedo_textured: ShaderModulePair,
     pub point: ShaderModulePair,
+    pub directional: ShaderModulePair,
+    pub spot: ShaderModulePair,
+    pub tiled_point: ShaderModulePair,
     pub ambient: ShaderModulePair,
     pub unlit: ShaderModulePair,
+    pub transparent: ShaderModulePair,
 }
 impl Shaders {
-    pub(crate) fn mesh_default(device: &Arc<Device>) -> Self {
-        Self {
+    /// Loads the default shaders of the Mesh renderer. With `multisampled`, the lighting shaders
+    /// read each sample of a multisampled G-buffer.
+    pub(crate) fn mesh_default(device: &Arc<Device>, multisampled: bool) -> Result<Self, RhyoliteError> {
+        // Lighting shaders are compiled twice, since multisampled input attachments have to be
+        // read with a different type
+        let lighting_frag = |single: fn(Arc<Device>) -> Result<Arc<ShaderModule>, ShaderCreationError>,
+                             ms: fn(Arc<Device>) -> Result<Arc<ShaderModule>, ShaderCreationError>| {
+            if multisampled { ms(device.clone()) } else { single(device.clone()) }
+        };
+
+        Ok(Self {
             albedo: ShaderModulePair {
-                vert: albedo_vert::load(device.clone()).unwrap(),
-                frag: albedo_frag::load(device.clone()).unwrap(),
+                vert: albedo_vert::load(device.clone())?,
+                frag: albedo_frag::load(device.clone())?,
+            },
+            albedo_textured: ShaderModulePair {
+                vert: albedo_textured_vert::load(device.clone())?,
+                frag: albedo_textured_frag::load(device.clone())?,
             },
             point: ShaderModulePair {
-                vert: point_vert::load(device.clone()).unwrap(),
-                frag: point_frag::load(device.clone()).unwrap(),
+                vert: point_vert::load(device.clone())?,
+                frag: lighting_frag(point_frag::load, point_ms_frag::load)?,
+            },
+            // Directional and spot lights are drawn over the whole screen like point lights, so
+            // they share a vertex shader
+            directional: ShaderModulePair {
+                vert: point_vert::load(device.clone())?,
+                frag: lighting_frag(directional_frag::load, directional_ms_frag::load)?,
+            },
+            spot: ShaderModulePair {
+                vert: point_vert::load(device.clone())?,
+                frag: lighting_frag(spot_frag::load, spot_ms_frag::load)?,
+            },
+            tiled_point: ShaderModulePair {
+                vert: point_vert::load(device.clone())?,
+                frag: lighting_frag(tiled_point_frag::load, tiled_point_ms_frag::load)?,
             },
             ambient: ShaderModulePair {
-                vert: ambient_vert::load(device.clone()).unwrap(),
-                frag: ambient_frag::load(device.clone()).unwrap(),
+                vert: ambient_vert::load(device.clone())?,
+                frag: lighting_frag(ambient_frag::load, ambient_ms_frag::load)?,
             },
             unlit: ShaderModulePair {
-                vert: unlit_vert::load(device.clone()).unwrap(),
-                frag: unlit_frag::load(device.clone()).unwrap(),
+                vert: unlit_vert::load(device.clone())?,
+                frag: unlit_frag::load(device.clone())?,
+            },
+            transparent: ShaderModulePair {
+                vert: albedo_vert::load(device.clone())?,
+                frag: transparent_frag::load(device.clone())?,
             },
-        }
+        })
     }
 }
 
@@ -0,0 +1,33 @@
+#version 450
+
+layout(location = 0) in vec2 uv;
+
+layout(set = 0, binding = 0) uniform sampler2D u_previous;
+
+// params: threshold, intensity, radius
+layout(push_constant) uniform UPostData {
+    vec2 texel_size;
+    vec4 params;
+} post;
+
+layout(location = 0) out vec4 f_color;
+
+const float WEIGHTS[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+// Keeps the part of a color that's brighter than the threshold
+vec3 bright(vec2 coord) {
+    vec3 color = texture(u_previous, coord).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    return color * max(luminance - post.params.x, 0.0) / max(luminance, 0.0001);
+}
+
+// The first pass of bloom, which blurs the bright parts of the image horizontally
+void main() {
+    vec2 step = vec2(post.texel_size.x * post.params.z, 0.0);
+    vec3 color = bright(uv) * WEIGHTS[0];
+    for (int i = 1; i < 5; i++) {
+        color += bright(uv + step * i) * WEIGHTS[i];
+        color += bright(uv - step * i) * WEIGHTS[i];
+    }
+    f_color = vec4(color, 1.0);
+}
@@ -0,0 +1,30 @@
+#version 450
+
+layout(location = 0) in vec2 uv;
+
+// The horizontally blurred bright parts, and the image that bloom is applied to
+layout(set = 0, binding = 0) uniform sampler2D u_previous;
+layout(set = 0, binding = 1) uniform sampler2D u_source;
+
+// params: threshold, intensity, radius
+layout(push_constant) uniform UPostData {
+    vec2 texel_size;
+    vec4 params;
+} post;
+
+layout(location = 0) out vec4 f_color;
+
+const float WEIGHTS[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+// The second pass of bloom, which finishes the blur vertically and adds it to the image
+void main() {
+    vec2 step = vec2(0.0, post.texel_size.y * post.params.z);
+    vec3 bloom = texture(u_previous, uv).rgb * WEIGHTS[0];
+    for (int i = 1; i < 5; i++) {
+        bloom += texture(u_previous, uv + step * i).rgb * WEIGHTS[i];
+        bloom += texture(u_previous, uv - step * i).rgb * WEIGHTS[i];
+    }
+
+    vec4 source = texture(u_source, uv);
+    f_color = vec4(source.rgb + bloom * post.params.y, source.a);
+}
@@ -0,0 +1,61 @@
+#version 450
+
+#define FXAA_SPAN_MAX 8.0
+#define FXAA_REDUCE_MUL (1.0 / 8.0)
+#define FXAA_REDUCE_MIN (1.0 / 128.0)
+
+layout(location = 0) in vec2 uv;
+
+layout(set = 0, binding = 0) uniform sampler2D u_previous;
+
+layout(push_constant) uniform UPostData {
+    vec2 texel_size;
+    vec4 params;
+} post;
+
+layout(location = 0) out vec4 f_color;
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+// Fast approximate anti-aliasing, which blurs along the edges it finds from differences in luma
+void main() {
+    vec2 texel = post.texel_size;
+    vec4 center = texture(u_previous, uv);
+
+    float luma_nw = luma(texture(u_previous, uv + vec2(-1.0, -1.0) * texel).rgb);
+    float luma_ne = luma(texture(u_previous, uv + vec2(1.0, -1.0) * texel).rgb);
+    float luma_sw = luma(texture(u_previous, uv + vec2(-1.0, 1.0) * texel).rgb);
+    float luma_se = luma(texture(u_previous, uv + vec2(1.0, 1.0) * texel).rgb);
+    float luma_m = luma(center.rgb);
+
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // The direction of the edge, perpendicular to the luma gradient
+    vec2 dir = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float dir_reduce = max(
+        (luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL,
+        FXAA_REDUCE_MIN
+    );
+    float rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2(-FXAA_SPAN_MAX), vec2(FXAA_SPAN_MAX)) * texel;
+
+    vec3 rgb_a = 0.5 * (
+        texture(u_previous, uv + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(u_previous, uv + dir * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 rgb_b = rgb_a * 0.5 + 0.25 * (
+        texture(u_previous, uv + dir * -0.5).rgb +
+        texture(u_previous, uv + dir * 0.5).rgb
+    );
+
+    // The wider sample is only used if it stays within the range of the neighborhood
+    float luma_b = luma(rgb_b);
+    vec3 color = (luma_b < luma_min || luma_b > luma_max) ? rgb_a : rgb_b;
+    f_color = vec4(color, center.a);
+}
@@ -0,0 +1,11 @@
+#version 450
+
+layout(location = 0) in vec2 position;
+
+layout(location = 0) out vec2 uv;
+
+// Covers the screen with a quad, mapping the top left corner to a UV of (0, 0)
+void main() {
+    uv = position * 0.5 + 0.5;
+    gl_Position = vec4(position, 0.0, 1.0);
+}
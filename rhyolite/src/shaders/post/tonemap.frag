@@ -0,0 +1,77 @@
+#version 450
+
+#define OPERATOR_EXPOSURE_ONLY 0
+#define OPERATOR_REINHARD 1
+#define OPERATOR_ACES 2
+
+#ifdef MULTISAMPLED
+layout(input_attachment_index = 0, set = 0, binding = 0) uniform subpassInputMS u_hdr;
+#else
+layout(input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput u_hdr;
+#endif
+
+layout(push_constant) uniform UTonemapData {
+    vec4 clear_color;
+    float exposure;
+    uint tonemap_operator;
+    uint encode_srgb;
+    uint sample_count;
+} tonemap;
+
+layout(location = 0) out vec4 f_color;
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+vec3 aces(vec3 x) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return (x * (a * x + b)) / (x * (c * x + d) + e);
+}
+
+vec3 linear_to_srgb(vec3 color) {
+    vec3 low = 12.92 * color;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(low, high, step(vec3(0.0031308), color));
+}
+
+// Tonemaps a single HDR color. The HDR target is cleared to transparent black, so its color is
+// premultiplied by how much of the pixel was drawn over, and the clear color fills in the rest.
+// The clear color is linear like the lights, so it's composited before the result is encoded.
+vec4 tonemap_color(vec4 hdr) {
+    vec3 color = hdr.rgb * tonemap.exposure;
+
+    if (tonemap.tonemap_operator == OPERATOR_REINHARD) {
+        color = color / (1.0 + color);
+    } else if (tonemap.tonemap_operator == OPERATOR_ACES) {
+        color = aces(color);
+    }
+    color = clamp(color, 0.0, 1.0);
+
+    float coverage = clamp(hdr.a, 0.0, 1.0);
+    color += tonemap.clear_color.rgb * (1.0 - coverage);
+    float alpha = coverage + tonemap.clear_color.a * (1.0 - coverage);
+    return vec4(color, alpha);
+}
+
+void main() {
+#ifdef MULTISAMPLED
+    // Samples are resolved after they're tonemapped, so that a very bright sample can't take
+    // over the edge it's on
+    vec4 resolved = vec4(0.0);
+    for (int i = 0; i < int(tonemap.sample_count); i++) {
+        resolved += tonemap_color(subpassLoad(u_hdr, i));
+    }
+    resolved /= float(tonemap.sample_count);
+#else
+    vec4 resolved = tonemap_color(subpassLoad(u_hdr));
+#endif
+    vec3 color = resolved.rgb;
+
+    // sRGB targets are encoded by the hardware when they're written to
+    if (tonemap.encode_srgb != 0) {
+        color = linear_to_srgb(color);
+    }
+    f_color = vec4(color, resolved.a);
+}
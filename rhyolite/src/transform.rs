@@ -20,6 +20,34 @@ impl Transform {
         }
     }
 
+    /// Creates a transform from a model matrix. The matrix is split into translation, rotation,
+    /// and scale, so that the model matrix of the transform is identical to `matrix`. If `matrix`
+    /// contains shear, it's stored in the rotation parameter.
+    pub fn from_matrix(matrix: &Mat4) -> Self {
+        let scale_vec = vec3(
+            matrix.column(0).xyz().magnitude(),
+            matrix.column(1).xyz().magnitude(),
+            matrix.column(2).xyz().magnitude(),
+        );
+
+        let mut rotation: Mat4 = identity();
+        for i in 0..3 {
+            let axis = if scale_vec[i] > f32::EPSILON {
+                matrix.column(i).xyz() / scale_vec[i]
+            } else {
+                Vec3::zeros()
+            };
+            rotation.fixed_view_mut::<3, 1>(0, i).copy_from(&axis);
+        }
+
+        Self {
+            cache: Cell::new(None),
+            translation: translate(&identity(), &matrix.column(3).xyz()),
+            rotation,
+            scale: scale(&identity(), &scale_vec),
+        }
+    }
+
     /// Uses a rotation matrix to set the rotation parameter of the transform.
     pub fn set_rotation_mat(&mut self, rotation: Mat4) {
         self.rotation = rotation;
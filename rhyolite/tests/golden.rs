@@ -0,0 +1,224 @@
+//! Golden-image regression tests for the mesh and marched pipelines.
+//!
+//! Each test renders a canonical scene offscreen and compares the result against a reference
+//! image stored in `tests/golden/`. These tests need a Vulkan implementation, but not a display,
+//! so they can run in CI with a software driver like lavapipe, which they always select so that
+//! the results don't depend on the GPU of the machine running them:
+//!
+//! ```txt
+//! cargo test -p rhyolite --features mesh,marched --test golden -- --ignored
+//! ```
+//!
+//! When a test fails, the rendered image and a diff image are written to the cargo target
+//! directory. To update the reference images after an intentional change, rerun the tests with
+//! `RHYOLITE_BLESS=1`.
+
+use std::path::PathBuf;
+
+use nalgebra_glm::vec3;
+use rhyolite::camera::Camera;
+use rhyolite::geometry::marched::Metaball;
+use rhyolite::geometry::mesh::MeshObjectBuilder;
+use rhyolite::lighting::{AmbientLight, PointLight};
+use rhyolite::renderer::capture::FrameCapture;
+use rhyolite::renderer::marched::MarchedRenderer;
+use rhyolite::renderer::mesh::{DrawInfo, MeshRenderer};
+use rhyolite::renderer::{DeviceSelector, RenderConfig, Renderer};
+use rhyolite::transform::Transform;
+use vulkano::device::physical::PhysicalDeviceType;
+use vulkano::image::SampleCount;
+
+const DIMENSIONS: [u32; 2] = [256, 256];
+
+/// The largest per-channel difference at which two pixels are still considered equal. Allows for
+/// small differences in floating-point precision between drivers.
+const CHANNEL_TOLERANCE: u8 = 3;
+/// The fraction of pixels that may differ by more than `CHANNEL_TOLERANCE` before a test fails.
+const MAX_MISMATCHED_FRACTION: f64 = 0.002;
+
+/// The configuration shared by every test, which renders on a CPU device.
+fn config() -> RenderConfig {
+    RenderConfig {
+        window_size: DIMENSIONS,
+        device: DeviceSelector::Type(PhysicalDeviceType::Cpu),
+        ..Default::default()
+    }
+}
+
+#[test]
+#[ignore = "requires a Vulkan implementation such as lavapipe"]
+fn mesh_teapot_two_point_lights() {
+    assert_matches_reference("mesh_teapot", &render_teapot(&config()));
+}
+
+#[test]
+#[ignore = "requires a Vulkan implementation such as lavapipe"]
+fn mesh_teapot_msaa() {
+    let config = RenderConfig {
+        samples: SampleCount::Sample4,
+        ..config()
+    };
+    assert_matches_reference("mesh_teapot_msaa", &render_teapot(&config));
+}
+
+/// Renders a teapot lit by two colored point lights with the mesh renderer
+fn render_teapot(config: &RenderConfig) -> FrameCapture {
+    let mut renderer = MeshRenderer::headless(config).unwrap();
+    let mut camera = Camera::new(Transform::identity(), 1.2, 0.02, 100.0);
+
+    let teapot = MeshObjectBuilder::from_file(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../examples/models/teapot.obj"),
+        &vec3(0.0, 1.0, -8.0),
+        &vec3(0.5, 0.5, 0.5),
+        &vec3(1.0, 1.0, 1.0),
+        (1.0, 128.0),
+    )
+    .unwrap()
+    .build(&renderer)
+    .unwrap();
+
+    let mut ambient_light = AmbientLight::new(vec3(1.0, 1.0, 1.0), 0.05);
+    let mut point_lights = vec![
+        PointLight::new(vec3(-4.0, -2.0, -4.0), vec3(1.0, 0.4, 0.2), 12.0),
+        PointLight::new(vec3(4.0, -3.0, -6.0), vec3(0.2, 0.5, 1.0), 12.0),
+    ];
+
+    renderer.start_render_pass(&mut camera).unwrap();
+    renderer.draw_lit_auto(DrawInfo::Vertex { object: &teapot }).unwrap();
+    renderer.draw_ambient_light(&mut ambient_light).unwrap();
+    for light in point_lights.iter_mut() {
+        renderer.draw_point_light(light).unwrap();
+    }
+    renderer.capture_next_frame().unwrap();
+    renderer.end_render_pass().unwrap();
+
+    renderer.take_captured_frame().unwrap()
+}
+
+#[test]
+#[ignore = "requires a Vulkan implementation such as lavapipe"]
+fn marched_metaball_grid() {
+    let mut renderer = MarchedRenderer::headless(&config()).unwrap();
+
+    let mut camera_transform = Transform::identity();
+    camera_transform.set_translation(&vec3(2.0, -1.0, -5.0));
+    let mut camera = Camera::new(camera_transform, 1.2, 0.02, 100.0);
+
+    let mut point_lights = vec![
+        PointLight::new(vec3(0.0, 10.0, -10.0), vec3(0.0, 0.2, 1.0), 60.0),
+        PointLight::new(vec3(0.0, -10.0, 10.0), vec3(1.0, 0.2, 0.0), 60.0),
+    ];
+    let mut ambient_light = AmbientLight::new(vec3(1.0, 1.0, 1.0), 0.1);
+    renderer.config_lighting(&mut point_lights, &mut vec![], &mut vec![], &mut ambient_light).unwrap();
+
+    // A central metaball surrounded by a 3x3 grid, as in the ray marching example
+    let mut metaballs = vec![Metaball::new(vec3(2.0, -1.0, 2.0), vec3(1.0, 1.0, 1.0), 0.6)];
+    for i in 0..9 {
+        metaballs.push(Metaball::new(
+            vec3((i / 3) as f32 * 2.0, 0.0, (i % 3) as f32 * 2.0),
+            vec3(1.0, 1.0, 1.0),
+            0.45,
+        ));
+    }
+
+    renderer.start(&mut camera).unwrap();
+    renderer.add_objects(&metaballs).unwrap();
+    renderer.capture_next_frame().unwrap();
+    renderer.finish().unwrap();
+
+    assert_matches_reference("marched_metaballs", &renderer.take_captured_frame().unwrap());
+}
+
+// ==============
+// HARNESS
+// ==============
+
+/// The result of comparing a rendered image to a reference image.
+struct Comparison {
+    mismatched_pixels: usize,
+    max_difference: u8,
+    diff: FrameCapture,
+}
+
+/// Compares two images of the same size pixel by pixel. The returned diff image shows pixels
+/// that differ by more than `CHANNEL_TOLERANCE` in red, scaled by the size of the difference,
+/// over a darkened grayscale copy of the reference.
+fn compare(actual: &FrameCapture, reference: &FrameCapture) -> Comparison {
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+    let mut diff = Vec::with_capacity(reference.data().len());
+
+    for (a, r) in actual.data().chunks_exact(4).zip(reference.data().chunks_exact(4)) {
+        let difference = (0..4).map(|i| a[i].abs_diff(r[i])).max().unwrap();
+        max_difference = max_difference.max(difference);
+
+        if difference > CHANNEL_TOLERANCE {
+            mismatched_pixels += 1;
+            diff.extend_from_slice(&[128 + difference / 2, 0, 0, 255]);
+        } else {
+            let luminance = ((r[0] as u32 + r[1] as u32 + r[2] as u32) / 12) as u8;
+            diff.extend_from_slice(&[luminance, luminance, luminance, 255]);
+        }
+    }
+
+    Comparison {
+        mismatched_pixels,
+        max_difference,
+        diff: FrameCapture::from_rgba8(reference.width(), reference.height(), diff),
+    }
+}
+
+/// Checks that `actual` matches the reference image called `name`, writing the rendered image
+/// and a diff image to the target directory if it doesn't.
+fn assert_matches_reference(name: &str, actual: &FrameCapture) {
+    let reference_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.png", name));
+
+    if std::env::var_os("RHYOLITE_BLESS").is_some() {
+        actual.save_png(&reference_path).unwrap();
+        return;
+    }
+
+    let reference = match image::open(&reference_path) {
+        Ok(image) => {
+            let image = image.to_rgba8();
+            FrameCapture::from_rgba8(image.width(), image.height(), image.into_raw())
+        }
+        Err(e) => panic!(
+            "Unable to load reference image {}: {}. Run with RHYOLITE_BLESS=1 to create it.",
+            reference_path.display(),
+            e
+        ),
+    };
+
+    assert_eq!(
+        actual.dimensions(),
+        reference.dimensions(),
+        "rendered image and reference image {} have different dimensions",
+        name
+    );
+
+    let comparison = compare(actual, &reference);
+    let allowed = (MAX_MISMATCHED_FRACTION * reference.data().len() as f64 / 4.0) as usize;
+
+    if comparison.mismatched_pixels > allowed {
+        let output_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+        std::fs::create_dir_all(&output_dir).unwrap();
+        let actual_path = output_dir.join(format!("{}.actual.png", name));
+        let diff_path = output_dir.join(format!("{}.diff.png", name));
+        actual.save_png(&actual_path).unwrap();
+        comparison.diff.save_png(&diff_path).unwrap();
+
+        panic!(
+            "{} differs from its reference image: {} pixels mismatched (max {} allowed), with a \
+            maximum channel difference of {}.\nRendered image: {}\nDiff image: {}",
+            name,
+            comparison.mismatched_pixels,
+            allowed,
+            comparison.max_difference,
+            actual_path.display(),
+            diff_path.display(),
+        );
+    }
+}
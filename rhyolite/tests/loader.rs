@@ -0,0 +1,276 @@
+use nalgebra_glm::Vec3;
+use rhyolite::geometry::mesh::loader::ModelBuilder;
+use rhyolite::geometry::mesh::{
+    LoadError, Material, MeshObjectBuilder, MtlMaterial, NormalGeneration, NormalWeighting,
+};
+
+const QUAD: &str = "
+# A unit quad with a single normal
+o Quad
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.0 1.0 0.0
+v 0.0 1.0 0.0
+
+vn 0.0 0.0 1.0
+f 1//1 2//1 3//1 4//1
+";
+
+#[test]
+fn quads_are_fan_triangulated() {
+    let model = ModelBuilder::from_reader(QUAD.as_bytes(), false).unwrap();
+    assert_eq!(model.name(), Some("Quad"));
+
+    let indices: Vec<[usize; 3]> = model.faces().iter().map(|f| f.vertex_indices).collect();
+    assert_eq!(indices, vec![[0, 1, 2], [0, 2, 3]]);
+    assert!(model.faces().iter().all(|f| f.normal_indices == Some([0, 0, 0])));
+    assert_eq!(model.build_basic().len(), 6);
+}
+
+#[test]
+fn negative_indices_are_relative() {
+    let data = "v 0 0 0\nv 1 0 0\nv 1 1 0\nf -3 -2 -1\nv 0 1 0\nf 1 3 -1\n";
+    let model = ModelBuilder::from_reader(data.as_bytes(), false).unwrap();
+
+    let indices: Vec<[usize; 3]> = model.faces().iter().map(|f| f.vertex_indices).collect();
+    assert_eq!(indices, vec![[0, 1, 2], [0, 2, 3]]);
+}
+
+#[test]
+fn winding_order_can_be_inverted() {
+    let model = ModelBuilder::from_reader("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 3".as_bytes(), true).unwrap();
+    assert_eq!(model.faces()[0].vertex_indices, [2, 1, 0]);
+}
+
+#[test]
+fn errors_report_line_numbers() {
+    let data = "v 0 0 0\n\nv 1 0 0\nf 1 2 5\n";
+    match ModelBuilder::from_reader(data.as_bytes(), false) {
+        Err(LoadError::Parse { line, .. }) => assert_eq!(line, 4),
+        other => panic!("expected a parse error, got {:?}", other.err()),
+    }
+
+    match ModelBuilder::from_reader("v 0 zero 0\n".as_bytes(), false) {
+        Err(LoadError::Parse { line, .. }) => assert_eq!(line, 1),
+        other => panic!("expected a parse error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn missing_files_are_io_errors() {
+    assert!(matches!(
+        ModelBuilder::from_file("does/not/exist.obj", false),
+        Err(LoadError::Io(_))
+    ));
+}
+
+#[test]
+fn indexed_builds_merge_identical_vertices() {
+    let model = ModelBuilder::from_reader(QUAD.as_bytes(), false).unwrap();
+    let (vertices, indices) = model.build_basic_indexed();
+    assert_eq!(vertices.len(), 4);
+    assert_eq!(indices, vec![0, 1, 2, 0, 2, 3]);
+}
+
+/// Two faces of a cube meeting at a right angle along the edge x = 1, z = 0
+const CORNER: &str = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 1 0 -1
+v 1 1 -1
+f 1 2 3 4
+f 2 5 6 3
+";
+
+fn corner_normals(mode: NormalGeneration, invert: bool) -> Vec<[f32; 3]> {
+    let mut model = ModelBuilder::from_reader(CORNER.as_bytes(), invert).unwrap();
+    assert!(!model.has_normals());
+    model.generate_normals(mode);
+    assert!(model.has_normals());
+    model.build_basic().iter().map(|v| v.normal).collect()
+}
+
+fn assert_close(a: [f32; 3], b: [f32; 3]) {
+    assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-5), "{a:?} != {b:?}");
+}
+
+#[test]
+fn flat_normals_follow_faces() {
+    let normals = corner_normals(NormalGeneration::Flat, false);
+    normals[..6].iter().for_each(|n| assert_close(*n, [0.0, 0.0, 1.0]));
+    normals[6..].iter().for_each(|n| assert_close(*n, [1.0, 0.0, 0.0]));
+
+    let inverted = corner_normals(NormalGeneration::Flat, true);
+    assert_close(inverted[0], [0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn smooth_normals_average_shared_positions() {
+    let normals = corner_normals(NormalGeneration::default(), false);
+    // The first vertex only belongs to the front face, and the second lies on the shared edge
+    assert_close(normals[0], [0.0, 0.0, 1.0]);
+    let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+    assert_close(normals[1], [diagonal, 0.0, diagonal]);
+}
+
+#[test]
+fn crease_angle_keeps_hard_edges() {
+    let mode = NormalGeneration::Smooth {
+        weighting: NormalWeighting::Area,
+        crease_angle: Some(std::f32::consts::FRAC_PI_4),
+    };
+    let normals = corner_normals(mode, false);
+    assert_close(normals[1], [0.0, 0.0, 1.0]);
+    assert_close(normals[6], [1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn texture_coordinates_are_flipped_vertically() {
+    let data = "v 0 0 0\nv 1 0 0\nv 1 1 0\nvt 0 0\nvt 1 0\nvt 1 0.25\nf 1/1 2/2 3/3\nf 1 2 3\n";
+    let model = ModelBuilder::from_reader(data.as_bytes(), false).unwrap();
+    assert!(!model.has_tex_coords());
+
+    let tex_coords: Vec<[f32; 2]> = model.build_textured().iter().map(|v| v.tex_coord).collect();
+    assert_eq!(tex_coords[..3], [[0.0, 1.0], [1.0, 1.0], [1.0, 0.75]]);
+    assert_eq!(tex_coords[3..], [[0.0, 0.0]; 3]);
+
+    let (vertices, indices) = model.build_textured_indexed();
+    assert_eq!(vertices.len(), 6);
+    assert_eq!(indices.len(), 6);
+}
+
+#[test]
+fn texture_coordinates_can_have_one_to_three_components() {
+    let data = "v 0 0 0\nv 1 0 0\nv 1 1 0\nvt 0.5\nvt 1 0.25\nvt 1 0.5 0.75\nf 1/1 2/2 3/3\n";
+    let model = ModelBuilder::from_reader(data.as_bytes(), false).unwrap();
+    assert!(model.has_tex_coords());
+
+    let tex_coords: Vec<[f32; 2]> = model.build_textured().iter().map(|v| v.tex_coord).collect();
+    assert_eq!(tex_coords, [[0.5, 1.0], [1.0, 0.75], [1.0, 0.5]]);
+
+    assert!(matches!(
+        ModelBuilder::from_reader("vt\n".as_bytes(), false),
+        Err(LoadError::Parse { line: 1, .. })
+    ));
+}
+
+const MATERIALS: &str = "
+newmtl Red
+Kd 1.0 0.0 0.0
+Ks 0.5 0.5 0.5
+Ns 32
+newmtl Textured
+Kd 0.8
+map_Kd -s 2 2 2 textures/brick.png
+";
+
+#[test]
+fn mtl_materials_are_parsed() {
+    let materials = MtlMaterial::from_reader(MATERIALS.as_bytes(), "models").unwrap();
+    assert_eq!(materials.len(), 2);
+
+    assert_eq!(materials[0].name, "Red");
+    assert_eq!(materials[0].diffuse, [1.0, 0.0, 0.0]);
+    assert_eq!(materials[0].specular_intensity(), 0.5);
+    assert_eq!(materials[0].shininess, 32.0);
+    assert_eq!(materials[0].diffuse_map, None);
+
+    assert_eq!(materials[1].diffuse, [0.8, 0.8, 0.8]);
+    assert_eq!(materials[1].diffuse_map.as_deref(), Some(std::path::Path::new("models/textures/brick.png")));
+
+    let error = MtlMaterial::from_reader("Kd 1 1 1".as_bytes(), "").unwrap_err();
+    assert!(matches!(error, LoadError::Parse { line: 1, .. }));
+}
+
+#[test]
+fn mtl_texture_paths_can_contain_spaces() {
+    let materials = MtlMaterial::from_reader(
+        "newmtl A\nmap_Kd -o 0.5 -mm 0 1 -clamp on my textures/old brick.png\n\
+         newmtl B\nmap_Kd -s 2 2 2 2 bricks.png\n"
+            .as_bytes(),
+        "models",
+    )
+    .unwrap();
+    assert_eq!(
+        materials[0].diffuse_map.as_deref(),
+        Some(std::path::Path::new("models/my textures/old brick.png"))
+    );
+    // At most three values follow `-s`, so the fourth number is the start of the file name
+    assert_eq!(materials[1].diffuse_map.as_deref(), Some(std::path::Path::new("models/2 bricks.png")));
+
+    for line in ["map_Kd -s 2 2 2", "map_Kd -mm 0 brick.png", "map_Kd -unknown brick.png"] {
+        let error = MtlMaterial::from_reader(format!("newmtl A\n{}", line).as_bytes(), "").unwrap_err();
+        assert!(matches!(error, LoadError::Parse { line: 2, .. }));
+    }
+}
+
+#[test]
+fn models_are_split_by_material() {
+    let directory = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("loader_materials");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("materials.mtl"), MATERIALS).unwrap();
+    std::fs::write(
+        directory.join("model.obj"),
+        "mtllib materials.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+         f 1 2 3\nusemtl Textured\nf 1 3 4\nusemtl Red\nf 1 2 4\nusemtl Missing\nf 2 3 4\n",
+    )
+    .unwrap();
+
+    let model = ModelBuilder::from_file(directory.join("model.obj"), false).unwrap();
+    assert_eq!(model.materials().len(), 2);
+
+    let submeshes = model.submeshes();
+    let names: Vec<Option<&str>> = submeshes.iter().map(|s| s.material_name()).collect();
+    assert_eq!(names, vec![Some("Textured"), Some("Red"), Some("Missing"), None]);
+    assert!(submeshes.iter().all(|s| s.faces().len() == 1));
+
+    // Submeshes only have a material if it could be found
+    let colors: Vec<Option<[f32; 3]>> = submeshes
+        .iter()
+        .map(|s| s.material().map(|material| material.diffuse))
+        .collect();
+    assert_eq!(colors, vec![Some([0.8; 3]), Some([1.0, 0.0, 0.0]), None, None]);
+}
+
+#[test]
+fn missing_material_libraries_use_default_materials() {
+    let directory = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("loader_missing_mtl");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(
+        directory.join("model.obj"),
+        "mtllib missing.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nusemtl Red\nf 1 2 3\n",
+    )
+    .unwrap();
+
+    let model = ModelBuilder::from_file(directory.join("model.obj"), false).unwrap();
+    assert!(model.materials().is_empty());
+    assert_eq!(model.submeshes()[0].material_name(), Some("Red"));
+    assert!(model.submeshes()[0].material().is_none());
+
+    let builders = MeshObjectBuilder::from_file_with_materials(
+        directory.join("model.obj"),
+        &Vec3::zeros(),
+        &Vec3::new(1.0, 1.0, 1.0),
+    )
+    .unwrap();
+    assert_eq!(builders.len(), 1);
+    let (material, default) = (builders[0].material(), Material::default());
+    assert_eq!(material.albedo(), default.albedo());
+    assert_eq!(material.specular_intensity(), default.specular_intensity());
+    assert_eq!(material.shininess(), default.shininess());
+}
+
+#[test]
+fn malformed_material_libraries_are_errors() {
+    let directory = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("loader_malformed_mtl");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("broken.mtl"), "Kd 1 1 1\n").unwrap();
+    std::fs::write(directory.join("model.obj"), "mtllib broken.mtl\nv 0 0 0\n").unwrap();
+
+    assert!(matches!(
+        ModelBuilder::from_file(directory.join("model.obj"), false),
+        Err(LoadError::MaterialLibrary { path, .. }) if path.ends_with("broken.mtl")
+    ));
+}